@@ -0,0 +1,38 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tarsius_core::{CoreError, LatexSource, Result};
+
+pub const TEX_FILE_NAME: &str = "main.tex";
+pub const BIB_FILE_NAME: &str = "references.bib";
+/// Where compiling `TEX_FILE_NAME` puts the PDF. Tarsius does not run LaTeX
+/// itself; a PDF found here is taken into releases.
+pub const PDF_FILE_NAME: &str = "main.pdf";
+
+/// Files written by a project build.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildOutput {
+    pub tex: PathBuf,
+    pub bib: Option<PathBuf>,
+    /// Linked scratches that were skipped because they no longer exist.
+    pub missing_scratches: Vec<String>,
+}
+
+pub(crate) fn write_sources(source: LatexSource, output_dir: &Path) -> Result<BuildOutput> {
+    fs::create_dir_all(output_dir).map_err(|e| CoreError::io(output_dir, e))?;
+    let tex = output_dir.join(TEX_FILE_NAME);
+    fs::write(&tex, &source.tex).map_err(|e| CoreError::io(&tex, e))?;
+    let bib = match &source.bib {
+        Some(content) => {
+            let bib = output_dir.join(BIB_FILE_NAME);
+            fs::write(&bib, content).map_err(|e| CoreError::io(&bib, e))?;
+            Some(bib)
+        }
+        None => None,
+    };
+    Ok(BuildOutput {
+        tex,
+        bib,
+        missing_scratches: source.missing_scratches,
+    })
+}
@@ -0,0 +1,100 @@
+use serde::Serialize;
+use std::sync::Arc;
+use tarsius_core::{
+    CacheMetrics, CacheSettings, CachingRepository, EntityKind, Project, Refreshed, Scratch,
+    Template, WorkspaceEvent,
+};
+use tarsius_storage::{
+    FilesystemProjectRepository, FilesystemScratchRepository, FilesystemTemplateRepository,
+    Workspace, WorkspaceChange,
+};
+
+pub(crate) type ScratchCache = CachingRepository<FilesystemScratchRepository, Scratch>;
+pub(crate) type ProjectCache = CachingRepository<FilesystemProjectRepository, Project>;
+pub(crate) type TemplateCache = CachingRepository<FilesystemTemplateRepository, Template>;
+
+/// Hit and miss counters of the repository caches.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheReport {
+    pub scratches: CacheMetrics,
+    pub projects: CacheMetrics,
+    pub templates: CacheMetrics,
+}
+
+/// One cache per repository, shared by every manager that reads it.
+#[derive(Clone)]
+pub(crate) struct Caches {
+    pub scratches: Arc<ScratchCache>,
+    pub projects: Arc<ProjectCache>,
+    pub templates: Arc<TemplateCache>,
+}
+
+impl Caches {
+    pub fn new(workspace: &Arc<Workspace>, settings: &CacheSettings) -> Self {
+        // Scratches make up most of a workspace; templates are few and small.
+        let budget = settings.memory_budget();
+        Self {
+            scratches: Arc::new(CachingRepository::new(
+                FilesystemScratchRepository::new(workspace.clone()),
+                budget / 2,
+            )),
+            projects: Arc::new(CachingRepository::new(
+                FilesystemProjectRepository::new(workspace.clone()),
+                budget * 3 / 8,
+            )),
+            templates: Arc::new(CachingRepository::new(
+                FilesystemTemplateRepository::new(workspace.clone()),
+                budget / 8,
+            )),
+        }
+    }
+
+    /// Re-reads the changed files and returns the events announcing the
+    /// ones edited outside the app. Writes made through the caches were
+    /// announced by whoever made them.
+    pub fn refresh(&self, changes: &[WorkspaceChange]) -> Vec<WorkspaceEvent> {
+        let mut events = Vec::new();
+        for change in changes {
+            let id = change.id.clone();
+            let event = match change.kind {
+                EntityKind::Scratch => match self.scratches.refresh(&change.id) {
+                    Refreshed::Unchanged => None,
+                    Refreshed::Changed(scratch) => Some(WorkspaceEvent::ScratchUpdated {
+                        id,
+                        revision: scratch.modified_at,
+                    }),
+                    Refreshed::Removed => Some(WorkspaceEvent::ScratchDeleted { id }),
+                },
+                EntityKind::Project => match self.projects.refresh(&change.id) {
+                    Refreshed::Unchanged => None,
+                    Refreshed::Changed(project) => Some(WorkspaceEvent::ProjectSaved {
+                        id,
+                        revision: project.modified_at,
+                    }),
+                    Refreshed::Removed => Some(WorkspaceEvent::ProjectDeleted { id }),
+                },
+                EntityKind::Template => match self.templates.refresh(&change.id) {
+                    Refreshed::Unchanged => None,
+                    _ => Some(WorkspaceEvent::TemplateChanged { id }),
+                },
+                _ => None,
+            };
+            events.extend(event);
+        }
+        events
+    }
+
+    pub fn invalidate_all(&self) {
+        self.scratches.invalidate_all();
+        self.projects.invalidate_all();
+        self.templates.invalidate_all();
+    }
+
+    pub fn report(&self) -> CacheReport {
+        CacheReport {
+            scratches: self.scratches.metrics(),
+            projects: self.projects.metrics(),
+            templates: self.templates.metrics(),
+        }
+    }
+}
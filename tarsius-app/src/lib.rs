@@ -0,0 +1,1690 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use tarsius_core::*;
+use tarsius_storage::{
+    BackupInfo, BackupManifest, CollisionPolicy, FilesystemBlueprintRepository,
+    FilesystemHistoryRepository, FilesystemJournalRepository, FilesystemReleaseRepository,
+    FilesystemTrashRepository, Finding, ImportReport, LockInfo, Problem, Workspace, WorkspaceIssue,
+    WorkspaceLock, WorkspaceWatcher,
+};
+
+mod build;
+mod caches;
+
+pub use build::*;
+pub use caches::CacheReport;
+
+use caches::Caches;
+
+/// Which scratches `App::list_scratches` keeps, by whether a project outline
+/// links to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageFilter {
+    Used,
+    Unused,
+}
+
+impl std::str::FromStr for UsageFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "used" => Ok(UsageFilter::Used),
+            "unused" => Ok(UsageFilter::Unused),
+            other => Err(format!("Unknown usage filter: {}", other)),
+        }
+    }
+}
+
+/// A project together with the outline links whose scratch no longer exists.
+#[derive(Debug, Clone)]
+pub struct LoadedProject {
+    pub project: Project,
+    pub warnings: Vec<DanglingLink>,
+}
+
+/// A journaled edit that is newer than the saved item.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingRecovery {
+    pub kind: JournalKind,
+    pub id: String,
+    /// Title from the draft, for listing.
+    pub title: String,
+    pub recorded_at: chrono::DateTime<Utc>,
+    pub saved_at: chrono::DateTime<Utc>,
+    pub draft: Draft,
+}
+
+/// Whether this instance may change the workspace.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceStatus {
+    pub read_only: bool,
+    /// The instance holding the workspace lock, when this one is read-only.
+    pub locked_by: Option<LockInfo>,
+}
+
+/// Everything a frontend can do with a workspace.
+///
+/// The Tauri commands and the CLI are thin adapters over this type; it keeps
+/// derived state such as the backlink index consistent across operations and
+/// publishes a `WorkspaceEvent` for every change.
+pub struct App {
+    workspace: Arc<Workspace>,
+    settings: WorkspaceSettings,
+    scratches: ScratchManager,
+    projects: Arc<ProjectManager>,
+    trash: TrashManager,
+    templates: Box<dyn TemplateRepository>,
+    journal: Box<dyn JournalRepository>,
+    // When the newest journaled edit of each item was made. Journal writes
+    // can arrive out of order, and an older one must not replace a newer
+    // draft. Also held while a draft is discarded.
+    journaled: Mutex<HashMap<(JournalKind, String), chrono::DateTime<Utc>>>,
+    releases: Box<dyn ReleaseRepository>,
+    caches: Caches,
+    // Runs the work behind `App::spawn`.
+    pool: BlockingPool,
+    events: Arc<EventBus>,
+    windows: Mutex<WindowRegistry>,
+    // Set once the main window closes: the windows closing after it are part
+    // of quitting and stay in the saved layout.
+    quitting: AtomicBool,
+    // Refreshes the caches when files change on disk; stops when dropped.
+    _watcher: WorkspaceWatcher,
+    // `None` when opened read-only.
+    lock: Option<WorkspaceLock>,
+}
+
+impl App {
+    /// Opens the workspace at `path`, creating its directories if needed.
+    ///
+    /// Fails with `CoreError::Locked` if another instance has the workspace
+    /// open; `open_read_only` can still be used then.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let workspace = Arc::new(Workspace::new(path));
+        workspace.ensure_dirs()?;
+        let lock = workspace.lock()?;
+        workspace.remove_stale_temp_files()?;
+        Self::assemble(workspace, Some(lock))
+    }
+
+    /// Opens the workspace without taking its lock. Every operation that
+    /// would change the workspace fails with `CoreError::Locked`.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::assemble(Arc::new(Workspace::new(path).read_only()), None)
+    }
+
+    fn assemble(workspace: Arc<Workspace>, lock: Option<WorkspaceLock>) -> Result<Self> {
+        let settings = workspace.load_settings()?;
+        let caches = Caches::new(&workspace, &settings.cache);
+        let events = Arc::new(EventBus::default());
+
+        let scratches = ScratchManager::new(Box::new(caches.scratches.clone()))
+            .with_trash(Box::new(FilesystemTrashRepository::new(workspace.clone())))
+            .with_projects(Box::new(caches.projects.clone()))
+            .with_events(events.clone())
+            .with_history(
+                Box::new(FilesystemHistoryRepository::new(workspace.clone())),
+                settings.history.clone(),
+            );
+        let projects = Arc::new(
+            ProjectManager::new(Box::new(caches.projects.clone()))
+                .with_trash(Box::new(FilesystemTrashRepository::new(workspace.clone())))
+                .with_scratches(Box::new(caches.scratches.clone()))
+                .with_templates(Box::new(caches.templates.clone()))
+                .with_blueprints(Box::new(FilesystemBlueprintRepository::new(
+                    workspace.clone(),
+                )))
+                .with_events(events.clone()),
+        );
+        let trash = TrashManager::new(
+            Box::new(FilesystemTrashRepository::new(workspace.clone())),
+            Box::new(caches.projects.clone()),
+            settings.trash_retention(),
+        );
+        let watcher = {
+            let caches = caches.clone();
+            let events = events.clone();
+            let projects = projects.clone();
+            WorkspaceWatcher::start(
+                workspace.clone(),
+                settings.cache.watch_interval(),
+                move |changes| {
+                    if changes.iter().any(|c| c.kind == EntityKind::Project) {
+                        projects.invalidate_backlinks();
+                    }
+                    for event in caches.refresh(&changes) {
+                        events.publish(event);
+                    }
+                },
+            )
+        };
+
+        // A damaged layout only costs the window positions.
+        let layout = workspace.load_window_layout().unwrap_or_default();
+
+        Ok(Self {
+            templates: Box::new(caches.templates.clone()),
+            journal: Box::new(FilesystemJournalRepository::new(workspace.clone())),
+            journaled: Mutex::new(HashMap::new()),
+            releases: Box::new(FilesystemReleaseRepository::new(workspace.clone())),
+            workspace,
+            settings,
+            scratches,
+            projects,
+            trash,
+            caches,
+            pool: BlockingPool::new(blocking_threads()),
+            events,
+            windows: Mutex::new(WindowRegistry::from_layout(layout)),
+            quitting: AtomicBool::new(false),
+            _watcher: watcher,
+            lock,
+        })
+    }
+
+    pub fn status(&self) -> WorkspaceStatus {
+        WorkspaceStatus {
+            read_only: self.lock.is_none(),
+            locked_by: match self.lock {
+                Some(_) => None,
+                None => self.workspace.lock_holder(),
+            },
+        }
+    }
+
+    fn writable(&self) -> Result<()> {
+        match self.lock {
+            Some(_) => Ok(()),
+            None => Err(CoreError::Locked(
+                "the workspace is open read-only".to_string(),
+            )),
+        }
+    }
+
+    /// Hit and miss counters of the repository caches.
+    pub fn cache_metrics(&self) -> CacheReport {
+        self.caches.report()
+    }
+
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
+    }
+
+    pub fn settings(&self) -> &WorkspaceSettings {
+        &self.settings
+    }
+
+    /// Receives every event published after this call.
+    pub fn subscribe(&self) -> Receiver<WorkspaceEvent> {
+        self.events.subscribe()
+    }
+
+    /// Runs `f` on a background thread and returns a future for its result,
+    /// so async frontends never wait on disk I/O or builds themselves.
+    /// Several calls run concurrently.
+    pub fn spawn<T, F>(self: &Arc<Self>, f: F) -> BlockingTask<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&App) -> Result<T> + Send + 'static,
+    {
+        let app = self.clone();
+        self.pool.spawn(move || f(&app))
+    }
+
+    // Scratches
+
+    pub fn create_scratch(
+        &self,
+        title: String,
+        content: String,
+        tags: Vec<String>,
+        source: Option<String>,
+    ) -> Result<Scratch> {
+        self.writable()?;
+        self.scratches.create(title, content, tags, source)
+    }
+
+    pub fn update_scratch(
+        &self,
+        id: String,
+        title: Option<String>,
+        content: Option<String>,
+        tags: Option<Vec<String>>,
+        source: Option<Option<String>>,
+    ) -> Result<Scratch> {
+        self.writable()?;
+        let scratch = self.scratches.update(id, title, content, tags, source)?;
+        self.discard_draft(JournalKind::Scratch, &scratch.id);
+        Ok(scratch)
+    }
+
+    /// Adds and removes tags, keeping the order of the tags that stay.
+    pub fn tag_scratch(&self, id: &str, add: &[String], remove: &[String]) -> Result<Scratch> {
+        let remove = normalize_tags(remove);
+        let mut tags = self.scratches.load(id)?.tags;
+        tags.retain(|tag| !remove.contains(&normalize_tag(tag)));
+        for tag in add {
+            // Tags may not repeat, so adding one already there is a no-op.
+            if !tags.iter().any(|t| normalize_tag(t) == normalize_tag(tag)) {
+                tags.push(tag.clone());
+            }
+        }
+        self.update_scratch(id.to_string(), None, None, Some(tags), None)
+    }
+
+    pub fn load_scratch(&self, id: &str) -> Result<Scratch> {
+        self.scratches.load(id)
+    }
+
+    pub fn list_scratches(&self, usage: Option<UsageFilter>) -> Result<Vec<Scratch>> {
+        let mut scratches = self.scratches.list()?;
+        if let Some(usage) = usage {
+            let used = self.projects.used_scratch_ids()?;
+            scratches.retain(|scratch| used.contains(&scratch.id) == (usage == UsageFilter::Used));
+        }
+        Ok(scratches)
+    }
+
+    /// One page of scratches, without their content.
+    pub fn scratch_summaries(
+        &self,
+        usage: Option<UsageFilter>,
+        query: &ListQuery,
+    ) -> Result<Page<ScratchSummary>> {
+        page_scratches(&self.list_scratches(usage)?, query)
+    }
+
+    pub fn delete_scratch(&self, id: &str, policy: DeletePolicy) -> Result<DeleteReport> {
+        self.writable()?;
+        let report = self.scratches.delete(id, policy)?;
+        // The trash moved the file without going through the cache.
+        self.caches.scratches.refresh(id);
+        self.discard_draft(JournalKind::Scratch, id);
+        // Unlinking rewrote project files behind the project manager's back.
+        self.projects.invalidate_backlinks();
+        self.events.publish(WorkspaceEvent::TrashChanged);
+        Ok(report)
+    }
+
+    /// The saved versions of a scratch, oldest first.
+    pub fn scratch_versions(&self, id: &str) -> Result<Vec<ScratchVersion>> {
+        self.scratches.versions(id)
+    }
+
+    pub fn diff_scratch_versions(&self, id: &str, from: u32, to: u32) -> Result<VersionDiff> {
+        self.scratches.diff_versions(id, from, to)
+    }
+
+    /// Saves an earlier version of a scratch as its current state.
+    pub fn restore_scratch_version(&self, id: &str, version: u32) -> Result<Scratch> {
+        self.writable()?;
+        let scratch = self.scratches.restore_version(id, version)?;
+        self.discard_draft(JournalKind::Scratch, &scratch.id);
+        Ok(scratch)
+    }
+
+    /// Same as `scratch_usages`; kept for callers of the older command.
+    pub fn scratch_references(&self, id: &str) -> Result<Vec<ScratchReference>> {
+        self.scratch_usages(id)
+    }
+
+    pub fn scratch_usages(&self, id: &str) -> Result<Vec<ScratchReference>> {
+        self.projects.scratch_usages(id)
+    }
+
+    // Projects
+
+    pub fn create_project(
+        &self,
+        title: String,
+        template_id: String,
+        output_dir: String,
+    ) -> Result<Project> {
+        self.writable()?;
+        self.projects.create(title, template_id, output_dir)
+    }
+
+    pub fn load_project(&self, id: &str) -> Result<LoadedProject> {
+        let (project, warnings) = self.projects.load_checked(id)?;
+        Ok(LoadedProject { project, warnings })
+    }
+
+    pub fn save_project(&self, project: &Project) -> Result<()> {
+        self.writable()?;
+        self.projects.save(project)?;
+        self.discard_draft(JournalKind::Project, &project.id);
+        Ok(())
+    }
+
+    /// Lists projects, leaving out archived ones unless `include_archived`.
+    pub fn list_projects(&self, include_archived: bool) -> Result<Vec<Project>> {
+        let mut projects = self.projects.list()?;
+        projects.retain(|p| include_archived || !p.archived);
+        Ok(projects)
+    }
+
+    /// One page of projects, without their outlines.
+    pub fn project_summaries(&self, query: &ListQuery) -> Result<Page<ProjectSummary>> {
+        page_projects(&self.projects.list()?, query)
+    }
+
+    /// Copies a project with fresh outline node ids. With `copy_included`,
+    /// every scratch the project includes is copied too and the copy's
+    /// `Include` links point at the new scratches, so editing one project
+    /// leaves the other alone.
+    pub fn duplicate_project(
+        &self,
+        id: &str,
+        title: String,
+        copy_included: bool,
+    ) -> Result<Project> {
+        self.writable()?;
+        let mut copies = HashMap::new();
+        let result = self
+            .copy_included_scratches(id, copy_included, &mut copies)
+            .and_then(|()| self.projects.duplicate(id, title, &copies));
+        if result.is_err() {
+            // Nothing links to the copies yet; leave no trace of them.
+            for copy in copies.values() {
+                self.discard_scratch(copy);
+            }
+        }
+        result
+    }
+
+    fn copy_included_scratches(
+        &self,
+        id: &str,
+        copy_included: bool,
+        copies: &mut HashMap<String, String>,
+    ) -> Result<()> {
+        if !copy_included {
+            return Ok(());
+        }
+        let project = self.projects.load(id)?;
+        for reference in all_references(&project) {
+            if reference.mode != IntegrationMode::Include
+                || copies.contains_key(&reference.scratch_id)
+            {
+                continue;
+            }
+            let scratch = match self.scratches.load(&reference.scratch_id) {
+                Ok(scratch) => scratch,
+                // The included copy still holds the text.
+                Err(CoreError::NotFound { .. }) => continue,
+                Err(e) => return Err(e),
+            };
+            let copy = self.scratches.create(
+                scratch.title,
+                scratch.content,
+                scratch.tags,
+                scratch.source,
+            )?;
+            copies.insert(reference.scratch_id, copy.id);
+        }
+        Ok(())
+    }
+
+    /// Deletes an unreferenced scratch for good, best effort.
+    fn discard_scratch(&self, id: &str) {
+        if self.scratches.delete(id, DeletePolicy::Block).is_ok() {
+            let _ = self.trash.purge(TrashKind::Scratch, id);
+        }
+        let _ = self.scratches.forget_history(id);
+        self.caches.scratches.refresh(id);
+    }
+
+    pub fn rename_project(&self, id: &str, title: String) -> Result<Project> {
+        self.writable()?;
+        self.projects.rename(id, title)
+    }
+
+    /// Archived projects are left out of `list_projects` and
+    /// `project_summaries` unless asked for, but are still searched.
+    pub fn set_project_archived(&self, id: &str, archived: bool) -> Result<Project> {
+        self.writable()?;
+        self.projects.set_archived(id, archived)
+    }
+
+    /// Moves the project to the trash.
+    pub fn delete_project(&self, id: &str) -> Result<()> {
+        self.writable()?;
+        self.projects.delete(id)?;
+        self.caches.projects.refresh(id);
+        self.discard_draft(JournalKind::Project, id);
+        self.events.publish(WorkspaceEvent::TrashChanged);
+        Ok(())
+    }
+
+    pub fn project_drift(&self, id: &str) -> Result<Vec<DriftedLink>> {
+        let project = self.projects.load(id)?;
+        Ok(self.projects.drifted_links(&project))
+    }
+
+    pub fn drift_view(
+        &self,
+        project_id: &str,
+        node_id: &str,
+        scratch_id: &str,
+    ) -> Result<DriftView> {
+        self.projects.drift_view(project_id, node_id, scratch_id)
+    }
+
+    pub fn resolve_drift(
+        &self,
+        project_id: &str,
+        node_id: &str,
+        scratch_id: &str,
+        resolution: DriftResolution,
+    ) -> Result<Project> {
+        self.writable()?;
+        self.projects
+            .resolve_drift(project_id, node_id, scratch_id, resolution)
+    }
+
+    /// Writes the project's LaTeX sources to `output_dir`, or to the
+    /// project's configured output directory. Relative paths are resolved
+    /// against the workspace; the configured directory must stay inside it.
+    pub fn build_project(&self, id: &str, output_dir: Option<&Path>) -> Result<BuildOutput> {
+        self.writable()?;
+        let project = self.projects.load(id)?;
+        let scratches = self.scratches.list()?;
+        let template = self.project_template(&project)?;
+        let source = LatexSourceBuilder::new(template.as_ref(), &scratches).build(&project);
+
+        let output_dir = match output_dir {
+            Some(dir) => self.workspace.base_path().join(dir),
+            None => self
+                .workspace
+                .contain(Path::new(&project.settings.output_dir))?,
+        };
+        let output = write_sources(source, &output_dir)?;
+        self.workspace.record_output(&output_dir)?;
+        self.events.publish(WorkspaceEvent::BuildFinished {
+            project_id: project.id,
+            tex: output.tex.clone(),
+            missing_scratches: output.missing_scratches.clone(),
+        });
+        Ok(output)
+    }
+
+    // Projects whose template file does not exist are built with the default
+    // preamble; one that exists but cannot be read fails the build.
+    fn project_template(&self, project: &Project) -> Result<Option<Template>> {
+        let id = &project.settings.template_id;
+        match self.templates.load(id) {
+            Ok(template) => Ok(Some(template)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(CoreError::Build(format!(
+                "template {} could not be read: {}",
+                id, e
+            ))),
+        }
+    }
+
+    /// The PDF last compiled into the project's output directory, if any.
+    pub fn project_pdf(&self, id: &str) -> Result<Option<Vec<u8>>> {
+        let project = self.projects.load(id)?;
+        self.read_pdf(&project)
+    }
+
+    fn read_pdf(&self, project: &Project) -> Result<Option<Vec<u8>>> {
+        let output_dir = self
+            .workspace
+            .contain(Path::new(&project.settings.output_dir))?;
+        let pdf_path = output_dir.join(PDF_FILE_NAME);
+        match std::fs::read(&pdf_path) {
+            Ok(pdf) => Ok(Some(pdf)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(CoreError::io(&pdf_path, e)),
+        }
+    }
+
+    fn check_pdf_is_current(&self, project: &Project, tex: &str) -> Result<()> {
+        let output_dir = self
+            .workspace
+            .contain(Path::new(&project.settings.output_dir))?;
+        let stale = |reason: &str| {
+            CoreError::Conflict(format!(
+                "{} in {} {}; build the project and compile it again",
+                PDF_FILE_NAME, project.settings.output_dir, reason
+            ))
+        };
+        let tex_path = output_dir.join(TEX_FILE_NAME);
+        match std::fs::read_to_string(&tex_path) {
+            Ok(built) if built == tex => {}
+            Ok(_) => return Err(stale("was built from an earlier version of the project")),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(stale("has no sources next to it"))
+            }
+            Err(e) => return Err(CoreError::io(&tex_path, e)),
+        }
+        let modified = |path: &Path| {
+            std::fs::metadata(path)
+                .and_then(|m| m.modified())
+                .map_err(|e| CoreError::io(path, e))
+        };
+        if modified(&output_dir.join(PDF_FILE_NAME))? <= modified(&tex_path)? {
+            return Err(stale("is older than its sources"));
+        }
+        Ok(())
+    }
+
+    // Releases
+
+    /// Freezes the project as it is now: its outline, the scratches it uses,
+    /// its template, the LaTeX built from them and the PDF last compiled
+    /// into the output directory, if any.
+    ///
+    /// A PDF is only taken if it was compiled from the project as it is now:
+    /// the sources in the output directory must match the LaTeX frozen here
+    /// and the PDF must be newer than them. Otherwise the release is refused
+    /// until the project is built and compiled again.
+    pub fn create_release(&self, project_id: &str, name: String) -> Result<Release> {
+        self.writable()?;
+        if name.trim().is_empty() {
+            return Err(CoreError::invalid("name", "must not be empty"));
+        }
+        let project = self.projects.load(project_id)?;
+        let scratches = self.scratches.list()?;
+        let template = self.project_template(&project)?;
+        let source = LatexSourceBuilder::new(template.as_ref(), &scratches).build(&project);
+        let pdf = self.read_pdf(&project)?;
+        if pdf.is_some() {
+            self.check_pdf_is_current(&project, &source.tex)?;
+        }
+
+        let release = Release::snapshot(&project, &scratches, template, name.trim().to_string());
+        let output = ReleaseOutput {
+            tex: source.tex,
+            bib: source.bib,
+            pdf,
+        };
+        self.releases.create(&release, &output)?;
+        self.events.publish(WorkspaceEvent::ReleaseCreated {
+            project_id: release.project_id.clone(),
+            release_id: release.id.clone(),
+        });
+        Ok(release)
+    }
+
+    /// The releases of a project, oldest first.
+    pub fn list_releases(&self, project_id: &str) -> Result<Vec<ReleaseSummary>> {
+        self.projects.load(project_id)?;
+        Ok(self
+            .releases
+            .list(project_id)?
+            .iter()
+            .map(ReleaseSummary::from)
+            .collect())
+    }
+
+    pub fn load_release(&self, project_id: &str, release_id: &str) -> Result<Release> {
+        self.releases.load(project_id, release_id)
+    }
+
+    /// How the project changed from release `from` to release `to`.
+    pub fn diff_releases(&self, project_id: &str, from: &str, to: &str) -> Result<ReleaseDiff> {
+        let (from, from_output) = (
+            self.releases.load(project_id, from)?,
+            self.releases.output(project_id, from)?,
+        );
+        let (to, to_output) = (
+            self.releases.load(project_id, to)?,
+            self.releases.output(project_id, to)?,
+        );
+        Ok(ReleaseDiff::between(&from, &from_output, &to, &to_output))
+    }
+
+    /// Starts a new project from the outline and settings of a release.
+    /// `Include` links keep the text they had; `Link` links point to the
+    /// scratches as they are now.
+    pub fn fork_release(
+        &self,
+        project_id: &str,
+        release_id: &str,
+        title: String,
+    ) -> Result<Project> {
+        self.writable()?;
+        let release = self.releases.load(project_id, release_id)?;
+        self.projects
+            .create_with_outline(title, release.project.outline, release.project.settings)
+    }
+
+    /// How the project's outline changed since a release.
+    pub fn outline_changes(
+        &self,
+        project_id: &str,
+        release_id: &str,
+    ) -> Result<Vec<OutlineChange>> {
+        let release = self.releases.load(project_id, release_id)?;
+        let project = self.projects.load(project_id)?;
+        Ok(diff_outlines(&release.project.outline, &project.outline))
+    }
+
+    /// Merges the outline of `other_id` into that of `project_id` without
+    /// saving it. Release `base_release_id` of `project_id` is the version
+    /// both started from, such as the release `other_id` was forked from.
+    pub fn preview_merge(
+        &self,
+        project_id: &str,
+        other_id: &str,
+        base_release_id: &str,
+    ) -> Result<OutlineMerge> {
+        let base = self.releases.load(project_id, base_release_id)?;
+        let ours = self.projects.load(project_id)?;
+        let theirs = self.projects.load(other_id)?;
+        merge_outlines(&base.project.outline, &ours.outline, &theirs.outline)
+    }
+
+    /// Like `preview_merge`, but saves the merged outline into `project_id`.
+    /// Conflicting nodes keep this project's side; the conflicts are returned
+    /// for review.
+    pub fn merge_projects(
+        &self,
+        project_id: &str,
+        other_id: &str,
+        base_release_id: &str,
+    ) -> Result<OutlineMerge> {
+        self.writable()?;
+        let merge = self.preview_merge(project_id, other_id, base_release_id)?;
+        let mut project = self.projects.load(project_id)?;
+        project.outline = merge.outline.clone();
+        self.projects.save(&project)?;
+        self.discard_draft(JournalKind::Project, project_id);
+        Ok(merge)
+    }
+
+    // Templates
+
+    pub fn list_templates(&self) -> Result<Vec<Template>> {
+        self.templates.list()
+    }
+
+    pub fn load_template(&self, id: &str) -> Result<Template> {
+        self.templates.load(id)
+    }
+
+    // Blueprints
+
+    /// The built-in blueprints, then the ones saved in this workspace.
+    pub fn list_blueprints(&self) -> Result<Vec<Blueprint>> {
+        self.projects.blueprints()
+    }
+
+    pub fn create_project_from_blueprint(
+        &self,
+        blueprint_id: &str,
+        title: String,
+    ) -> Result<Project> {
+        self.writable()?;
+        self.projects.create_from_blueprint(blueprint_id, title)
+    }
+
+    pub fn save_project_as_blueprint(
+        &self,
+        project_id: &str,
+        name: String,
+        description: String,
+    ) -> Result<Blueprint> {
+        self.writable()?;
+        self.projects
+            .save_as_blueprint(project_id, name, description)
+    }
+
+    pub fn delete_blueprint(&self, id: &str) -> Result<()> {
+        self.writable()?;
+        self.projects.delete_blueprint(id)
+    }
+
+    // Search
+
+    pub fn search(&self, query: &str) -> Result<Vec<SearchHit>> {
+        let scratches = self.scratches.list()?;
+        let projects = self.projects.list()?;
+        Ok(search(query, &scratches, &projects))
+    }
+
+    // Trash
+
+    pub fn list_trash(&self) -> Result<Vec<TrashEntry>> {
+        self.trash.list()
+    }
+
+    pub fn restore_from_trash(&self, kind: TrashKind, id: &str) -> Result<TrashEntry> {
+        self.writable()?;
+        let entry = self.trash.restore(kind, id)?;
+        match kind {
+            TrashKind::Scratch => {
+                self.caches.scratches.refresh(id);
+            }
+            TrashKind::Project => {
+                self.caches.projects.refresh(id);
+            }
+        }
+        self.projects.invalidate_backlinks();
+        // Restoring went around the managers, so announce what came back.
+        match kind {
+            TrashKind::Scratch => {
+                let mut project_ids: Vec<&str> = entry
+                    .removed_links
+                    .iter()
+                    .map(|link| link.project_id.as_str())
+                    .collect();
+                project_ids.sort();
+                project_ids.dedup();
+                for project in project_ids.iter().flat_map(|id| self.projects.load(id)) {
+                    self.events.publish(WorkspaceEvent::ProjectSaved {
+                        id: project.id,
+                        revision: project.modified_at,
+                    });
+                }
+                if let Ok(scratch) = self.scratches.load(id) {
+                    self.events.publish(WorkspaceEvent::ScratchCreated {
+                        id: scratch.id,
+                        revision: scratch.modified_at,
+                    });
+                }
+            }
+            TrashKind::Project => {
+                if let Ok(project) = self.projects.load(id) {
+                    self.events.publish(WorkspaceEvent::ProjectCreated {
+                        id: project.id,
+                        revision: project.modified_at,
+                    });
+                }
+            }
+        }
+        self.events.publish(WorkspaceEvent::TrashChanged);
+        Ok(entry)
+    }
+
+    pub fn purge_from_trash(&self, kind: TrashKind, id: &str) -> Result<()> {
+        self.writable()?;
+        self.trash.purge(kind, id)?;
+        if kind == TrashKind::Scratch {
+            self.scratches.forget_history(id)?;
+        }
+        self.events.publish(WorkspaceEvent::TrashChanged);
+        Ok(())
+    }
+
+    /// Removes trash entries older than the configured retention.
+    pub fn purge_expired_trash(&self) -> Result<Vec<TrashEntry>> {
+        self.writable()?;
+        let purged = self.trash.purge_expired(Utc::now())?;
+        for entry in purged.iter().filter(|e| e.kind == TrashKind::Scratch) {
+            self.scratches.forget_history(&entry.id)?;
+        }
+        if !purged.is_empty() {
+            self.events.publish(WorkspaceEvent::TrashChanged);
+        }
+        Ok(purged)
+    }
+
+    // Windows
+
+    /// The saved windows to reopen at startup, other than the main window.
+    /// Windows whose scratch or project is gone are dropped.
+    pub fn restore_windows(&self) -> Vec<WindowState> {
+        let mut windows = self.windows.lock().unwrap();
+        windows.retain_targets(|kind, id| match kind {
+            WindowKind::Scratch => self.scratches.load(id).is_ok(),
+            _ => self.projects.load(id).is_ok(),
+        });
+        // The main window always exists; this records it if it is new.
+        let _ = windows.open(WindowKind::Main, None);
+        windows
+            .layout()
+            .windows
+            .into_iter()
+            .filter(|w| w.kind != WindowKind::Main)
+            .collect()
+    }
+
+    /// Registers a window showing `target_id`, or returns the one already
+    /// showing it. The flag tells whether the frontend must create it.
+    pub fn open_window(
+        &self,
+        kind: WindowKind,
+        target_id: Option<String>,
+    ) -> Result<(WindowState, bool)> {
+        match (kind, &target_id) {
+            (WindowKind::Scratch, Some(id)) => {
+                self.scratches.load(id)?;
+            }
+            (_, Some(id)) => {
+                self.projects.load(id)?;
+            }
+            (_, None) => {}
+        }
+        let (window, new) = self.windows.lock().unwrap().open(kind, target_id)?;
+        if new {
+            self.save_window_layout()?;
+            self.events.publish(WorkspaceEvent::WindowOpened {
+                label: window.label.clone(),
+                kind: window.kind,
+                target_id: window.target_id.clone(),
+            });
+        }
+        Ok((window, new))
+    }
+
+    /// Forgets a window the user closed. Closing the main window quits, so
+    /// the layout is kept as it is then.
+    pub fn close_window(&self, label: &str) -> Result<()> {
+        if label == MAIN_WINDOW {
+            self.save_window_layout()?;
+            self.quitting.store(true, Ordering::SeqCst);
+            return Ok(());
+        }
+        if self.quitting.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        if self.windows.lock().unwrap().close(label) {
+            self.save_window_layout()?;
+            self.events.publish(WorkspaceEvent::WindowClosed {
+                label: label.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Records where a window is. It is saved with the next change to the
+    /// set of windows, or on quit, as moves come in quick succession.
+    pub fn move_window(&self, label: &str, bounds: WindowBounds) {
+        if !self.quitting.load(Ordering::SeqCst) {
+            self.windows.lock().unwrap().set_bounds(label, bounds);
+        }
+    }
+
+    pub fn window(&self, label: &str) -> Option<WindowState> {
+        self.windows.lock().unwrap().get(label).cloned()
+    }
+
+    pub fn windows(&self) -> Vec<WindowState> {
+        self.windows.lock().unwrap().layout().windows
+    }
+
+    /// Read-only instances leave the layout to the instance holding the lock.
+    fn save_window_layout(&self) -> Result<()> {
+        if self.lock.is_none() {
+            return Ok(());
+        }
+        let layout = self.windows.lock().unwrap().layout();
+        self.workspace.save_window_layout(&layout)
+    }
+
+    // Quarantine
+
+    /// Files that could not be read and were moved to the quarantine.
+    pub fn workspace_issues(&self) -> Result<Vec<WorkspaceIssue>> {
+        self.workspace.workspace_issues()
+    }
+
+    /// Moves a repaired file back into the workspace.
+    pub fn restore_quarantined(&self, issue_id: &str) -> Result<WorkspaceIssue> {
+        self.writable()?;
+        let issue = self.workspace.restore_quarantined(issue_id)?;
+        self.caches.invalidate_all();
+        self.projects.invalidate_backlinks();
+        self.events.publish(WorkspaceEvent::QuarantineRestored {
+            issue_id: issue_id.to_string(),
+        });
+        Ok(issue)
+    }
+
+    // Integrity
+
+    /// Audits the workspace files without changing them.
+    pub fn check_workspace(&self) -> Result<Vec<Finding>> {
+        self.workspace.check()
+    }
+
+    /// Applies the automatic fixes for those of `problems` that a fresh
+    /// check still finds, in order, and returns what is still wrong
+    /// afterwards. Fixes that delete files are only applied if `confirmed`.
+    pub fn repair_workspace(&self, problems: &[Problem], confirmed: bool) -> Result<Vec<Finding>> {
+        self.writable()?;
+        self.workspace.repair(problems, confirmed)?;
+        self.caches.invalidate_all();
+        self.projects.invalidate_backlinks();
+        self.events.publish(WorkspaceEvent::WorkspaceRepaired);
+        self.workspace.check()
+    }
+
+    // Journal
+
+    /// Records the editor state of a scratch, as it was at `edited_at`, ahead
+    /// of its next save. A draft older than the one already journaled is
+    /// dropped.
+    pub fn record_scratch_draft(
+        &self,
+        id: &str,
+        draft: ScratchDraft,
+        edited_at: chrono::DateTime<Utc>,
+    ) -> Result<()> {
+        self.record_draft(JournalEntry {
+            id: id.to_string(),
+            recorded_at: edited_at,
+            draft: Draft::Scratch(draft),
+        })
+    }
+
+    /// Records an edited project ahead of its next save.
+    pub fn record_project_draft(&self, project: ProjectDto) -> Result<()> {
+        self.record_draft(JournalEntry {
+            id: project.id.clone(),
+            recorded_at: Utc::now(),
+            draft: Draft::Project(project),
+        })
+    }
+
+    fn record_draft(&self, entry: JournalEntry) -> Result<()> {
+        self.writable()?;
+        let mut journaled = self.journaled.lock().unwrap();
+        let key = (entry.kind(), entry.id.clone());
+        if journaled
+            .get(&key)
+            .is_some_and(|newest| *newest > entry.recorded_at)
+        {
+            return Ok(());
+        }
+        self.journal.record(&entry)?;
+        journaled.insert(key, entry.recorded_at);
+        Ok(())
+    }
+
+    /// Journaled edits that were never saved, oldest first. Entries that are
+    /// older than the saved item, or whose item is gone, are dropped.
+    pub fn pending_recoveries(&self) -> Result<Vec<PendingRecovery>> {
+        let mut pending = Vec::new();
+        for entry in self.journal.list()? {
+            let kind = entry.kind();
+            let saved = match &entry.draft {
+                Draft::Scratch(_) => self.scratches.load(&entry.id).map(|s| s.modified_at),
+                Draft::Project(_) => self.projects.load(&entry.id).map(|p| p.modified_at),
+            };
+            let saved_at = match saved {
+                Ok(saved_at) if saved_at < entry.recorded_at => saved_at,
+                Ok(_) | Err(CoreError::NotFound { .. }) => {
+                    if self.lock.is_some() {
+                        self.journal.discard(kind, &entry.id)?;
+                    }
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let title = match &entry.draft {
+                Draft::Scratch(draft) => draft.title.clone(),
+                Draft::Project(project) => project.title.clone(),
+            };
+            pending.push(PendingRecovery {
+                kind,
+                id: entry.id,
+                title,
+                recorded_at: entry.recorded_at,
+                saved_at,
+                draft: entry.draft,
+            });
+        }
+        Ok(pending)
+    }
+
+    /// Saves the journaled draft over the stored item.
+    pub fn recover_draft(&self, kind: JournalKind, id: &str) -> Result<()> {
+        let entry = self
+            .journal
+            .list()?
+            .into_iter()
+            .find(|e| e.kind() == kind && e.id == id)
+            .ok_or_else(|| CoreError::not_found(EntityKind::JournalEntry, id))?;
+        match entry.draft {
+            Draft::Scratch(draft) => {
+                self.update_scratch(
+                    entry.id,
+                    Some(draft.title),
+                    Some(draft.content),
+                    Some(draft.tags),
+                    Some(draft.source),
+                )?;
+            }
+            Draft::Project(dto) => {
+                let mut project: Project = dto.try_into()?;
+                project.modified_at = Utc::now();
+                self.save_project(&project)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops a journaled draft without applying it.
+    pub fn discard_recovery(&self, kind: JournalKind, id: &str) -> Result<()> {
+        self.writable()?;
+        let _journaled = self.journaled.lock().unwrap();
+        self.journal.discard(kind, id)
+    }
+
+    // Failing to discard is harmless after a successful save: the draft is
+    // older than the saved item and `pending_recoveries` drops it.
+    fn discard_draft(&self, kind: JournalKind, id: &str) {
+        let _journaled = self.journaled.lock().unwrap();
+        let _ = self.journal.discard(kind, id);
+    }
+
+    // Backups
+
+    pub fn export_workspace(&self, dest: &Path) -> Result<BackupManifest> {
+        tarsius_storage::export_workspace(&self.workspace, dest)
+    }
+
+    pub fn import_workspace(
+        &self,
+        archive: &Path,
+        policy: CollisionPolicy,
+    ) -> Result<ImportReport> {
+        self.writable()?;
+        let report = tarsius_storage::import_workspace(&self.workspace, archive, policy)?;
+        self.caches.invalidate_all();
+        self.projects.invalidate_backlinks();
+        self.events.publish(WorkspaceEvent::WorkspaceImported);
+        Ok(report)
+    }
+
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>> {
+        tarsius_storage::list_backups(&self.workspace, &self.settings.backup)
+    }
+
+    /// Runs the automatic backup if one is due. Read-only instances leave
+    /// backups to the instance holding the lock.
+    pub fn run_scheduled_backup(&self) -> Result<Option<PathBuf>> {
+        if self.lock.is_none() {
+            return Ok(None);
+        }
+        tarsius_storage::run_scheduled_backup(&self.workspace, &self.settings.backup, Utc::now())
+    }
+}
+
+/// One worker per core, within limits: the work is mostly disk-bound.
+fn blocking_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(2)
+        .clamp(2, 8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn link(scratch_id: &str) -> ScratchLink {
+        ScratchLink {
+            scratch_id: scratch_id.to_string(),
+            mode: IntegrationMode::Link,
+            insertion: InsertionFlags {
+                body: true,
+                footnote: false,
+                reference: false,
+                appendix: false,
+            },
+            included: None,
+        }
+    }
+
+    #[test]
+    fn test_scratch_lifecycle_publishes_events() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+        let events = app.subscribe();
+
+        let scratch = app
+            .create_scratch("Note".to_string(), "Body".to_string(), vec![], None)
+            .unwrap();
+        let tagged = app
+            .tag_scratch(&scratch.id, &["a".to_string(), "b".to_string()], &[])
+            .unwrap();
+        assert_eq!(tagged.tags, vec!["a", "b"]);
+        let untagged = app
+            .tag_scratch(&scratch.id, &["a".to_string()], &["b".to_string()])
+            .unwrap();
+        assert_eq!(untagged.tags, vec!["a"]);
+
+        assert_eq!(app.search("body").unwrap().len(), 1);
+        app.delete_scratch(&scratch.id, DeletePolicy::Block)
+            .unwrap();
+        assert!(app.list_scratches(None).unwrap().is_empty());
+        assert_eq!(app.list_trash().unwrap().len(), 1);
+
+        let received: Vec<WorkspaceEvent> = events.try_iter().collect();
+        assert_eq!(
+            received,
+            vec![
+                WorkspaceEvent::ScratchCreated {
+                    id: scratch.id.clone(),
+                    revision: scratch.modified_at,
+                },
+                WorkspaceEvent::ScratchUpdated {
+                    id: scratch.id.clone(),
+                    revision: tagged.modified_at,
+                },
+                WorkspaceEvent::ScratchUpdated {
+                    id: scratch.id.clone(),
+                    revision: untagged.modified_at,
+                },
+                WorkspaceEvent::ScratchDeleted {
+                    id: scratch.id.clone()
+                },
+                WorkspaceEvent::TrashChanged,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_usage_filter_and_build() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+        let used = app
+            .create_scratch("Used".to_string(), "Linked text".to_string(), vec![], None)
+            .unwrap();
+        let unused = app
+            .create_scratch("Unused".to_string(), "Loose".to_string(), vec![], None)
+            .unwrap();
+        let mut project = app
+            .create_project("Paper".to_string(), "none".to_string(), "out".to_string())
+            .unwrap();
+        project.outline.scratches.push(link(&used.id));
+        app.save_project(&project).unwrap();
+
+        let ids = |filter| -> Vec<String> {
+            app.list_scratches(Some(filter))
+                .unwrap()
+                .into_iter()
+                .map(|s| s.id)
+                .collect()
+        };
+        assert_eq!(ids(UsageFilter::Used), vec![used.id.clone()]);
+        assert_eq!(ids(UsageFilter::Unused), vec![unused.id.clone()]);
+
+        let output = app.build_project(&project.id, None).unwrap();
+        assert_eq!(output.tex, dir.path().join("out").join(TEX_FILE_NAME));
+        let tex = std::fs::read_to_string(&output.tex).unwrap();
+        assert!(tex.contains("Linked text"));
+        assert!(output.bib.is_none());
+
+        // A missing template falls back to the default; a broken one fails.
+        std::fs::write(dir.path().join("templates").join("none.json"), "{").unwrap();
+        let err = app.build_project(&project.id, None).unwrap_err();
+        assert!(matches!(err, CoreError::Build(_)), "{}", err);
+
+        // Unlinking on delete must show up in the usage index right away.
+        app.delete_scratch(&used.id, DeletePolicy::Unlink).unwrap();
+        assert!(app.scratch_usages(&used.id).unwrap().is_empty());
+        assert!(app.load_project(&project.id).unwrap().warnings.is_empty());
+    }
+
+    #[test]
+    fn test_releases_freeze_the_project() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+        let scratch = app
+            .create_scratch(
+                "Method".to_string(),
+                "As submitted".to_string(),
+                vec![],
+                None,
+            )
+            .unwrap();
+        let mut project = app
+            .create_project("Paper".to_string(), "none".to_string(), "out".to_string())
+            .unwrap();
+        project.outline.scratches.push(link(&scratch.id));
+        app.save_project(&project).unwrap();
+        // Stands in for running LaTeX on the built sources.
+        let compile = || {
+            let output = app.build_project(&project.id, None).unwrap();
+            let built = std::fs::metadata(&output.tex).unwrap().modified().unwrap();
+            let pdf = std::fs::File::create(dir.path().join("out").join(PDF_FILE_NAME)).unwrap();
+            pdf.set_modified(built + std::time::Duration::from_secs(1))
+                .unwrap();
+        };
+        compile();
+
+        let submitted = app
+            .create_release(&project.id, "Submitted".to_string())
+            .unwrap();
+        assert_eq!(submitted.scratches[0].content, "As submitted");
+        assert!(app.create_release(&project.id, " ".to_string()).is_err());
+
+        app.update_scratch(
+            scratch.id.clone(),
+            None,
+            Some("As revised".to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+        // The PDF was compiled before the revision.
+        assert!(matches!(
+            app.create_release(&project.id, "Revised".to_string()),
+            Err(CoreError::Conflict(_))
+        ));
+        compile();
+        let revised = app
+            .create_release(&project.id, "Revised".to_string())
+            .unwrap();
+        let releases = app.list_releases(&project.id).unwrap();
+        assert_eq!(
+            releases.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(),
+            vec!["Submitted", "Revised"]
+        );
+        // The earlier release still holds what was submitted.
+        let frozen = app.load_release(&project.id, &submitted.id).unwrap();
+        assert_eq!(frozen.scratches[0].content, "As submitted");
+        assert!(dir
+            .path()
+            .join("projects")
+            .join(&project.id)
+            .join("releases")
+            .join(&submitted.id)
+            .join(PDF_FILE_NAME)
+            .exists());
+
+        let diff = app
+            .diff_releases(&project.id, &submitted.id, &revised.id)
+            .unwrap();
+        assert_eq!(diff.scratches.len(), 1);
+        assert_eq!(diff.scratches[0].kind, ChangeKind::Changed);
+        assert!(diff.tex.iter().any(|s| s.op == DiffOp::Insert));
+
+        let fork = app
+            .fork_release(&project.id, &submitted.id, "Paper, take two".to_string())
+            .unwrap();
+        assert_ne!(fork.id, project.id);
+        assert_eq!(fork.outline.scratches[0].scratch_id, scratch.id);
+        assert!(app.list_releases(&fork.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fork_is_merged_back() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+        let mut project = app
+            .create_project("Paper".to_string(), "none".to_string(), "out".to_string())
+            .unwrap();
+        project.outline.children.push(OutlineNode {
+            id: "method".to_string(),
+            title: "Method".to_string(),
+            content: Some("First try".to_string()),
+            children: vec![],
+            scratches: vec![],
+        });
+        app.save_project(&project).unwrap();
+        let base = app
+            .create_release(&project.id, "Submitted".to_string())
+            .unwrap();
+        let mut fork = app
+            .fork_release(&project.id, &base.id, "Paper, revised".to_string())
+            .unwrap();
+
+        project.outline.children[0].title = "Methods".to_string();
+        app.save_project(&project).unwrap();
+        fork.outline.children[0].content = Some("Second try".to_string());
+        fork.outline.children.push(OutlineNode {
+            id: "results".to_string(),
+            title: "Results".to_string(),
+            content: None,
+            children: vec![],
+            scratches: vec![],
+        });
+        app.save_project(&fork).unwrap();
+        assert_eq!(app.outline_changes(&project.id, &base.id).unwrap().len(), 1);
+
+        let preview = app.preview_merge(&project.id, &fork.id, &base.id).unwrap();
+        assert!(preview.conflicts.is_empty());
+        assert_eq!(
+            app.load_project(&project.id)
+                .unwrap()
+                .project
+                .outline
+                .children
+                .len(),
+            1
+        );
+
+        app.merge_projects(&project.id, &fork.id, &base.id).unwrap();
+        let merged = app.load_project(&project.id).unwrap().project.outline;
+        assert_eq!(merged.children.len(), 2);
+        assert_eq!(merged.children[0].title, "Methods");
+        assert_eq!(merged.children[0].content.as_deref(), Some("Second try"));
+    }
+
+    #[test]
+    fn test_duplicate_copies_included_scratches() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+        let scratch = app
+            .create_scratch("Method".to_string(), "Shared".to_string(), vec![], None)
+            .unwrap();
+        let mut project = app
+            .create_project("Paper".to_string(), "none".to_string(), "out".to_string())
+            .unwrap();
+        project.outline.scratches.push(ScratchLink {
+            mode: IntegrationMode::Include,
+            ..link(&scratch.id)
+        });
+        app.save_project(&project).unwrap();
+
+        let shallow = app
+            .duplicate_project(&project.id, "Shallow".to_string(), false)
+            .unwrap();
+        assert_eq!(shallow.outline.scratches[0].scratch_id, scratch.id);
+        let deep = app
+            .duplicate_project(&project.id, "Deep".to_string(), true)
+            .unwrap();
+        let copy_id = &deep.outline.scratches[0].scratch_id;
+        assert_ne!(copy_id, &scratch.id);
+        assert_eq!(app.load_scratch(copy_id).unwrap().content, "Shared");
+        assert_eq!(app.list_scratches(None).unwrap().len(), 2);
+
+        // A copy that fails leaves no copied scratches behind.
+        assert!(app
+            .duplicate_project(&project.id, " ".to_string(), true)
+            .is_err());
+        assert_eq!(app.list_scratches(None).unwrap().len(), 2);
+        assert!(app.list_trash().unwrap().is_empty());
+
+        app.rename_project(&deep.id, "Deep copy".to_string())
+            .unwrap();
+        app.set_project_archived(&deep.id, true).unwrap();
+        let titles = |include_archived| -> Vec<String> {
+            let mut titles: Vec<String> = app
+                .list_projects(include_archived)
+                .unwrap()
+                .into_iter()
+                .map(|p| p.title)
+                .collect();
+            titles.sort();
+            titles
+        };
+        assert_eq!(titles(false), vec!["Paper", "Shallow"]);
+        assert_eq!(titles(true), vec!["Deep copy", "Paper", "Shallow"]);
+        assert_eq!(
+            app.project_summaries(&ListQuery::default()).unwrap().total,
+            2
+        );
+        assert!(app
+            .search("Deep copy")
+            .unwrap()
+            .iter()
+            .any(|hit| hit.id == deep.id));
+
+        app.delete_project(&shallow.id).unwrap();
+        assert_eq!(app.list_trash().unwrap()[0].id, shallow.id);
+        assert_eq!(titles(true), vec!["Deep copy", "Paper"]);
+    }
+
+    #[test]
+    fn test_projects_start_from_blueprints() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+        let thesis = app
+            .create_project_from_blueprint("thesis", "My thesis".to_string())
+            .unwrap();
+        // The blueprint brought its template along, and it typesets chapters.
+        assert_eq!(app.load_template("thesis").unwrap().name, "Thesis");
+        let output = app.build_project(&thesis.id, None).unwrap();
+        let tex = std::fs::read_to_string(output.tex).unwrap();
+        assert!(tex.contains("\\chapter{Introduction}"));
+        assert!(tex.contains("\\mainmatter"));
+
+        let saved = app
+            .save_project_as_blueprint(&thesis.id, "Department thesis".to_string(), String::new())
+            .unwrap();
+        assert!(dir
+            .path()
+            .join("blueprints")
+            .join(format!("{}.json", saved.id))
+            .exists());
+        assert_eq!(app.list_blueprints().unwrap().len(), 6);
+        let next = app
+            .create_project_from_blueprint(&saved.id, "Next thesis".to_string())
+            .unwrap();
+        assert_eq!(next.settings.template_id, "thesis");
+        app.delete_blueprint(&saved.id).unwrap();
+        assert_eq!(app.list_blueprints().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_unsaved_drafts_are_recovered() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+        let scratch = app
+            .create_scratch("Note".to_string(), "Saved".to_string(), vec![], None)
+            .unwrap();
+        let draft = |content: &str| ScratchDraft {
+            title: "Note".to_string(),
+            content: content.to_string(),
+            tags: vec!["draft".to_string()],
+            source: None,
+        };
+
+        // A draft that was saved afterwards is not offered.
+        app.record_scratch_draft(&scratch.id, draft("Saved later"), Utc::now())
+            .unwrap();
+        app.update_scratch(scratch.id.clone(), None, None, None, None)
+            .unwrap();
+        assert!(app.pending_recoveries().unwrap().is_empty());
+
+        let typed_at = Utc::now();
+        app.record_scratch_draft(&scratch.id, draft("Typed before the crash"), typed_at)
+            .unwrap();
+        // A journal write that was overtaken does not replace the newer draft.
+        app.record_scratch_draft(
+            &scratch.id,
+            draft("Typed earlier"),
+            typed_at - chrono::Duration::seconds(1),
+        )
+        .unwrap();
+        drop(app);
+
+        let app = App::open(dir.path()).unwrap();
+        let pending = app.pending_recoveries().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].kind, JournalKind::Scratch);
+        assert_eq!(pending[0].title, "Note");
+        app.recover_draft(JournalKind::Scratch, &scratch.id)
+            .unwrap();
+        let recovered = app.load_scratch(&scratch.id).unwrap();
+        assert_eq!(recovered.content, "Typed before the crash");
+        assert_eq!(recovered.tags, vec!["draft"]);
+        assert!(app.pending_recoveries().unwrap().is_empty());
+
+        let project = app
+            .create_project("Paper".to_string(), "none".to_string(), "out".to_string())
+            .unwrap();
+        let mut dto = ProjectDto::from(project.clone());
+        dto.title = "Paper, revised".to_string();
+        app.record_project_draft(dto).unwrap();
+        app.discard_recovery(JournalKind::Project, &project.id)
+            .unwrap();
+        assert!(app.pending_recoveries().unwrap().is_empty());
+        assert_eq!(
+            app.load_project(&project.id).unwrap().project.title,
+            "Paper"
+        );
+    }
+
+    #[test]
+    fn test_scratch_history_is_diffed_and_restored() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+        let scratch = app
+            .create_scratch("Note".to_string(), "First draft".to_string(), vec![], None)
+            .unwrap();
+        app.update_scratch(
+            scratch.id.clone(),
+            None,
+            Some("Second draft".to_string()),
+            Some(vec!["a".to_string()]),
+            None,
+        )
+        .unwrap();
+        // Saving without changes records nothing, and saving again right away
+        // replaces the last version.
+        app.update_scratch(scratch.id.clone(), None, None, None, None)
+            .unwrap();
+        app.update_scratch(
+            scratch.id.clone(),
+            None,
+            Some("Second draft, edited".to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let versions = app.scratch_versions(&scratch.id).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[1].content, "Second draft, edited");
+        assert_eq!(
+            versions[1].changed,
+            vec![ScratchField::Content, ScratchField::Tags]
+        );
+        let diff = app.diff_scratch_versions(&scratch.id, 1, 2).unwrap();
+        assert_eq!(diff.tags_added, vec!["a"]);
+        assert!(diff.content.contains(&DiffSpan {
+            op: DiffOp::Insert,
+            text: "Second".to_string()
+        }));
+
+        let restored = app.restore_scratch_version(&scratch.id, 1).unwrap();
+        assert_eq!(restored.content, "First draft");
+        assert!(restored.tags.is_empty());
+        assert_eq!(app.scratch_versions(&scratch.id).unwrap().len(), 3);
+        assert!(matches!(
+            app.restore_scratch_version(&scratch.id, 9),
+            Err(CoreError::NotFound {
+                kind: EntityKind::ScratchVersion,
+                ..
+            })
+        ));
+
+        // The history goes once the scratch is gone for good.
+        app.delete_scratch(&scratch.id, DeletePolicy::Block)
+            .unwrap();
+        app.purge_from_trash(TrashKind::Scratch, &scratch.id)
+            .unwrap();
+        assert!(!dir.path().join("history").join(&scratch.id).exists());
+    }
+
+    #[test]
+    fn test_second_instance_is_read_only() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+        let scratch = app
+            .create_scratch("Note".to_string(), "Body".to_string(), vec![], None)
+            .unwrap();
+        let project = app
+            .create_project("Paper".to_string(), "none".to_string(), "out".to_string())
+            .unwrap();
+        assert!(!app.status().read_only);
+
+        assert!(matches!(App::open(dir.path()), Err(CoreError::Locked(_))));
+        let viewer = App::open_read_only(dir.path()).unwrap();
+        let status = viewer.status();
+        assert!(status.read_only);
+        assert_eq!(status.locked_by.unwrap().pid, std::process::id());
+        assert_eq!(viewer.load_scratch(&scratch.id).unwrap().content, "Body");
+        assert!(matches!(
+            viewer.create_scratch("Other".to_string(), String::new(), vec![], None),
+            Err(CoreError::Locked(_))
+        ));
+        assert!(viewer.run_scheduled_backup().unwrap().is_none());
+        assert!(matches!(
+            viewer.build_project(&project.id, None),
+            Err(CoreError::Locked(_))
+        ));
+        assert!(!dir.path().join("out").exists());
+
+        drop(app);
+        assert!(App::open(dir.path()).is_ok());
+    }
+
+    #[test]
+    fn test_spawned_work_runs_concurrently() {
+        let dir = TempDir::new().unwrap();
+        let app = Arc::new(App::open(dir.path()).unwrap());
+
+        let created: Vec<_> = (0..4)
+            .map(|i| {
+                app.spawn(move |app| {
+                    app.create_scratch(format!("Note {}", i), String::new(), vec![], None)
+                })
+            })
+            .collect();
+        for task in created {
+            task.wait().unwrap();
+        }
+        let listed = app.spawn(|app| app.list_scratches(None)).wait().unwrap();
+        assert_eq!(listed.len(), 4);
+        assert!(app
+            .spawn(|app| app.load_scratch("missing"))
+            .wait()
+            .unwrap_err()
+            .is_not_found());
+    }
+
+    #[test]
+    fn test_window_layout_is_restored() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+        assert!(app.restore_windows().is_empty());
+        let project = app
+            .create_project("Paper".to_string(), "none".to_string(), "out".to_string())
+            .unwrap();
+        let scratch = app
+            .create_scratch("Note".to_string(), String::new(), vec![], None)
+            .unwrap();
+
+        let (preview, new) = app
+            .open_window(WindowKind::Preview, Some(project.id.clone()))
+            .unwrap();
+        assert!(new);
+        assert!(
+            !app.open_window(WindowKind::Preview, Some(project.id.clone()))
+                .unwrap()
+                .1
+        );
+        let (note, _) = app
+            .open_window(WindowKind::Scratch, Some(scratch.id.clone()))
+            .unwrap();
+        assert!(app
+            .open_window(WindowKind::Scratch, Some("missing".to_string()))
+            .unwrap_err()
+            .is_not_found());
+        let bounds = WindowBounds {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+        };
+        app.move_window(&preview.label, bounds);
+        app.close_window(&note.label).unwrap();
+        // Quitting keeps the windows still open.
+        app.close_window(MAIN_WINDOW).unwrap();
+        app.close_window(&preview.label).unwrap();
+        drop(app);
+
+        let app = App::open(dir.path()).unwrap();
+        let restored = app.restore_windows();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].label, preview.label);
+        assert_eq!(restored[0].bounds, Some(bounds));
+    }
+}
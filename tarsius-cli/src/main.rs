@@ -0,0 +1,937 @@
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use tarsius_app::App;
+use tarsius_core::{
+    CoreError, DeletePolicy, DiffOp, DiffSpan, OutlineChange, OutlineNode, Scratch,
+};
+
+type CliResult<T> = std::result::Result<T, String>;
+
+/// Headless access to a Tarsius workspace.
+#[derive(Parser)]
+#[command(name = "tarsius", version)]
+struct Cli {
+    /// Workspace directory.
+    #[arg(
+        long,
+        short,
+        global = true,
+        env = "TARSIUS_WORKSPACE",
+        default_value = "workspace"
+    )]
+    workspace: PathBuf,
+    /// Print results as JSON.
+    #[arg(long, global = true)]
+    json: bool,
+    /// Open the workspace without locking it, e.g. while the app has it
+    /// open. Commands that change the workspace fail.
+    #[arg(long, global = true)]
+    read_only: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage scratches.
+    #[command(subcommand)]
+    Scratch(ScratchCommand),
+    /// Inspect and build projects.
+    #[command(subcommand)]
+    Project(ProjectCommand),
+    /// Inspect templates.
+    #[command(subcommand)]
+    Template(TemplateCommand),
+    /// Start projects from blueprints and save projects as blueprints.
+    #[command(subcommand)]
+    Blueprint(BlueprintCommand),
+    /// Search scratches and project outlines.
+    Search { query: String },
+    /// Write the whole workspace to a backup archive.
+    Export { path: PathBuf },
+    /// Check the workspace files for problems.
+    Fsck {
+        /// Apply the automatic fixes. Fixes that delete files are asked
+        /// about first, and skipped when there is no terminal to ask on.
+        #[arg(long)]
+        repair: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScratchCommand {
+    List {
+        /// Only list scratches carrying this tag.
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    Show {
+        id: String,
+    },
+    /// Create a scratch; the content is read from stdin unless given.
+    New {
+        #[arg(long)]
+        title: String,
+        #[arg(long)]
+        content: Option<String>,
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        #[arg(long)]
+        source: Option<String>,
+    },
+    /// Update a scratch; pass `--stdin` to replace the content from stdin.
+    Edit {
+        id: String,
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(long, conflicts_with = "stdin")]
+        content: Option<String>,
+        #[arg(long)]
+        stdin: bool,
+        #[arg(long)]
+        source: Option<String>,
+    },
+    Rm {
+        id: String,
+        /// What to do with links to the scratch: Block, Unlink or Inline.
+        #[arg(long, default_value = "Block")]
+        policy: DeletePolicy,
+    },
+    Tag {
+        id: String,
+        #[arg(long)]
+        add: Vec<String>,
+        #[arg(long)]
+        remove: Vec<String>,
+    },
+    /// List the saved versions of a scratch.
+    History {
+        id: String,
+    },
+    /// Show the words changed between two versions of a scratch.
+    Diff {
+        id: String,
+        from: u32,
+        to: u32,
+    },
+    /// Save an earlier version of a scratch as its current state.
+    Revert {
+        id: String,
+        version: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProjectCommand {
+    List {
+        /// Include archived projects.
+        #[arg(long)]
+        archived: bool,
+    },
+    Show {
+        id: String,
+    },
+    /// Print the project outline.
+    Tree {
+        id: String,
+    },
+    /// Generate the LaTeX sources for a project.
+    Build {
+        id: String,
+        /// Output directory; defaults to the project's configured one.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Copy a project, with fresh outline node ids.
+    Duplicate {
+        id: String,
+        #[arg(long)]
+        title: String,
+        /// Also copy the scratches the project includes.
+        #[arg(long)]
+        copy_included: bool,
+    },
+    Rename {
+        id: String,
+        title: String,
+    },
+    /// Hide a project from the project list. It is still searched.
+    Archive {
+        id: String,
+    },
+    Unarchive {
+        id: String,
+    },
+    /// Move a project to the trash.
+    Rm {
+        id: String,
+    },
+    /// Freeze the project, the scratches it uses and its build output.
+    Release {
+        id: String,
+        /// What the release is for, such as "Submitted to the journal".
+        #[arg(long)]
+        name: String,
+    },
+    /// List the releases of a project.
+    Releases {
+        id: String,
+    },
+    /// Show how the project changed between two releases.
+    ReleaseDiff {
+        id: String,
+        from: String,
+        to: String,
+    },
+    /// Start a new project from a release.
+    Fork {
+        id: String,
+        release: String,
+        #[arg(long)]
+        title: String,
+    },
+    /// Show how the outline changed since a release.
+    Changes {
+        id: String,
+        release: String,
+    },
+    /// Merge another project's outline into this one.
+    Merge {
+        id: String,
+        other: String,
+        /// The release of this project both versions started from.
+        #[arg(long)]
+        base: String,
+        /// Print the merge without saving it.
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum BlueprintCommand {
+    List,
+    /// Create a project from a blueprint.
+    New {
+        id: String,
+        #[arg(long)]
+        title: String,
+    },
+    /// Save a project's outline, template and settings as a blueprint.
+    Save {
+        project: String,
+        #[arg(long)]
+        name: String,
+        #[arg(long, default_value = "")]
+        description: String,
+    },
+    Rm {
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateCommand {
+    List,
+    Show { id: String },
+}
+
+#[derive(Serialize)]
+struct TreeNode {
+    id: String,
+    title: String,
+    scratches: Vec<String>,
+    children: Vec<TreeNode>,
+}
+
+impl From<&OutlineNode> for TreeNode {
+    fn from(node: &OutlineNode) -> Self {
+        Self {
+            id: node.id.clone(),
+            title: node.title.clone(),
+            scratches: node
+                .scratches
+                .iter()
+                .map(|l| l.scratch_id.clone())
+                .collect(),
+            children: node.children.iter().map(TreeNode::from).collect(),
+        }
+    }
+}
+
+struct Context {
+    app: App,
+    json: bool,
+}
+
+impl Context {
+    fn open(path: &Path, json: bool, read_only: bool) -> CliResult<Self> {
+        if !path.is_dir() {
+            return Err(format!("Workspace not found: {}", path.display()));
+        }
+        let app = if read_only {
+            App::open_read_only(path)
+        } else {
+            App::open(path)
+        };
+        let app = app.map_err(|e| match e {
+            CoreError::Locked(_) => format!("{}; pass --read-only to inspect it anyway", e),
+            e => format!("Failed to open workspace: {}", e),
+        })?;
+        Ok(Self { app, json })
+    }
+
+    /// Prints `value` as JSON, or falls back to the plain-text rendering.
+    fn print<T: Serialize>(&self, value: &T, text: impl FnOnce() -> String) -> CliResult<()> {
+        if self.json {
+            let json = serde_json::to_string_pretty(value)
+                .map_err(|e| format!("Failed to serialize output: {}", e))?;
+            println!("{}", json);
+        } else {
+            let text = text();
+            if !text.is_empty() {
+                println!("{}", text.trim_end());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> CliResult<()> {
+    let ctx = Context::open(&cli.workspace, cli.json, cli.read_only)?;
+    match cli.command {
+        Command::Scratch(command) => run_scratch(&ctx, command),
+        Command::Project(command) => run_project(&ctx, command),
+        Command::Template(command) => run_template(&ctx, command),
+        Command::Blueprint(command) => run_blueprint(&ctx, command),
+        Command::Search { query } => {
+            let hits = ctx
+                .app
+                .search(&query)
+                .map_err(|e| format!("Failed to search: {}", e))?;
+            ctx.print(&hits, || {
+                hits.iter()
+                    .map(|hit| {
+                        format!(
+                            "{:?}\t{}\t{}\t{}\n",
+                            hit.kind, hit.id, hit.title, hit.snippet
+                        )
+                    })
+                    .collect()
+            })
+        }
+        Command::Export { path } => {
+            let manifest = ctx
+                .app
+                .export_workspace(&path)
+                .map_err(|e| format!("Failed to export workspace: {}", e))?;
+            ctx.print(&manifest, || {
+                format!(
+                    "Exported {} files to {}",
+                    manifest.files.len(),
+                    path.display()
+                )
+            })
+        }
+        Command::Fsck { repair } => run_fsck(&ctx, repair),
+    }
+}
+
+fn run_fsck(ctx: &Context, repair: bool) -> CliResult<()> {
+    let mut findings = ctx
+        .app
+        .check_workspace()
+        .map_err(|e| format!("Failed to check workspace: {}", e))?;
+    let mut repaired = 0;
+    if repair {
+        let mut problems = Vec::new();
+        for finding in findings.iter().filter(|f| f.fix.is_some()) {
+            if let Some(question) = &finding.confirm {
+                if !confirm(question)? {
+                    continue;
+                }
+            }
+            problems.push(finding.problem.clone());
+        }
+        repaired = problems.len();
+        findings = ctx
+            .app
+            .repair_workspace(&problems, true)
+            .map_err(|e| format!("Failed to repair workspace: {}", e))?;
+    }
+    ctx.print(&findings, || {
+        let mut text = String::new();
+        if repair {
+            text.push_str(&format!("Repaired {} problems\n", repaired));
+        }
+        for finding in &findings {
+            text.push_str(&finding.message);
+            if let Some(fix) = &finding.fix {
+                text.push_str(&format!(" (fix: {})", fix));
+            }
+            text.push('\n');
+        }
+        if findings.is_empty() {
+            text.push_str("No problems found\n");
+        }
+        text
+    })?;
+    if findings.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} problems found", findings.len()))
+    }
+}
+
+fn run_scratch(ctx: &Context, command: ScratchCommand) -> CliResult<()> {
+    match command {
+        ScratchCommand::List { tag } => {
+            let mut scratches = ctx
+                .app
+                .list_scratches(None)
+                .map_err(|e| format!("Failed to list scratches: {}", e))?;
+            if let Some(tag) = tag {
+                scratches.retain(|s| s.tags.contains(&tag));
+            }
+            scratches.sort_by_key(|s| std::cmp::Reverse(s.modified_at));
+            let dtos: Vec<tarsius_core::ScratchDto> =
+                scratches.iter().cloned().map(Into::into).collect();
+            ctx.print(&dtos, || scratches.iter().map(scratch_line).collect())
+        }
+        ScratchCommand::Show { id } => {
+            let scratch = load_scratch(ctx, &id)?;
+            print_scratch(ctx, scratch)
+        }
+        ScratchCommand::New {
+            title,
+            content,
+            tags,
+            source,
+        } => {
+            let content = match content {
+                Some(content) => content,
+                None => read_stdin()?,
+            };
+            let scratch = ctx
+                .app
+                .create_scratch(title, content, tags, source)
+                .map_err(|e| format!("Failed to create scratch: {}", e))?;
+            ctx.print(&tarsius_core::ScratchDto::from(scratch.clone()), || {
+                scratch.id.clone()
+            })
+        }
+        ScratchCommand::Edit {
+            id,
+            title,
+            content,
+            stdin,
+            source,
+        } => {
+            let content = if stdin { Some(read_stdin()?) } else { content };
+            let scratch = ctx
+                .app
+                .update_scratch(id, title, content, None, source.map(Some))
+                .map_err(|e| format!("Failed to update scratch: {}", e))?;
+            print_scratch(ctx, scratch)
+        }
+        ScratchCommand::Rm { id, policy } => {
+            let report = ctx
+                .app
+                .delete_scratch(&id, policy)
+                .map_err(|e| format!("Failed to delete scratch: {}", e))?;
+            let dto = tarsius_core::DeleteReportDto::from(report.clone());
+            ctx.print(&dto, || {
+                let mut text = format!("Moved {} to trash\n", report.scratch_id);
+                for reference in &report.references {
+                    text.push_str(&format!(
+                        "  detached from {} / {}\n",
+                        reference.project_title, reference.node_title
+                    ));
+                }
+                text
+            })
+        }
+        ScratchCommand::Tag { id, add, remove } => {
+            let scratch = ctx
+                .app
+                .tag_scratch(&id, &add, &remove)
+                .map_err(|e| format!("Failed to update scratch: {}", e))?;
+            print_scratch(ctx, scratch)
+        }
+        ScratchCommand::History { id } => {
+            let versions = ctx
+                .app
+                .scratch_versions(&id)
+                .map_err(|e| format!("Failed to load history: {}", e))?;
+            ctx.print(&versions, || {
+                versions
+                    .iter()
+                    .map(|v| {
+                        let changed: Vec<String> =
+                            v.changed.iter().map(|f| format!("{:?}", f)).collect();
+                        format!(
+                            "{}\t{}\t{}\n",
+                            v.version,
+                            v.recorded_at.to_rfc3339(),
+                            changed.join(", ")
+                        )
+                    })
+                    .collect()
+            })
+        }
+        ScratchCommand::Diff { id, from, to } => {
+            let diff = ctx
+                .app
+                .diff_scratch_versions(&id, from, to)
+                .map_err(|e| format!("Failed to diff versions: {}", e))?;
+            ctx.print(&diff, || {
+                let mut text = format!("# {}\n", render_diff(&diff.title));
+                if !diff.tags_added.is_empty() {
+                    text.push_str(&format!("tags added: {}\n", diff.tags_added.join(", ")));
+                }
+                if !diff.tags_removed.is_empty() {
+                    text.push_str(&format!("tags removed: {}\n", diff.tags_removed.join(", ")));
+                }
+                if diff.source_from != diff.source_to {
+                    text.push_str(&format!(
+                        "source: {} -> {}\n",
+                        diff.source_from.as_deref().unwrap_or("-"),
+                        diff.source_to.as_deref().unwrap_or("-")
+                    ));
+                }
+                text.push('\n');
+                text.push_str(&render_diff(&diff.content));
+                text
+            })
+        }
+        ScratchCommand::Revert { id, version } => {
+            let scratch = ctx
+                .app
+                .restore_scratch_version(&id, version)
+                .map_err(|e| format!("Failed to restore version: {}", e))?;
+            print_scratch(ctx, scratch)
+        }
+    }
+}
+
+fn run_project(ctx: &Context, command: ProjectCommand) -> CliResult<()> {
+    match command {
+        ProjectCommand::List { archived } => {
+            let projects = ctx
+                .app
+                .list_projects(archived)
+                .map_err(|e| format!("Failed to list projects: {}", e))?;
+            let dtos: Vec<tarsius_core::ProjectDto> =
+                projects.iter().cloned().map(Into::into).collect();
+            ctx.print(&dtos, || {
+                projects
+                    .iter()
+                    .map(|p| format!("{}\t{}\n", p.id, p.title))
+                    .collect()
+            })
+        }
+        ProjectCommand::Show { id } => {
+            let project = load_project(ctx, &id)?;
+            let dto = tarsius_core::ProjectDto::from(project.clone());
+            ctx.print(&dto, || {
+                format!(
+                    "id:       {}\ntitle:    {}\ntemplate: {}\noutput:   {}\nmodified: {}\n",
+                    project.id,
+                    project.title,
+                    project.settings.template_id,
+                    project.settings.output_dir,
+                    project.modified_at.to_rfc3339()
+                )
+            })
+        }
+        ProjectCommand::Tree { id } => {
+            let project = load_project(ctx, &id)?;
+            let tree = TreeNode::from(&project.outline);
+            ctx.print(&tree, || {
+                let mut text = String::new();
+                write_tree(&tree, 0, &mut text);
+                text
+            })
+        }
+        ProjectCommand::Build { id, output } => {
+            let result = ctx
+                .app
+                .build_project(&id, output.as_deref())
+                .map_err(|e| format!("Failed to build project: {}", e))?;
+            if !ctx.json {
+                for id in &result.missing_scratches {
+                    eprintln!("warning: linked scratch {} not found", id);
+                }
+            }
+            ctx.print(&result, || result.tex.display().to_string())
+        }
+        ProjectCommand::Duplicate {
+            id,
+            title,
+            copy_included,
+        } => {
+            let project = ctx
+                .app
+                .duplicate_project(&id, title, copy_included)
+                .map_err(|e| format!("Failed to duplicate project: {}", e))?;
+            let dto = tarsius_core::ProjectDto::from(project.clone());
+            ctx.print(&dto, || project.id.clone())
+        }
+        ProjectCommand::Rename { id, title } => {
+            let project = ctx
+                .app
+                .rename_project(&id, title)
+                .map_err(|e| format!("Failed to rename project: {}", e))?;
+            let dto = tarsius_core::ProjectDto::from(project.clone());
+            ctx.print(&dto, || project.title.clone())
+        }
+        ProjectCommand::Archive { id } => set_archived(ctx, &id, true),
+        ProjectCommand::Unarchive { id } => set_archived(ctx, &id, false),
+        ProjectCommand::Rm { id } => {
+            ctx.app
+                .delete_project(&id)
+                .map_err(|e| format!("Failed to delete project: {}", e))?;
+            ctx.print(&serde_json::json!({ "id": id }), || {
+                format!("Moved {} to trash\n", id)
+            })
+        }
+        ProjectCommand::Release { id, name } => {
+            let release = ctx
+                .app
+                .create_release(&id, name)
+                .map_err(|e| format!("Failed to create release: {}", e))?;
+            if !ctx.json {
+                for id in &release.missing_scratches {
+                    eprintln!("warning: linked scratch {} not found", id);
+                }
+            }
+            let summary = tarsius_core::ReleaseSummary::from(&release);
+            ctx.print(&summary, || release.id.clone())
+        }
+        ProjectCommand::Releases { id } => {
+            let releases = ctx
+                .app
+                .list_releases(&id)
+                .map_err(|e| format!("Failed to list releases: {}", e))?;
+            ctx.print(&releases, || {
+                releases
+                    .iter()
+                    .map(|r| format!("{}\t{}\t{}\n", r.id, r.created_at.to_rfc3339(), r.name))
+                    .collect()
+            })
+        }
+        ProjectCommand::ReleaseDiff { id, from, to } => {
+            let diff = ctx
+                .app
+                .diff_releases(&id, &from, &to)
+                .map_err(|e| format!("Failed to diff releases: {}", e))?;
+            ctx.print(&diff, || {
+                let mut text = format!("## Outline\n{}\n", render_outline_changes(&diff.outline));
+                for change in &diff.scratches {
+                    text.push_str(&format!(
+                        "## {:?}: {} ({})\n{}\n",
+                        change.kind,
+                        change.title,
+                        change.scratch_id,
+                        render_diff(&change.content)
+                    ));
+                }
+                text
+            })
+        }
+        ProjectCommand::Fork { id, release, title } => {
+            let project = ctx
+                .app
+                .fork_release(&id, &release, title)
+                .map_err(|e| format!("Failed to fork release: {}", e))?;
+            let dto = tarsius_core::ProjectDto::from(project.clone());
+            ctx.print(&dto, || project.id.clone())
+        }
+        ProjectCommand::Changes { id, release } => {
+            let changes = ctx
+                .app
+                .outline_changes(&id, &release)
+                .map_err(|e| format!("Failed to diff outline: {}", e))?;
+            ctx.print(&changes, || render_outline_changes(&changes))
+        }
+        ProjectCommand::Merge {
+            id,
+            other,
+            base,
+            dry_run,
+        } => {
+            let merge = if dry_run {
+                ctx.app.preview_merge(&id, &other, &base)
+            } else {
+                ctx.app.merge_projects(&id, &other, &base)
+            }
+            .map_err(|e| format!("Failed to merge projects: {}", e))?;
+            ctx.print(&merge, || {
+                merge
+                    .conflicts
+                    .iter()
+                    .map(|c| format!("conflict\t{:?}\t{}\t{}\n", c.kind, c.node_id, c.title))
+                    .collect()
+            })
+        }
+    }
+}
+
+fn run_blueprint(ctx: &Context, command: BlueprintCommand) -> CliResult<()> {
+    match command {
+        BlueprintCommand::List => {
+            let blueprints = ctx
+                .app
+                .list_blueprints()
+                .map_err(|e| format!("Failed to list blueprints: {}", e))?;
+            ctx.print(&blueprints, || {
+                blueprints
+                    .iter()
+                    .map(|b| format!("{}\t{}\t{}\n", b.id, b.name, b.description))
+                    .collect()
+            })
+        }
+        BlueprintCommand::New { id, title } => {
+            let project = ctx
+                .app
+                .create_project_from_blueprint(&id, title)
+                .map_err(|e| format!("Failed to create project: {}", e))?;
+            let dto = tarsius_core::ProjectDto::from(project.clone());
+            ctx.print(&dto, || project.id.clone())
+        }
+        BlueprintCommand::Save {
+            project,
+            name,
+            description,
+        } => {
+            let blueprint = ctx
+                .app
+                .save_project_as_blueprint(&project, name, description)
+                .map_err(|e| format!("Failed to save blueprint: {}", e))?;
+            ctx.print(&blueprint, || blueprint.id.clone())
+        }
+        BlueprintCommand::Rm { id } => {
+            ctx.app
+                .delete_blueprint(&id)
+                .map_err(|e| format!("Failed to delete blueprint: {}", e))?;
+            ctx.print(&serde_json::json!({ "id": id }), || {
+                format!("Deleted {}\n", id)
+            })
+        }
+    }
+}
+
+fn run_template(ctx: &Context, command: TemplateCommand) -> CliResult<()> {
+    match command {
+        TemplateCommand::List => {
+            let templates = ctx
+                .app
+                .list_templates()
+                .map_err(|e| format!("Failed to list templates: {}", e))?;
+            let dtos: Vec<tarsius_core::TemplateDto> =
+                templates.iter().cloned().map(Into::into).collect();
+            ctx.print(&dtos, || {
+                templates
+                    .iter()
+                    .map(|t| format!("{}\t{}\n", t.id, t.name))
+                    .collect()
+            })
+        }
+        TemplateCommand::Show { id } => {
+            let template = ctx
+                .app
+                .load_template(&id)
+                .map_err(|e| format!("Failed to load template: {}", e))?;
+            let dto = tarsius_core::TemplateDto::from(template.clone());
+            ctx.print(&dto, || template.content.clone())
+        }
+    }
+}
+
+fn set_archived(ctx: &Context, id: &str, archived: bool) -> CliResult<()> {
+    let project = ctx
+        .app
+        .set_project_archived(id, archived)
+        .map_err(|e| format!("Failed to archive project: {}", e))?;
+    let dto = tarsius_core::ProjectDto::from(project.clone());
+    ctx.print(&dto, || project.id.clone())
+}
+
+fn load_scratch(ctx: &Context, id: &str) -> CliResult<Scratch> {
+    ctx.app
+        .load_scratch(id)
+        .map_err(|e| format!("Failed to load scratch: {}", e))
+}
+
+fn load_project(ctx: &Context, id: &str) -> CliResult<tarsius_core::Project> {
+    ctx.app
+        .load_project(id)
+        .map(|loaded| loaded.project)
+        .map_err(|e| format!("Failed to load project: {}", e))
+}
+
+fn print_scratch(ctx: &Context, scratch: Scratch) -> CliResult<()> {
+    let dto = tarsius_core::ScratchDto::from(scratch.clone());
+    ctx.print(&dto, || {
+        let mut text = format!("# {}\n", scratch.title);
+        if !scratch.tags.is_empty() {
+            text.push_str(&format!("tags: {}\n", scratch.tags.join(", ")));
+        }
+        if let Some(source) = &scratch.source {
+            text.push_str(&format!("source: {}\n", source));
+        }
+        text.push('\n');
+        text.push_str(&scratch.content);
+        text
+    })
+}
+
+fn scratch_line(scratch: &Scratch) -> String {
+    if scratch.tags.is_empty() {
+        format!("{}\t{}\n", scratch.id, scratch.title)
+    } else {
+        format!(
+            "{}\t{}\t[{}]\n",
+            scratch.id,
+            scratch.title,
+            scratch.tags.join(", ")
+        )
+    }
+}
+
+/// Marks deleted words as `[-...-]` and inserted ones as `{+...+}`.
+fn render_diff(spans: &[DiffSpan]) -> String {
+    spans
+        .iter()
+        .map(|span| match span.op {
+            DiffOp::Equal => span.text.clone(),
+            DiffOp::Delete => format!("[-{}-]", span.text),
+            DiffOp::Insert => format!("{{+{}+}}", span.text),
+        })
+        .collect()
+}
+
+/// One line per change, such as `renamed n1: Method -> Methods`.
+fn render_outline_changes(changes: &[OutlineChange]) -> String {
+    changes
+        .iter()
+        .map(|change| match change {
+            OutlineChange::Added {
+                node_id,
+                parent_id,
+                title,
+            } => format!("added {}: {} under {}\n", node_id, title, parent_id),
+            OutlineChange::Removed { node_id, title } => {
+                format!("removed {}: {}\n", node_id, title)
+            }
+            OutlineChange::Moved {
+                node_id,
+                title,
+                from_parent,
+                to_parent,
+            } => format!(
+                "moved {}: {} from {} to {}\n",
+                node_id, title, from_parent, to_parent
+            ),
+            OutlineChange::Renamed { node_id, from, to } => {
+                format!("renamed {}: {} -> {}\n", node_id, from, to)
+            }
+            OutlineChange::ContentEdited {
+                node_id,
+                title,
+                content,
+            } => format!("edited {}: {}\n{}\n", node_id, title, render_diff(content)),
+            OutlineChange::LinkAttached {
+                node_id,
+                scratch_id,
+            } => format!("linked {}: {}\n", node_id, scratch_id),
+            OutlineChange::LinkDetached {
+                node_id,
+                scratch_id,
+            } => format!("unlinked {}: {}\n", node_id, scratch_id),
+        })
+        .collect()
+}
+
+fn write_tree(node: &TreeNode, depth: usize, out: &mut String) {
+    out.push_str(&format!(
+        "{}{} ({})",
+        "  ".repeat(depth),
+        node.title,
+        node.id
+    ));
+    if !node.scratches.is_empty() {
+        out.push_str(&format!(" <- {}", node.scratches.join(", ")));
+    }
+    out.push('\n');
+    for child in &node.children {
+        write_tree(child, depth + 1, out);
+    }
+}
+
+/// Asks `question` on the terminal. Without one, the answer is no.
+fn confirm(question: &str) -> CliResult<bool> {
+    if !std::io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    eprint!("{} [y/N] ", question);
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn read_stdin() -> CliResult<String> {
+    let mut content = String::new();
+    std::io::stdin()
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_scratch_rm_policy() {
+        let cli = Cli::try_parse_from([
+            "tarsius",
+            "--workspace",
+            "/tmp/ws",
+            "scratch",
+            "rm",
+            "abc",
+            "--policy",
+            "Unlink",
+            "--json",
+        ])
+        .unwrap();
+        assert!(cli.json);
+        assert_eq!(cli.workspace, PathBuf::from("/tmp/ws"));
+        assert!(matches!(
+            cli.command,
+            Command::Scratch(ScratchCommand::Rm {
+                policy: DeletePolicy::Unlink,
+                ..
+            })
+        ));
+    }
+}
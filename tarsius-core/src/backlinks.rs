@@ -0,0 +1,107 @@
+use crate::{all_references, Project, ScratchReference};
+use std::collections::{HashMap, HashSet};
+
+/// Reverse index from scratch id to the outline nodes that link to it.
+#[derive(Debug, Default)]
+pub struct BacklinkIndex {
+    by_scratch: HashMap<String, Vec<ScratchReference>>,
+}
+
+impl BacklinkIndex {
+    pub fn build(projects: &[Project]) -> Self {
+        let mut index = Self::default();
+        for project in projects {
+            index.insert_project(project);
+        }
+        index
+    }
+
+    /// Replaces whatever the index held for `project` with its current links.
+    pub fn update_project(&mut self, project: &Project) {
+        self.remove_project(&project.id);
+        self.insert_project(project);
+    }
+
+    pub fn remove_project(&mut self, project_id: &str) {
+        self.by_scratch.retain(|_, usages| {
+            usages.retain(|usage| usage.project_id != project_id);
+            !usages.is_empty()
+        });
+    }
+
+    pub fn usages(&self, scratch_id: &str) -> Vec<ScratchReference> {
+        self.by_scratch.get(scratch_id).cloned().unwrap_or_default()
+    }
+
+    pub fn used_scratch_ids(&self) -> HashSet<String> {
+        self.by_scratch.keys().cloned().collect()
+    }
+
+    fn insert_project(&mut self, project: &Project) {
+        for reference in all_references(project) {
+            self.by_scratch
+                .entry(reference.scratch_id.clone())
+                .or_default()
+                .push(reference);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InsertionFlags, IntegrationMode, OutlineNode, ProjectSettings, ScratchLink};
+    use chrono::Utc;
+
+    fn project(id: &str, scratch_ids: &[&str]) -> Project {
+        Project {
+            id: id.to_string(),
+            title: id.to_string(),
+            outline: OutlineNode {
+                id: format!("{}-root", id),
+                title: "Root".to_string(),
+                content: None,
+                children: vec![],
+                scratches: scratch_ids
+                    .iter()
+                    .map(|scratch_id| ScratchLink {
+                        scratch_id: scratch_id.to_string(),
+                        mode: IntegrationMode::Include,
+                        insertion: InsertionFlags {
+                            body: true,
+                            footnote: false,
+                            reference: false,
+                            appendix: false,
+                        },
+                        included: None,
+                    })
+                    .collect(),
+            },
+            settings: ProjectSettings {
+                template_id: "t".to_string(),
+                output_dir: "out".to_string(),
+            },
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            archived: false,
+        }
+    }
+
+    #[test]
+    fn test_index_tracks_project_updates() {
+        let mut index = BacklinkIndex::build(&[project("a", &["s1", "s2"]), project("b", &["s1"])]);
+        assert_eq!(index.usages("s1").len(), 2);
+        assert_eq!(index.usages("s2").len(), 1);
+
+        index.update_project(&project("a", &["s1"]));
+        assert!(index.usages("s2").is_empty());
+        assert_eq!(index.usages("s1").len(), 2);
+
+        index.remove_project("b");
+        let usages = index.usages("s1");
+        assert_eq!(usages.len(), 1);
+        assert_eq!(usages[0].project_id, "a");
+        assert_eq!(usages[0].node_id, "a-root");
+        assert_eq!(index.used_scratch_ids(), HashSet::from(["s1".to_string()]));
+    }
+}
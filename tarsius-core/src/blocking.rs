@@ -0,0 +1,172 @@
+use crate::{CoreError, Result};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed set of worker threads for blocking work such as file I/O, imports
+/// and builds. Each job is awaited through a `BlockingTask`, which does not
+/// depend on any particular async runtime.
+pub struct BlockingPool {
+    jobs: Sender<Job>,
+}
+
+impl BlockingPool {
+    /// Starts `threads` workers (at least one). They exit once the pool is
+    /// dropped and the queued jobs have run.
+    pub fn new(threads: usize) -> Self {
+        let (jobs, queue) = channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+        for i in 0..threads.max(1) {
+            let queue = queue.clone();
+            thread::Builder::new()
+                .name(format!("tarsius-blocking-{}", i))
+                .spawn(move || work(&queue))
+                .expect("failed to start blocking pool worker");
+        }
+        Self { jobs }
+    }
+
+    /// Queues `f` and returns a future for its result. A panic in `f` is
+    /// reported as a `CoreError::Storage` instead of unwinding.
+    pub fn spawn<T, F>(&self, f: F) -> BlockingTask<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T> + Send + 'static,
+    {
+        let slot = Arc::new(Mutex::new(Slot {
+            result: None,
+            waker: None,
+        }));
+        let job_slot = slot.clone();
+        let job: Job = Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+                Err(CoreError::Storage("Background task panicked".to_string()))
+            });
+            fill(&job_slot, result);
+        });
+        if self.jobs.send(job).is_err() {
+            fill(
+                &slot,
+                Err(CoreError::Storage("Blocking pool has stopped".to_string())),
+            );
+        }
+        BlockingTask { slot }
+    }
+}
+
+fn work(queue: &Mutex<Receiver<Job>>) {
+    loop {
+        // The guard is dropped before the job runs, so other workers can
+        // pick up the next one meanwhile.
+        let job = match queue.lock() {
+            Ok(queue) => queue.recv(),
+            Err(_) => return,
+        };
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
+
+struct Slot<T> {
+    result: Option<Result<T>>,
+    waker: Option<Waker>,
+}
+
+fn fill<T>(slot: &Mutex<Slot<T>>, result: Result<T>) {
+    let waker = {
+        let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+        slot.result = Some(result);
+        slot.waker.take()
+    };
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+/// The result of a job queued on a `BlockingPool`.
+pub struct BlockingTask<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+impl<T> BlockingTask<T> {
+    /// Blocks the current thread until the job has run, for callers that
+    /// are not async.
+    pub fn wait(self) -> Result<T> {
+        let mut task = self;
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(result) = Pin::new(&mut task).poll(&mut cx) {
+                return result;
+            }
+            thread::park();
+        }
+    }
+}
+
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+impl<T> Future for BlockingTask<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T>> {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_runs_jobs_off_the_calling_thread() {
+        let pool = BlockingPool::new(2);
+        let caller = thread::current().id();
+        let ran = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let ran = ran.clone();
+                pool.spawn(move || {
+                    assert_ne!(thread::current().id(), caller);
+                    ran.fetch_add(1, Ordering::SeqCst);
+                    Ok(i * 2)
+                })
+            })
+            .collect();
+        let results: Vec<usize> = tasks.into_iter().map(|t| t.wait().unwrap()).collect();
+        assert_eq!(results, (0..8).map(|i| i * 2).collect::<Vec<_>>());
+        assert_eq!(ran.load(Ordering::SeqCst), 8);
+    }
+
+    #[test]
+    fn test_panics_become_errors() {
+        let pool = BlockingPool::new(1);
+        let task = pool.spawn(|| -> Result<()> { panic!("boom") });
+        assert!(matches!(task.wait(), Err(CoreError::Storage(_))));
+        // The worker survives the panic.
+        assert_eq!(pool.spawn(|| Ok(1)).wait().unwrap(), 1);
+    }
+}
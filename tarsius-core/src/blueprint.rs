@@ -0,0 +1,454 @@
+use crate::{OutlineNode, Project, ProjectSettings, Result, Template, ROOT_TITLE};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Where projects made from the built-in blueprints write their output, each
+/// in a directory of its own.
+const DEFAULT_OUTPUT_DIR: &str = "output";
+
+/// A starting point for new projects: an outline skeleton, the template it
+/// is typeset with and the project settings.
+///
+/// Placeholder text is written as LaTeX comments, so a project still builds
+/// before every placeholder has been replaced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Blueprint {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Node ids are replaced by fresh ones in every project made from it.
+    pub outline: OutlineNode,
+    /// Saved to the workspace templates under its id, unless a template with
+    /// that id is already there.
+    pub template: Option<Template>,
+    /// `output_dir` is a parent directory: every project made from the
+    /// blueprint writes into a subdirectory named after its id.
+    pub settings: ProjectSettings,
+    /// Built-in blueprints come with the program and cannot be deleted.
+    #[serde(default)]
+    pub builtin: bool,
+}
+
+impl Blueprint {
+    /// The skeleton of `project`: its outline and node text without the
+    /// scratch links, which belong to the project rather than its shape.
+    pub fn from_project(
+        project: &Project,
+        template: Option<Template>,
+        name: String,
+        description: String,
+    ) -> Self {
+        let mut outline = project.outline.clone();
+        drop_links(&mut outline);
+        let mut settings = project.settings.clone();
+        // A project made from a blueprint hands on the parent directory, so
+        // that the directories do not nest.
+        let parent = settings
+            .output_dir
+            .strip_suffix(project.id.as_str())
+            .and_then(|dir| dir.strip_suffix('/'));
+        if let Some(parent) = parent.filter(|dir| !dir.is_empty()) {
+            settings.output_dir = parent.to_string();
+        }
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            description,
+            outline,
+            template,
+            settings,
+            builtin: false,
+        }
+    }
+
+    /// The output directory of the project `project_id` made from this
+    /// blueprint.
+    pub fn output_dir_for(&self, project_id: &str) -> String {
+        format!(
+            "{}/{}",
+            self.settings.output_dir.trim_end_matches('/'),
+            project_id
+        )
+    }
+}
+
+fn drop_links(node: &mut OutlineNode) {
+    node.scratches.clear();
+    for child in &mut node.children {
+        drop_links(child);
+    }
+}
+
+/// Stores the blueprints users saved. Built-in ones are not stored.
+pub trait BlueprintRepository: Send + Sync {
+    fn save(&self, blueprint: &Blueprint) -> Result<()>;
+    fn load(&self, id: &str) -> Result<Blueprint>;
+    fn list(&self) -> Result<Vec<Blueprint>>;
+    fn delete(&self, id: &str) -> Result<()>;
+}
+
+/// The blueprints that come with the program.
+pub fn builtin_blueprints() -> Vec<Blueprint> {
+    vec![
+        imrad_paper(),
+        thesis(),
+        grant_proposal(),
+        lecture_slides(),
+        book(),
+    ]
+}
+
+fn builtin(
+    id: &str,
+    name: &str,
+    description: &str,
+    children: Vec<OutlineNode>,
+    template: Template,
+) -> Blueprint {
+    Blueprint {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        outline: OutlineNode {
+            id: "root".to_string(),
+            title: ROOT_TITLE.to_string(),
+            content: None,
+            children,
+            scratches: vec![],
+        },
+        settings: ProjectSettings {
+            template_id: template.id.clone(),
+            output_dir: DEFAULT_OUTPUT_DIR.to_string(),
+        },
+        template: Some(template),
+        builtin: true,
+    }
+}
+
+/// A node with placeholder text. Its id is the title in lower case with
+/// dashes, which is unique within each built-in outline.
+fn node(title: &str, placeholder: &str, children: Vec<OutlineNode>) -> OutlineNode {
+    OutlineNode {
+        id: title.to_lowercase().replace(' ', "-"),
+        title: title.to_string(),
+        content: (!placeholder.is_empty()).then(|| placeholder.to_string()),
+        children,
+        scratches: vec![],
+    }
+}
+
+fn template(id: &str, name: &str, content: &str) -> Template {
+    Template {
+        id: id.to_string(),
+        name: name.to_string(),
+        content: content.to_string(),
+    }
+}
+
+const ARTICLE_TEMPLATE: &str = "\\documentclass[11pt]{article}
+\\usepackage[utf8]{inputenc}
+\\usepackage{graphicx}
+\\usepackage{hyperref}
+
+\\title{{{title}}}
+\\author{}
+\\date{\\today}
+
+\\begin{document}
+\\maketitle
+{{body}}
+\\end{document}
+";
+
+const BOOK_TEMPLATE: &str = "\\documentclass[11pt,oneside]{book}
+\\usepackage[utf8]{inputenc}
+\\usepackage{graphicx}
+\\usepackage{hyperref}
+
+\\title{{{title}}}
+\\author{}
+\\date{}
+
+\\begin{document}
+\\frontmatter
+\\maketitle
+\\tableofcontents
+{{body}}
+\\end{document}
+";
+
+const SLIDES_TEMPLATE: &str = "\\documentclass{beamer}
+\\usepackage[utf8]{inputenc}
+
+\\title{{{title}}}
+\\author{}
+\\date{\\today}
+
+\\begin{document}
+\\frame{\\titlepage}
+{{body}}
+\\end{document}
+";
+
+fn imrad_paper() -> Blueprint {
+    builtin(
+        "imrad-paper",
+        "IMRaD paper",
+        "A research article: introduction, methods, results and discussion.",
+        vec![
+            node(
+                "Abstract",
+                "% The question, how it was studied, what was found and why it matters, in about 250 words.",
+                vec![],
+            ),
+            node(
+                "Introduction",
+                "% What is known, what is missing, and the question this paper answers.",
+                vec![],
+            ),
+            node(
+                "Methods",
+                "% Enough detail for someone else to repeat the study.",
+                vec![
+                    node("Data", "% Where the data came from and how it was selected.", vec![]),
+                    node("Procedure", "% What was done, in order.", vec![]),
+                    node("Analysis", "% How the results were computed and tested.", vec![]),
+                ],
+            ),
+            node(
+                "Results",
+                "% What was found, without interpretation. Refer to figures and tables.",
+                vec![],
+            ),
+            node(
+                "Discussion",
+                "% What the results mean, how they compare to earlier work, and their limits.",
+                vec![],
+            ),
+            node("Conclusion", "% The answer to the question, in a few sentences.", vec![]),
+        ],
+        template("article", "Article", ARTICLE_TEMPLATE),
+    )
+}
+
+fn thesis() -> Blueprint {
+    builtin(
+        "thesis",
+        "Thesis",
+        "Chapters with front matter before them and appendices after.",
+        vec![
+            node(
+                "Abstract",
+                "% The research question, approach, main findings and contribution.",
+                vec![],
+            ),
+            node(
+                "Acknowledgements",
+                "% Supervisors, funders, colleagues, family.\n\n% The numbered chapters start after this command.\n\\mainmatter",
+                vec![],
+            ),
+            node(
+                "Introduction",
+                "% The problem, why it matters, the research questions and how the thesis is organised.",
+                vec![],
+            ),
+            node(
+                "Literature Review",
+                "% The work this thesis builds on and the gap it fills.",
+                vec![],
+            ),
+            node(
+                "Methodology",
+                "% How the research questions are answered, and why this way.",
+                vec![],
+            ),
+            node("Results", "% What the research found.", vec![]),
+            node(
+                "Discussion",
+                "% What the findings mean, their limits and what they change.",
+                vec![],
+            ),
+            node(
+                "Conclusion",
+                "% The answers to the research questions and the work left to do.\n\n% The chapters after this command are appendices.\n\\appendix",
+                vec![],
+            ),
+            node(
+                "Supplementary Material",
+                "% Data, proofs and instruments too long for the chapters.",
+                vec![],
+            ),
+        ],
+        template("thesis", "Thesis", BOOK_TEMPLATE),
+    )
+}
+
+fn grant_proposal() -> Blueprint {
+    builtin(
+        "grant-proposal",
+        "Grant proposal",
+        "Aims, approach, team and budget, as most funders ask for them.",
+        vec![
+            node(
+                "Summary",
+                "% The project in one page: the problem, the aims and the expected impact.",
+                vec![],
+            ),
+            node(
+                "Background and Significance",
+                "% Why the problem matters now and what is missing.",
+                vec![],
+            ),
+            node(
+                "Aims",
+                "% Two or three aims that together solve the problem.",
+                vec![
+                    node(
+                        "Aim 1",
+                        "% The aim, its hypothesis and how success is measured.",
+                        vec![],
+                    ),
+                    node(
+                        "Aim 2",
+                        "% The aim, its hypothesis and how success is measured.",
+                        vec![],
+                    ),
+                ],
+            ),
+            node(
+                "Approach",
+                "% How each aim will be reached.",
+                vec![
+                    node(
+                        "Methods",
+                        "% Design, data and analysis for each aim.",
+                        vec![],
+                    ),
+                    node("Timeline", "% Milestones by quarter or year.", vec![]),
+                    node(
+                        "Risks and Alternatives",
+                        "% What could go wrong and what happens then.",
+                        vec![],
+                    ),
+                ],
+            ),
+            node(
+                "Expected Outcomes",
+                "% Results, publications and what changes if the project succeeds.",
+                vec![],
+            ),
+            node(
+                "Team",
+                "% Who does what, and why they are the right people.",
+                vec![],
+            ),
+            node("Budget Justification", "% What each cost is for.", vec![]),
+        ],
+        template("proposal", "Proposal", ARTICLE_TEMPLATE),
+    )
+}
+
+fn lecture_slides() -> Blueprint {
+    let slide = |title: &str, placeholder: &str| {
+        node(
+            title,
+            &format!(
+                "\\begin{{frame}}{{{}}}\n% {}\n\\end{{frame}}",
+                title, placeholder
+            ),
+            vec![],
+        )
+    };
+    builtin(
+        "lecture-slides",
+        "Lecture slides",
+        "A beamer presentation; each section holds its slides as frames.",
+        vec![
+            node(
+                "Overview",
+                "\\begin{frame}{Overview}\n\\tableofcontents\n\\end{frame}",
+                vec![],
+            ),
+            slide("Motivation", "Why this topic matters to the audience."),
+            slide("Background", "What the audience needs to know first."),
+            slide("Main Idea", "The one thing to remember from this lecture."),
+            slide("Examples", "The idea at work, from simple to realistic."),
+            slide("Summary", "The main points again, and what comes next."),
+        ],
+        template("slides", "Slides", SLIDES_TEMPLATE),
+    )
+}
+
+fn book() -> Blueprint {
+    builtin(
+        "book",
+        "Book",
+        "Preface, chapters with sections, and an epilogue.",
+        vec![
+            node(
+                "Preface",
+                "% Who the book is for and how to read it.\n\n% The numbered chapters start after this command.\n\\mainmatter",
+                vec![],
+            ),
+            node(
+                "Chapter One",
+                "% What this chapter sets out to do.",
+                vec![
+                    node("First Section", "", vec![]),
+                    node("Second Section", "", vec![]),
+                ],
+            ),
+            node(
+                "Chapter Two",
+                "% What this chapter sets out to do.",
+                vec![node("Third Section", "", vec![])],
+            ),
+            node(
+                "Chapter Three",
+                "% What this chapter sets out to do.",
+                vec![node("Fourth Section", "", vec![])],
+            ),
+            node(
+                "Epilogue",
+                "% Where things stand at the end.\n\n% Bibliography and appendices follow this command.\n\\backmatter",
+                vec![],
+            ),
+        ],
+        template("book", "Book", BOOK_TEMPLATE),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{validate_project, DocumentClass};
+    use chrono::Utc;
+
+    #[test]
+    fn test_builtin_blueprints_are_valid_projects() {
+        let blueprints = builtin_blueprints();
+        let mut ids: Vec<&str> = blueprints.iter().map(|b| b.id.as_str()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 5);
+        for blueprint in &blueprints {
+            let template = blueprint.template.as_ref().unwrap();
+            assert_eq!(blueprint.settings.template_id, template.id);
+            let mut project = Project {
+                id: "p".to_string(),
+                title: blueprint.name.clone(),
+                outline: blueprint.outline.clone(),
+                settings: blueprint.settings.clone(),
+                created_at: Utc::now(),
+                modified_at: Utc::now(),
+                archived: false,
+            };
+            let class = DocumentClass::of_template(&template.content);
+            assert!(
+                validate_project(&mut project, Some(class)).is_ok(),
+                "{}",
+                blueprint.id
+            );
+        }
+    }
+}
@@ -0,0 +1,532 @@
+use crate::{
+    OutlineNode, Project, ProjectRepository, Result, Scratch, ScratchLink, ScratchRepository,
+    Template, TemplateRepository,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::Mutex;
+
+/// An entity that `CachingRepository` can keep.
+pub trait Cacheable: Clone + Send {
+    fn cache_id(&self) -> &str;
+    /// Rough size in memory, in bytes, counted against the cache budget.
+    fn weight(&self) -> usize;
+}
+
+impl Cacheable for Scratch {
+    fn cache_id(&self) -> &str {
+        &self.id
+    }
+
+    fn weight(&self) -> usize {
+        size_of::<Scratch>()
+            + self.id.len()
+            + self.title.len()
+            + self.content.len()
+            + self
+                .tags
+                .iter()
+                .map(|t| size_of::<String>() + t.len())
+                .sum::<usize>()
+            + self.source.as_ref().map_or(0, String::len)
+    }
+}
+
+impl Cacheable for Project {
+    fn cache_id(&self) -> &str {
+        &self.id
+    }
+
+    fn weight(&self) -> usize {
+        fn node_weight(node: &OutlineNode) -> usize {
+            size_of::<OutlineNode>()
+                + node.id.len()
+                + node.title.len()
+                + node.content.as_ref().map_or(0, String::len)
+                + node
+                    .scratches
+                    .iter()
+                    .map(|link| {
+                        size_of::<ScratchLink>()
+                            + link.scratch_id.len()
+                            + link.included.as_ref().map_or(0, |copy| {
+                                copy.original.len() + copy.source_hash.len() + copy.text.len()
+                            })
+                    })
+                    .sum::<usize>()
+                + node.children.iter().map(node_weight).sum::<usize>()
+        }
+        size_of::<Project>()
+            + self.id.len()
+            + self.title.len()
+            + self.settings.template_id.len()
+            + self.settings.output_dir.len()
+            + node_weight(&self.outline)
+    }
+}
+
+impl Cacheable for Template {
+    fn cache_id(&self) -> &str {
+        &self.id
+    }
+
+    fn weight(&self) -> usize {
+        size_of::<Template>() + self.id.len() + self.name.len() + self.content.len()
+    }
+}
+
+/// What `refresh` found on disk for one item.
+#[derive(Debug, Clone)]
+pub enum Refreshed<T> {
+    /// The file holds what the cache already had, e.g. after a write made
+    /// through the cache.
+    Unchanged,
+    /// The item was added or edited outside the cache. Items the cache did
+    /// not hold are reported here too, as there is nothing to compare with.
+    Changed(T),
+    Removed,
+}
+
+/// Counters of one cache. A `list` counts as one hit or miss.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheMetrics {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub bytes: usize,
+    pub budget: usize,
+}
+
+struct Entry<T> {
+    value: T,
+    weight: usize,
+    last_used: u64,
+}
+
+struct CacheState<T> {
+    entries: HashMap<String, Entry<T>>,
+    // Whether `entries` holds every item of the backend, so that `list` can
+    // be answered from memory.
+    complete: bool,
+    bytes: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl<T: Cacheable> CacheState<T> {
+    fn touch(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn insert(&mut self, value: T, budget: usize) {
+        let id = value.cache_id().to_string();
+        self.remove(&id);
+        let weight = value.weight();
+        if weight > budget {
+            self.complete = false;
+            return;
+        }
+        let last_used = self.touch();
+        self.bytes += weight;
+        self.entries.insert(
+            id,
+            Entry {
+                value,
+                weight,
+                last_used,
+            },
+        );
+        while self.bytes > budget {
+            self.evict_oldest();
+        }
+    }
+
+    fn remove(&mut self, id: &str) {
+        if let Some(entry) = self.entries.remove(id) {
+            self.bytes -= entry.weight;
+        }
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(id, _)| id.clone());
+        if let Some(id) = oldest {
+            self.remove(&id);
+            self.evictions += 1;
+            self.complete = false;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+        self.complete = false;
+    }
+}
+
+/// Keeps the entities of a repository in memory, in front of any backend.
+///
+/// Items are read from the backend on first use and kept until the memory
+/// budget is exceeded, when the least recently used ones are dropped. Writes
+/// through the cache keep it current; changes made behind its back (other
+/// programs, the trash moving files) must be reported with `invalidate`.
+///
+/// Share one instance, e.g. through an `Arc`, between everything that uses
+/// the same backend, or their writes will not see each other.
+pub struct CachingRepository<R, T> {
+    inner: R,
+    budget: usize,
+    state: Mutex<CacheState<T>>,
+}
+
+impl<R, T: Cacheable + Serialize> CachingRepository<R, T> {
+    /// `budget` is in bytes; `0` disables caching.
+    pub fn new(inner: R, budget: usize) -> Self {
+        Self {
+            inner,
+            budget,
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                complete: false,
+                bytes: 0,
+                clock: 0,
+                hits: 0,
+                misses: 0,
+                evictions: 0,
+            }),
+        }
+    }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        let state = self.state.lock().unwrap();
+        CacheMetrics {
+            hits: state.hits,
+            misses: state.misses,
+            evictions: state.evictions,
+            entries: state.entries.len(),
+            bytes: state.bytes,
+            budget: self.budget,
+        }
+    }
+
+    /// Forgets one item, which was changed, added or removed elsewhere.
+    pub fn invalidate(&self, id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.remove(id);
+        state.complete = false;
+    }
+
+    pub fn invalidate_all(&self) {
+        self.state.lock().unwrap().clear();
+    }
+
+    // Re-reads one item that changed on disk. Unlike `invalidate`, this keeps
+    // a complete cache complete, so one edited file does not cost a full
+    // re-read on the next `list`.
+    fn refresh_with(&self, id: &str, load: impl FnOnce(&R) -> Result<T>) -> Refreshed<T> {
+        let mut state = self.state.lock().unwrap();
+        let held = state.entries.get(id).map(|entry| entry.value.clone());
+        let tracked = state.complete || held.is_some();
+        match load(&self.inner) {
+            Ok(value) => {
+                if held.is_some_and(|held| same(&held, &value)) {
+                    return Refreshed::Unchanged;
+                }
+                if tracked {
+                    state.insert(value.clone(), self.budget);
+                }
+                Refreshed::Changed(value)
+            }
+            Err(e) if e.is_not_found() => {
+                state.remove(id);
+                // A complete cache without the item deleted it itself.
+                if held.is_none() && state.complete {
+                    Refreshed::Unchanged
+                } else {
+                    Refreshed::Removed
+                }
+            }
+            // Unreadable files are left to `fsck`.
+            Err(_) => {
+                state.remove(id);
+                state.complete = false;
+                Refreshed::Unchanged
+            }
+        }
+    }
+
+    // The lock is held while the backend is read, so that an invalidation
+    // cannot be overtaken by the stale data it invalidates.
+    fn load_with(&self, id: &str, load: impl FnOnce(&R) -> Result<T>) -> Result<T> {
+        let mut state = self.state.lock().unwrap();
+        let tick = state.touch();
+        if let Some(entry) = state.entries.get_mut(id) {
+            entry.last_used = tick;
+            let value = entry.value.clone();
+            state.hits += 1;
+            return Ok(value);
+        }
+        state.misses += 1;
+        let value = load(&self.inner)?;
+        state.insert(value.clone(), self.budget);
+        Ok(value)
+    }
+
+    fn list_with(&self, list: impl FnOnce(&R) -> Result<Vec<T>>) -> Result<Vec<T>> {
+        let mut state = self.state.lock().unwrap();
+        if state.complete {
+            state.hits += 1;
+            let mut values: Vec<T> = state.entries.values().map(|e| e.value.clone()).collect();
+            values.sort_by(|a, b| a.cache_id().cmp(b.cache_id()));
+            return Ok(values);
+        }
+        state.misses += 1;
+        let values = list(&self.inner)?;
+        state.clear();
+        state.complete = true;
+        for value in &values {
+            state.insert(value.clone(), self.budget);
+        }
+        Ok(values)
+    }
+
+    // Writes hold the lock too, so that a concurrent write or refresh cannot
+    // leave the cache with a different value than the one on disk.
+    fn save_with(&self, value: &T, save: impl FnOnce(&R) -> Result<()>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Err(e) = save(&self.inner) {
+            // The file may or may not have been written.
+            state.remove(value.cache_id());
+            state.complete = false;
+            return Err(e);
+        }
+        state.insert(value.clone(), self.budget);
+        Ok(())
+    }
+
+    fn delete_with(&self, id: &str, delete: impl FnOnce(&R) -> Result<()>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let result = delete(&self.inner);
+        state.remove(id);
+        if result.is_err() {
+            state.complete = false;
+        }
+        result
+    }
+}
+
+// Compares by the serialized form, as the entities do not implement `Eq`.
+fn same<T: Serialize>(a: &T, b: &T) -> bool {
+    match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+impl<R: ScratchRepository> CachingRepository<R, Scratch> {
+    /// Picks up a change made to the item outside the cache.
+    pub fn refresh(&self, id: &str) -> Refreshed<Scratch> {
+        self.refresh_with(id, |inner| inner.load(id))
+    }
+}
+
+impl<R: ScratchRepository> ScratchRepository for CachingRepository<R, Scratch> {
+    fn save(&self, scratch: &Scratch) -> Result<()> {
+        self.save_with(scratch, |inner| inner.save(scratch))
+    }
+
+    fn load(&self, id: &str) -> Result<Scratch> {
+        self.load_with(id, |inner| inner.load(id))
+    }
+
+    fn list(&self) -> Result<Vec<Scratch>> {
+        self.list_with(|inner| inner.list())
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        self.delete_with(id, |inner| inner.delete(id))
+    }
+}
+
+impl<R: ProjectRepository> CachingRepository<R, Project> {
+    /// Picks up a change made to the item outside the cache.
+    pub fn refresh(&self, id: &str) -> Refreshed<Project> {
+        self.refresh_with(id, |inner| inner.load(id))
+    }
+}
+
+impl<R: ProjectRepository> ProjectRepository for CachingRepository<R, Project> {
+    fn save(&self, project: &Project) -> Result<()> {
+        self.save_with(project, |inner| inner.save(project))
+    }
+
+    fn load(&self, id: &str) -> Result<Project> {
+        self.load_with(id, |inner| inner.load(id))
+    }
+
+    fn list(&self) -> Result<Vec<Project>> {
+        self.list_with(|inner| inner.list())
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        self.delete_with(id, |inner| inner.delete(id))
+    }
+}
+
+impl<R: TemplateRepository> CachingRepository<R, Template> {
+    /// Picks up a change made to the item outside the cache.
+    pub fn refresh(&self, id: &str) -> Refreshed<Template> {
+        self.refresh_with(id, |inner| inner.load(id))
+    }
+}
+
+impl<R: TemplateRepository> TemplateRepository for CachingRepository<R, Template> {
+    fn save(&self, template: &Template) -> Result<()> {
+        self.save_with(template, |inner| inner.save(template))
+    }
+
+    fn load(&self, id: &str) -> Result<Template> {
+        self.load_with(id, |inner| inner.load(id))
+    }
+
+    fn list(&self) -> Result<Vec<Template>> {
+        self.list_with(|inner| inner.list())
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        self.delete_with(id, |inner| inner.delete(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CoreError, EntityKind};
+    use chrono::Utc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingRepo {
+        scratches: Mutex<HashMap<String, Scratch>>,
+        reads: AtomicUsize,
+    }
+
+    impl ScratchRepository for CountingRepo {
+        fn save(&self, scratch: &Scratch) -> Result<()> {
+            self.scratches
+                .lock()
+                .unwrap()
+                .insert(scratch.id.clone(), scratch.clone());
+            Ok(())
+        }
+
+        fn load(&self, id: &str) -> Result<Scratch> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            self.scratches
+                .lock()
+                .unwrap()
+                .get(id)
+                .cloned()
+                .ok_or_else(|| CoreError::not_found(EntityKind::Scratch, id))
+        }
+
+        fn list(&self) -> Result<Vec<Scratch>> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            Ok(self.scratches.lock().unwrap().values().cloned().collect())
+        }
+
+        fn delete(&self, id: &str) -> Result<()> {
+            self.scratches.lock().unwrap().remove(id);
+            Ok(())
+        }
+    }
+
+    fn scratch(id: &str, content: &str) -> Scratch {
+        Scratch {
+            id: id.to_string(),
+            title: id.to_string(),
+            content: content.to_string(),
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            tags: vec![],
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_reads_are_served_from_memory_until_invalidated() {
+        let cache = CachingRepository::new(CountingRepo::default(), 1 << 20);
+        cache.save(&scratch("a", "one")).unwrap();
+        cache.inner.save(&scratch("b", "two")).unwrap();
+
+        assert_eq!(cache.list().unwrap().len(), 2);
+        assert_eq!(cache.list().unwrap().len(), 2);
+        assert_eq!(cache.load("b").unwrap().content, "two");
+        assert_eq!(cache.inner.reads.load(Ordering::SeqCst), 1);
+
+        // Changed behind the cache's back.
+        cache.inner.save(&scratch("b", "changed")).unwrap();
+        assert_eq!(cache.load("b").unwrap().content, "two");
+        cache.invalidate("b");
+        assert_eq!(cache.load("b").unwrap().content, "changed");
+
+        cache.delete("a").unwrap();
+        assert!(cache.load("a").is_err());
+        let metrics = cache.metrics();
+        assert_eq!((metrics.hits, metrics.misses), (3, 3));
+
+        // A refreshed item is re-read without giving up on the full list.
+        cache.list().unwrap();
+        cache.inner.save(&scratch("c", "new")).unwrap();
+        cache.refresh("c");
+        cache.inner.delete("b").unwrap();
+        cache.refresh("b");
+        let ids: Vec<_> = cache.list().unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec!["c"]);
+        assert_eq!(cache.metrics().hits, 4);
+    }
+
+    #[test]
+    fn test_refresh_reports_only_changes_made_elsewhere() {
+        let cache = CachingRepository::new(CountingRepo::default(), 1 << 20);
+        cache.save(&scratch("a", "one")).unwrap();
+        assert!(matches!(cache.refresh("a"), Refreshed::Unchanged));
+
+        cache.inner.save(&scratch("a", "edited")).unwrap();
+        assert!(matches!(cache.refresh("a"), Refreshed::Changed(s) if s.content == "edited"));
+        assert!(matches!(cache.refresh("a"), Refreshed::Unchanged));
+
+        cache.inner.delete("a").unwrap();
+        assert!(matches!(cache.refresh("a"), Refreshed::Removed));
+
+        // Deleted through the cache, which holds every item.
+        cache.save(&scratch("b", "two")).unwrap();
+        cache.list().unwrap();
+        cache.delete("b").unwrap();
+        assert!(matches!(cache.refresh("b"), Refreshed::Unchanged));
+    }
+
+    #[test]
+    fn test_budget_evicts_least_recently_used() {
+        let budget = scratch("a", "x").weight() * 2;
+        let cache = CachingRepository::new(CountingRepo::default(), budget);
+        for id in ["a", "b", "c"] {
+            cache.save(&scratch(id, "x")).unwrap();
+        }
+        let metrics = cache.metrics();
+        assert_eq!((metrics.entries, metrics.evictions), (2, 1));
+        assert!(metrics.bytes <= budget);
+
+        // "a" was evicted, so listing cannot be answered from memory.
+        assert_eq!(cache.list().unwrap().len(), 3);
+        assert_eq!(cache.metrics().misses, 1);
+    }
+}
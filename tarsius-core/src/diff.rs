@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+
+/// Beyond this many edits the changed middle of two texts is shown as one
+/// deletion and one insertion, which bounds the time and memory spent.
+const MAX_EDITS: usize = 4000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// A run of text that both sides share, or that only one side has.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffSpan {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// Compares two texts word by word. Whitespace runs count as words, so the
+/// `Equal` and `Delete` spans joined give `old` and the `Equal` and `Insert`
+/// spans joined give `new`.
+pub fn word_diff(old: &str, new: &str) -> Vec<DiffSpan> {
+    let a = tokenize(old);
+    let b = tokenize(new);
+
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut spans = Vec::new();
+    push_all(&mut spans, DiffOp::Equal, &a[..prefix]);
+    match edit_script(a_mid, b_mid) {
+        Some(script) => {
+            for (op, index) in script {
+                let token = match op {
+                    DiffOp::Insert => b_mid[index],
+                    _ => a_mid[index],
+                };
+                push(&mut spans, op, token);
+            }
+        }
+        None => {
+            push_all(&mut spans, DiffOp::Delete, a_mid);
+            push_all(&mut spans, DiffOp::Insert, b_mid);
+        }
+    }
+    push_all(&mut spans, DiffOp::Equal, &a[a.len() - suffix..]);
+    spans
+}
+
+/// Splits `text` into alternating runs of whitespace and of other characters.
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_space = None;
+    for (i, c) in text.char_indices() {
+        let space = c.is_whitespace();
+        if in_space.is_some_and(|s| s != space) {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        in_space = Some(space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+fn push(spans: &mut Vec<DiffSpan>, op: DiffOp, token: &str) {
+    match spans.last_mut() {
+        Some(last) if last.op == op => last.text.push_str(token),
+        _ => spans.push(DiffSpan {
+            op,
+            text: token.to_string(),
+        }),
+    }
+}
+
+fn push_all(spans: &mut Vec<DiffSpan>, op: DiffOp, tokens: &[&str]) {
+    for token in tokens {
+        push(spans, op, token);
+    }
+}
+
+/// The shortest edit script from `a` to `b` (Myers, 1986), as operations
+/// with the index of their token in `a` (`Equal`, `Delete`) or `b`
+/// (`Insert`). `None` when it takes more than `MAX_EDITS` edits.
+fn edit_script(a: &[&str], b: &[&str]) -> Option<Vec<(DiffOp, usize)>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (a.len() + b.len()).min(MAX_EDITS) as isize;
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // The furthest-reaching x per diagonal before each round, for
+    // backtracking.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    let mut found = None;
+    'rounds: for d in 0..=max {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+                v[i + 1]
+            } else {
+                v[i - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                found = Some(d);
+                break 'rounds;
+            }
+        }
+    }
+    let depth = found?;
+
+    let mut script = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (0..=depth).rev() {
+        let before = &trace[d as usize];
+        let at = |k: isize| before[(k + d) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = if d == 0 { 0 } else { at(prev_k) };
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            script.push((DiffOp::Equal, x as usize));
+        }
+        if d > 0 {
+            if x == prev_x {
+                script.push((DiffOp::Insert, (y - 1) as usize));
+            } else {
+                script.push((DiffOp::Delete, (x - 1) as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    script.reverse();
+    Some(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn side(spans: &[DiffSpan], skip: DiffOp) -> String {
+        spans
+            .iter()
+            .filter(|s| s.op != skip)
+            .map(|s| s.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_word_diff_marks_changed_words() {
+        let old = "The quick brown fox jumps";
+        let new = "The slow brown fox leaps high";
+        let spans = word_diff(old, new);
+        assert_eq!(side(&spans, DiffOp::Insert), old);
+        assert_eq!(side(&spans, DiffOp::Delete), new);
+        let changed: Vec<(DiffOp, &str)> = spans
+            .iter()
+            .filter(|s| s.op != DiffOp::Equal)
+            .map(|s| (s.op, s.text.as_str()))
+            .collect();
+        assert_eq!(
+            changed,
+            vec![
+                (DiffOp::Delete, "quick"),
+                (DiffOp::Insert, "slow"),
+                (DiffOp::Delete, "jumps"),
+                (DiffOp::Insert, "leaps high"),
+            ]
+        );
+
+        assert!(word_diff("", "").is_empty());
+        assert_eq!(
+            word_diff("", "new text"),
+            vec![DiffSpan {
+                op: DiffOp::Insert,
+                text: "new text".to_string()
+            }]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{IntegrationMode, OutlineNode, ScratchLink};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The copy of a scratch body held by an `Include` link.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncludedCopy {
+    /// Scratch body as it was when it was included (or last synced).
+    pub original: String,
+    /// Hash of `original`, compared against the scratch to detect drift.
+    pub source_hash: String,
+    /// The included text as the project currently uses it.
+    pub text: String,
+}
+
+impl IncludedCopy {
+    pub fn of(content: &str) -> Self {
+        Self {
+            original: content.to_string(),
+            source_hash: content_hash(content),
+            text: content.to_string(),
+        }
+    }
+
+    /// Whether the scratch body has changed since this copy was taken.
+    pub fn is_stale(&self, current: &str) -> bool {
+        self.source_hash != content_hash(current)
+    }
+
+    /// Marks `current` as the new baseline, replacing the local text with
+    /// `text`.
+    pub fn resync(&mut self, current: &str, text: String) {
+        self.original = current.to_string();
+        self.source_hash = content_hash(current);
+        self.text = text;
+    }
+}
+
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// An `Include` link whose source scratch changed after it was included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftedLink {
+    pub node_id: String,
+    pub node_title: String,
+    pub scratch_id: String,
+    /// Whether the included text was edited locally since the last sync.
+    pub locally_modified: bool,
+}
+
+/// The three texts involved in reconciling a drifted include.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftView {
+    pub node_id: String,
+    pub scratch_id: String,
+    /// Scratch body at include time.
+    pub original: String,
+    /// Scratch body now.
+    pub current: String,
+    /// Included text as the project has it now.
+    pub local: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum DriftResolution {
+    /// Replace the included text with the current scratch body.
+    AcceptUpdate,
+    /// Keep the included text and stop reporting this change.
+    KeepLocal,
+    /// Use the given text, produced from the three-way view.
+    Merge(String),
+}
+
+impl DriftResolution {
+    pub fn parse(kind: &str, merged: Option<String>) -> std::result::Result<Self, String> {
+        match (kind, merged) {
+            ("AcceptUpdate", _) => Ok(DriftResolution::AcceptUpdate),
+            ("KeepLocal", _) => Ok(DriftResolution::KeepLocal),
+            ("Merge", Some(text)) => Ok(DriftResolution::Merge(text)),
+            ("Merge", None) => Err("Merge resolution requires merged text".to_string()),
+            (other, _) => Err(format!("Unknown drift resolution: {}", other)),
+        }
+    }
+}
+
+pub(crate) fn included_links_mut(node: &mut OutlineNode) -> Vec<(String, &mut ScratchLink)> {
+    let mut links = Vec::new();
+    for link in node.scratches.iter_mut() {
+        if matches!(link.mode, IntegrationMode::Include) {
+            links.push((node.id.clone(), link));
+        }
+    }
+    for child in node.children.iter_mut() {
+        links.extend(included_links_mut(child));
+    }
+    links
+}
+
+pub(crate) fn collect_drift(
+    node: &OutlineNode,
+    current: &dyn Fn(&str) -> Option<String>,
+    out: &mut Vec<DriftedLink>,
+) {
+    for link in &node.scratches {
+        let Some(copy) = &link.included else {
+            continue;
+        };
+        if let Some(content) = current(&link.scratch_id) {
+            if copy.is_stale(&content) {
+                out.push(DriftedLink {
+                    node_id: node.id.clone(),
+                    node_title: node.title.clone(),
+                    scratch_id: link.scratch_id.clone(),
+                    locally_modified: copy.text != copy.original,
+                });
+            }
+        }
+    }
+    for child in &node.children {
+        collect_drift(child, current, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_detects_source_changes() {
+        let mut copy = IncludedCopy::of("first draft");
+        assert!(!copy.is_stale("first draft"));
+        assert!(copy.is_stale("second draft"));
+
+        copy.text = "first draft, edited".to_string();
+        copy.resync("second draft", copy.text.clone());
+        assert!(!copy.is_stale("second draft"));
+        assert_eq!(copy.original, "second draft");
+        assert_eq!(copy.text, "first draft, edited");
+    }
+
+    #[test]
+    fn test_parse_resolution() {
+        assert!(matches!(
+            DriftResolution::parse("Merge", Some("x".to_string())),
+            Ok(DriftResolution::Merge(text)) if text == "x"
+        ));
+        assert!(DriftResolution::parse("Merge", None).is_err());
+        assert!(DriftResolution::parse("Overwrite", None).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// What kind of thing a `CoreError::NotFound` refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Scratch,
+    Project,
+    Template,
+    OutlineNode,
+    /// A scratch link inside an outline node; the id is `node/scratch`.
+    Link,
+    TrashEntry,
+    JournalEntry,
+    QuarantinedItem,
+    /// A saved version of a scratch; the id is `scratch/version`.
+    ScratchVersion,
+    /// A release of a project; the id is `project/release`.
+    Release,
+    Blueprint,
+}
+
+impl fmt::Display for EntityKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            EntityKind::Scratch => "Scratch",
+            EntityKind::Project => "Project",
+            EntityKind::Template => "Template",
+            EntityKind::OutlineNode => "Outline node",
+            EntityKind::Link => "Link",
+            EntityKind::TrashEntry => "Trashed item",
+            EntityKind::JournalEntry => "Unsaved draft",
+            EntityKind::QuarantinedItem => "Quarantined item",
+            EntityKind::ScratchVersion => "Scratch version",
+            EntityKind::Release => "Release",
+            EntityKind::Blueprint => "Blueprint",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One rejected input value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// Path to the offending field, e.g. `title` or `outline.children[2].id`.
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CoreError {
+    Storage(String),
+    NotFound {
+        kind: EntityKind,
+        id: String,
+    },
+    Validation(Vec<FieldError>),
+    Conflict(String),
+    Io {
+        path: String,
+        message: String,
+    },
+    /// A stored file could not be decoded. `line` and `column` are 1-based,
+    /// or 0 when unknown.
+    Parse {
+        file: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Build(String),
+    /// The workspace is held by another instance, or was opened read-only.
+    Locked(String),
+}
+
+impl CoreError {
+    pub fn not_found(kind: EntityKind, id: impl Into<String>) -> Self {
+        CoreError::NotFound {
+            kind,
+            id: id.into(),
+        }
+    }
+
+    /// A validation error for a single field.
+    pub fn invalid(field: impl Into<String>, message: impl Into<String>) -> Self {
+        CoreError::Validation(vec![FieldError::new(field, message)])
+    }
+
+    pub fn io(path: &Path, error: impl fmt::Display) -> Self {
+        CoreError::Io {
+            path: path.display().to_string(),
+            message: error.to_string(),
+        }
+    }
+
+    /// Stable identifier of the error kind, for frontends to match on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            CoreError::Storage(_) => "storage",
+            CoreError::NotFound { .. } => "not_found",
+            CoreError::Validation(_) => "validation",
+            CoreError::Conflict(_) => "conflict",
+            CoreError::Io { .. } => "io",
+            CoreError::Parse { .. } => "parse",
+            CoreError::Build(_) => "build",
+            CoreError::Locked(_) => "locked",
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, CoreError::NotFound { .. })
+    }
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CoreError::Storage(s) => write!(f, "Storage error: {}", s),
+            CoreError::NotFound { kind, id } => write!(f, "{} not found: {}", kind, id),
+            CoreError::Validation(errors) => {
+                write!(f, "Invalid input: ")?;
+                for (i, error) in errors.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}: {}", error.field, error.message)?;
+                }
+                Ok(())
+            }
+            CoreError::Conflict(s) => write!(f, "Conflict: {}", s),
+            CoreError::Io { path, message } => write!(f, "I/O error on {}: {}", path, message),
+            CoreError::Parse {
+                file,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Could not parse {}:{}:{}: {}",
+                file, line, column, message
+            ),
+            CoreError::Build(s) => write!(f, "Build failed: {}", s),
+            CoreError::Locked(s) => write!(f, "Workspace is locked: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for CoreError {}
+
+/// Serializes as `{ "code", "message", "details" }`. `code` and the shape of
+/// `details` per code are stable; `message` is English text for logs and as a
+/// fallback when the frontend has no translation.
+impl Serialize for CoreError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CoreError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        match self {
+            CoreError::Storage(s)
+            | CoreError::Conflict(s)
+            | CoreError::Build(s)
+            | CoreError::Locked(s) => state.serialize_field("details", &Reason { reason: s })?,
+            CoreError::NotFound { kind, id } => {
+                state.serialize_field("details", &NotFoundDetails { kind, id })?
+            }
+            CoreError::Validation(errors) => {
+                state.serialize_field("details", &ValidationDetails { errors })?
+            }
+            CoreError::Io { path, message } => state.serialize_field(
+                "details",
+                &IoDetails {
+                    path,
+                    reason: message,
+                },
+            )?,
+            CoreError::Parse {
+                file,
+                line,
+                column,
+                message,
+            } => state.serialize_field(
+                "details",
+                &ParseDetails {
+                    file,
+                    line: *line,
+                    column: *column,
+                    reason: message,
+                },
+            )?,
+        }
+        state.end()
+    }
+}
+
+#[derive(Serialize)]
+struct Reason<'a> {
+    reason: &'a str,
+}
+
+#[derive(Serialize)]
+struct NotFoundDetails<'a> {
+    kind: &'a EntityKind,
+    id: &'a str,
+}
+
+#[derive(Serialize)]
+struct ValidationDetails<'a> {
+    errors: &'a [FieldError],
+}
+
+#[derive(Serialize)]
+struct IoDetails<'a> {
+    path: &'a str,
+    reason: &'a str,
+}
+
+#[derive(Serialize)]
+struct ParseDetails<'a> {
+    file: &'a str,
+    line: usize,
+    column: usize,
+    reason: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_serialized_shape() {
+        let error = CoreError::not_found(EntityKind::Scratch, "abc");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": "not_found",
+                "message": "Scratch not found: abc",
+                "details": { "kind": "scratch", "id": "abc" },
+            })
+        );
+
+        let error = CoreError::Validation(vec![
+            FieldError::new("title", "must not be empty"),
+            FieldError::new("tags[1]", "duplicate tag"),
+        ]);
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["code"], "validation");
+        assert_eq!(value["details"]["errors"][1]["field"], "tags[1]");
+        assert_eq!(
+            error.to_string(),
+            "Invalid input: title: must not be empty; tags[1]: duplicate tag"
+        );
+    }
+}
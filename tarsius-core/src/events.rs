@@ -0,0 +1,101 @@
+use crate::WindowKind;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+
+/// A change to the workspace. `revision` is the `modified_at` of the saved
+/// item, so a view can tell whether the copy it holds is current.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum WorkspaceEvent {
+    ScratchCreated {
+        id: String,
+        revision: DateTime<Utc>,
+    },
+    ScratchUpdated {
+        id: String,
+        revision: DateTime<Utc>,
+    },
+    ScratchDeleted {
+        id: String,
+    },
+    ProjectCreated {
+        id: String,
+        revision: DateTime<Utc>,
+    },
+    ProjectSaved {
+        id: String,
+        revision: DateTime<Utc>,
+    },
+    ProjectDeleted {
+        id: String,
+    },
+    ReleaseCreated {
+        project_id: String,
+        release_id: String,
+    },
+    /// A template file was added, edited or removed.
+    TemplateChanged {
+        id: String,
+    },
+    TrashChanged,
+    WorkspaceImported,
+    QuarantineRestored {
+        issue_id: String,
+    },
+    WorkspaceRepaired,
+    WindowOpened {
+        label: String,
+        kind: WindowKind,
+        target_id: Option<String>,
+    },
+    WindowClosed {
+        label: String,
+    },
+    BuildFinished {
+        project_id: String,
+        tex: PathBuf,
+        missing_scratches: Vec<String>,
+    },
+}
+
+/// Fans events out to every live subscriber.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<Sender<WorkspaceEvent>>>,
+}
+
+impl EventBus {
+    pub fn subscribe(&self) -> Receiver<WorkspaceEvent> {
+        let (tx, rx) = channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Sends `event` to all subscribers, dropping those that hung up.
+    pub fn publish(&self, event: WorkspaceEvent) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_reaches_live_subscribers() {
+        let bus = EventBus::default();
+        let first = bus.subscribe();
+        let second = bus.subscribe();
+        drop(second);
+
+        bus.publish(WorkspaceEvent::TrashChanged);
+        assert_eq!(first.try_recv().unwrap(), WorkspaceEvent::TrashChanged);
+        assert_eq!(bus.subscribers.lock().unwrap().len(), 1);
+    }
+}
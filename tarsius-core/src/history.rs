@@ -0,0 +1,194 @@
+use crate::{word_diff, DiffSpan, HistorySettings, Result, Scratch};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScratchField {
+    Title,
+    Content,
+    Tags,
+    Source,
+}
+
+/// One saved state of a scratch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScratchVersion {
+    /// Counts up from 1 per scratch; numbers of pruned versions are not
+    /// reused.
+    pub version: u32,
+    pub recorded_at: DateTime<Utc>,
+    /// Fields that differ from the version before; all of them for the first.
+    pub changed: Vec<ScratchField>,
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub source: Option<String>,
+}
+
+impl ScratchVersion {
+    /// The version recording `scratch` after `previous`, or `None` when
+    /// nothing changed.
+    pub fn next(scratch: &Scratch, previous: Option<&ScratchVersion>) -> Option<Self> {
+        let changed = match previous {
+            Some(previous) => previous.changed_fields(scratch),
+            None => vec![
+                ScratchField::Title,
+                ScratchField::Content,
+                ScratchField::Tags,
+                ScratchField::Source,
+            ],
+        };
+        if changed.is_empty() {
+            return None;
+        }
+        Some(Self {
+            version: previous.map_or(1, |p| p.version + 1),
+            recorded_at: scratch.modified_at,
+            changed,
+            title: scratch.title.clone(),
+            content: scratch.content.clone(),
+            tags: scratch.tags.clone(),
+            source: scratch.source.clone(),
+        })
+    }
+
+    fn changed_fields(&self, scratch: &Scratch) -> Vec<ScratchField> {
+        let mut changed = Vec::new();
+        if self.title != scratch.title {
+            changed.push(ScratchField::Title);
+        }
+        if self.content != scratch.content {
+            changed.push(ScratchField::Content);
+        }
+        if self.tags != scratch.tags {
+            changed.push(ScratchField::Tags);
+        }
+        if self.source != scratch.source {
+            changed.push(ScratchField::Source);
+        }
+        changed
+    }
+}
+
+/// How a scratch differs between two of its versions.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VersionDiff {
+    pub from: u32,
+    pub to: u32,
+    pub title: Vec<DiffSpan>,
+    pub content: Vec<DiffSpan>,
+    pub tags_added: Vec<String>,
+    pub tags_removed: Vec<String>,
+    pub source_from: Option<String>,
+    pub source_to: Option<String>,
+}
+
+impl VersionDiff {
+    pub fn between(from: &ScratchVersion, to: &ScratchVersion) -> Self {
+        Self {
+            from: from.version,
+            to: to.version,
+            title: word_diff(&from.title, &to.title),
+            content: word_diff(&from.content, &to.content),
+            tags_added: to
+                .tags
+                .iter()
+                .filter(|t| !from.tags.contains(t))
+                .cloned()
+                .collect(),
+            tags_removed: from
+                .tags
+                .iter()
+                .filter(|t| !to.tags.contains(t))
+                .cloned()
+                .collect(),
+            source_from: from.source.clone(),
+            source_to: to.source.clone(),
+        }
+    }
+}
+
+impl HistorySettings {
+    /// Whether a save at `at` replaces `last` rather than following it.
+    pub fn merges_into(&self, last: &ScratchVersion, at: DateTime<Utc>) -> bool {
+        at - last.recorded_at < Duration::seconds(i64::from(self.merge_window_secs))
+    }
+
+    /// Drops the versions this policy does not keep from `versions`, which
+    /// are ordered oldest first.
+    pub fn prune(&self, versions: &mut Vec<ScratchVersion>, now: DateTime<Utc>) {
+        let Some(latest) = versions.last().map(|v| v.version) else {
+            return;
+        };
+        let cutoff = now - Duration::days(i64::from(self.retention_days));
+        versions.retain(|v| v.version == latest || v.recorded_at >= cutoff);
+        let excess = versions.len().saturating_sub(self.max_versions.max(1));
+        versions.drain(..excess);
+    }
+}
+
+/// Stores the versions of each scratch, independently of any version control
+/// the workspace may be under. Versions are written one at a time, so a save
+/// costs the same however long the history is.
+pub trait HistoryRepository: Send + Sync {
+    /// The versions of a scratch, oldest first; empty if it has none.
+    fn list(&self, scratch_id: &str) -> Result<Vec<ScratchVersion>>;
+    /// Stores one version, replacing a stored one with the same number.
+    fn put(&self, scratch_id: &str, version: &ScratchVersion) -> Result<()>;
+    /// Drops the given versions; numbers that are not stored are ignored.
+    fn remove(&self, scratch_id: &str, versions: &[u32]) -> Result<()>;
+    /// Drops the whole history of a scratch.
+    fn delete(&self, scratch_id: &str) -> Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(title: &str, content: &str) -> Scratch {
+        let now = Utc::now();
+        Scratch {
+            id: "s1".to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            created_at: now,
+            modified_at: now,
+            tags: vec!["a".to_string()],
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_versions_record_changed_fields_and_prune() {
+        let first = ScratchVersion::next(&scratch("Note", "one"), None).unwrap();
+        assert_eq!(first.version, 1);
+        assert_eq!(first.changed.len(), 4);
+        assert!(ScratchVersion::next(&scratch("Note", "one"), Some(&first)).is_none());
+        let second = ScratchVersion::next(&scratch("Note", "one two"), Some(&first)).unwrap();
+        assert_eq!(second.version, 2);
+        assert_eq!(second.changed, vec![ScratchField::Content]);
+
+        let diff = VersionDiff::between(&first, &second);
+        assert_eq!(diff.content.last().unwrap().text, " two");
+        assert!(diff.tags_added.is_empty());
+
+        let mut versions: Vec<ScratchVersion> = (1..=5)
+            .map(|n| ScratchVersion {
+                version: n,
+                recorded_at: Utc::now() - Duration::days(100 - i64::from(n)),
+                ..first.clone()
+            })
+            .collect();
+        let settings = HistorySettings {
+            max_versions: 3,
+            retention_days: 90,
+            merge_window_secs: 60,
+        };
+        assert!(settings.merges_into(&versions[4], versions[4].recorded_at));
+        assert!(!settings.merges_into(&versions[3], versions[4].recorded_at));
+        settings.prune(&mut versions, Utc::now());
+        // All are past the retention period, but the latest always stays.
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version, 5);
+    }
+}
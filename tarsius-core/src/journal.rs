@@ -0,0 +1,69 @@
+use crate::{ProjectDto, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum JournalKind {
+    Scratch,
+    Project,
+}
+
+impl FromStr for JournalKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "Scratch" => Ok(JournalKind::Scratch),
+            "Project" => Ok(JournalKind::Project),
+            other => Err(format!("Unknown journal kind: {}", other)),
+        }
+    }
+}
+
+/// Editor state of a scratch that may not have been saved yet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScratchDraft {
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub source: Option<String>,
+}
+
+/// Unsaved edits, kept as the frontend sent them. They are only validated
+/// when recovered, so a half-typed title does not stop them being recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "draft")]
+pub enum Draft {
+    Scratch(ScratchDraft),
+    Project(ProjectDto),
+}
+
+/// The latest pending edit of one scratch or project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: String,
+    pub recorded_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub draft: Draft,
+}
+
+impl JournalEntry {
+    pub fn kind(&self) -> JournalKind {
+        match self.draft {
+            Draft::Scratch(_) => JournalKind::Scratch,
+            Draft::Project(_) => JournalKind::Project,
+        }
+    }
+}
+
+/// Write-ahead journal of unsaved edits.
+///
+/// Recording must be cheap enough to happen on every keystroke; an entry
+/// replaces any earlier one for the same item and is discarded once the item
+/// is saved. Entries still present at startup are offered for recovery.
+pub trait JournalRepository: Send + Sync {
+    fn record(&self, entry: &JournalEntry) -> Result<()>;
+    fn list(&self) -> Result<Vec<JournalEntry>>;
+    fn discard(&self, kind: JournalKind, id: &str) -> Result<()>;
+}
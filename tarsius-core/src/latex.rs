@@ -0,0 +1,382 @@
+use crate::{IntegrationMode, OutlineNode, Project, Scratch, ScratchLink, Template};
+use std::collections::HashMap;
+
+/// Placeholders recognised in `Template::content`.
+pub const TITLE_PLACEHOLDER: &str = "{{title}}";
+pub const BODY_PLACEHOLDER: &str = "{{body}}";
+
+const DEFAULT_PREAMBLE: &str = "\\documentclass{article}\n\\usepackage[utf8]{inputenc}\n";
+
+const SECTIONING: [&str; 6] = [
+    "chapter",
+    "section",
+    "subsection",
+    "subsubsection",
+    "paragraph",
+    "subparagraph",
+];
+
+/// The LaTeX document class a template uses, as far as the outline cares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentClass {
+    Article,
+    /// `report` or `book`, which have chapters above sections.
+    Book,
+}
+
+impl DocumentClass {
+    /// Reads the class from the `\documentclass` line of a template. Unknown
+    /// classes, and templates without one, are treated as articles.
+    pub fn of_template(content: &str) -> Self {
+        let Some(start) = content.find("\\documentclass") else {
+            return DocumentClass::Article;
+        };
+        let rest = &content[start..];
+        let name = rest
+            .find('{')
+            .and_then(|open| {
+                let close = rest[open..].find('}')?;
+                Some(rest[open + 1..open + close].trim())
+            })
+            .unwrap_or("");
+        match name {
+            "book" | "report" | "memoir" | "scrbook" | "scrreprt" => DocumentClass::Book,
+            _ => DocumentClass::Article,
+        }
+    }
+
+    /// Sectioning commands from the outermost level down.
+    fn sectioning(self) -> &'static [&'static str] {
+        match self {
+            DocumentClass::Article => &SECTIONING[1..],
+            DocumentClass::Book => &SECTIONING,
+        }
+    }
+
+    /// How many outline levels below the root can be typeset as headings.
+    pub fn max_depth(self) -> usize {
+        self.sectioning().len()
+    }
+}
+
+/// Generated LaTeX sources for a project.
+#[derive(Debug, Clone)]
+pub struct LatexSource {
+    pub tex: String,
+    /// BibTeX entries for scratches linked as references, if there are any.
+    pub bib: Option<String>,
+    /// Linked scratch ids that could not be resolved.
+    pub missing_scratches: Vec<String>,
+}
+
+/// Turns a project outline into LaTeX source.
+///
+/// The outline root becomes the document itself; its children become
+/// sections, their children subsections, and so on. A template may place the
+/// generated text with `{{body}}` and the project title with `{{title}}`;
+/// a template without `{{body}}` is treated as a preamble.
+pub struct LatexSourceBuilder<'a> {
+    template: Option<&'a Template>,
+    class: DocumentClass,
+    scratches: HashMap<&'a str, &'a Scratch>,
+}
+
+impl<'a> LatexSourceBuilder<'a> {
+    pub fn new(template: Option<&'a Template>, scratches: &'a [Scratch]) -> Self {
+        Self {
+            template,
+            class: template
+                .map(|t| DocumentClass::of_template(&t.content))
+                .unwrap_or(DocumentClass::Article),
+            scratches: scratches.iter().map(|s| (s.id.as_str(), s)).collect(),
+        }
+    }
+
+    pub fn build(&self, project: &Project) -> LatexSource {
+        let mut state = BuildState::default();
+        let mut body = String::new();
+        self.write_node_body(&project.outline, &mut body, &mut state);
+        for child in &project.outline.children {
+            self.write_node(child, 0, &mut body, &mut state);
+        }
+        if !state.appendices.is_empty() {
+            body.push_str("\n\\appendix\n");
+            for scratch in &state.appendices {
+                body.push_str(&format!(
+                    "\n\\section{{{}}}\n{}\n",
+                    escape_latex(&scratch.title),
+                    scratch.content.trim_end()
+                ));
+            }
+        }
+        let bib = if state.references.is_empty() {
+            None
+        } else {
+            body.push_str("\n\\bibliographystyle{plain}\n\\bibliography{references}\n");
+            Some(
+                state
+                    .references
+                    .iter()
+                    .map(|scratch| bib_entry(scratch))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+        };
+
+        let title = escape_latex(&project.title);
+        let tex = match self.template.map(|t| t.content.as_str()) {
+            Some(template) if template.contains(BODY_PLACEHOLDER) => template
+                .replace(TITLE_PLACEHOLDER, &title)
+                .replace(BODY_PLACEHOLDER, &body),
+            preamble => format!(
+                "{}\n\\title{{{}}}\n\n\\begin{{document}}\n\\maketitle\n{}\n\\end{{document}}\n",
+                preamble
+                    .unwrap_or(DEFAULT_PREAMBLE)
+                    .replace(TITLE_PLACEHOLDER, &title)
+                    .trim_end(),
+                title,
+                body
+            ),
+        };
+        LatexSource {
+            tex,
+            bib,
+            missing_scratches: state.missing,
+        }
+    }
+
+    fn write_node(
+        &self,
+        node: &OutlineNode,
+        depth: usize,
+        out: &mut String,
+        state: &mut BuildState<'a>,
+    ) {
+        let sectioning = self.class.sectioning();
+        let command = sectioning[depth.min(sectioning.len() - 1)];
+        out.push_str(&format!(
+            "\n\\{}{{{}}}\n",
+            command,
+            escape_latex(&node.title)
+        ));
+        self.write_node_body(node, out, state);
+        for child in &node.children {
+            self.write_node(child, depth + 1, out, state);
+        }
+    }
+
+    fn write_node_body(&self, node: &OutlineNode, out: &mut String, state: &mut BuildState<'a>) {
+        if let Some(content) = node.content.as_deref().filter(|c| !c.trim().is_empty()) {
+            out.push_str(content.trim_end());
+            out.push('\n');
+        }
+        for link in &node.scratches {
+            self.write_link(link, out, state);
+        }
+    }
+
+    fn write_link(&self, link: &ScratchLink, out: &mut String, state: &mut BuildState<'a>) {
+        if let (IntegrationMode::Include, Some(copy)) = (&link.mode, &link.included) {
+            out.push_str(copy.text.trim_end());
+            out.push('\n');
+            return;
+        }
+        let Some(scratch) = self.scratches.get(link.scratch_id.as_str()).copied() else {
+            if !state.missing.contains(&link.scratch_id) {
+                state.missing.push(link.scratch_id.clone());
+            }
+            return;
+        };
+        if matches!(link.mode, IntegrationMode::Include) || link.insertion.body {
+            out.push_str(scratch.content.trim_end());
+            out.push('\n');
+        }
+        if matches!(link.mode, IntegrationMode::Include) {
+            return;
+        }
+        if link.insertion.footnote {
+            out.push_str(&format!("\\footnote{{{}}}\n", scratch.content.trim()));
+        }
+        if link.insertion.reference {
+            out.push_str(&format!("\\cite{{{}}}\n", scratch.id));
+            if !state.references.iter().any(|s| s.id == scratch.id) {
+                state.references.push(scratch);
+            }
+        }
+        if link.insertion.appendix && !state.appendices.iter().any(|s| s.id == scratch.id) {
+            state.appendices.push(scratch);
+        }
+    }
+}
+
+#[derive(Default)]
+struct BuildState<'a> {
+    appendices: Vec<&'a Scratch>,
+    references: Vec<&'a Scratch>,
+    missing: Vec<String>,
+}
+
+fn bib_entry(scratch: &Scratch) -> String {
+    let mut entry = format!(
+        "@misc{{{},\n  title = {{{}}}",
+        scratch.id,
+        escape_latex(&scratch.title)
+    );
+    if let Some(source) = &scratch.source {
+        entry.push_str(&format!(",\n  howpublished = {{{}}}", escape_latex(source)));
+    }
+    entry.push_str("\n}\n");
+    entry
+}
+
+/// Escapes characters with special meaning in LaTeX text.
+pub fn escape_latex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IncludedCopy, InsertionFlags, ProjectSettings};
+    use chrono::Utc;
+
+    fn scratch(id: &str, content: &str) -> Scratch {
+        Scratch {
+            id: id.to_string(),
+            title: format!("Scratch {}", id),
+            content: content.to_string(),
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            tags: vec![],
+            source: Some("Doe 2020".to_string()),
+        }
+    }
+
+    fn link(id: &str, mode: IntegrationMode, flags: [bool; 4]) -> ScratchLink {
+        ScratchLink {
+            scratch_id: id.to_string(),
+            mode,
+            insertion: InsertionFlags {
+                body: flags[0],
+                footnote: flags[1],
+                reference: flags[2],
+                appendix: flags[3],
+            },
+            included: None,
+        }
+    }
+
+    fn project(children: Vec<OutlineNode>) -> Project {
+        Project {
+            id: "p".to_string(),
+            title: "Q&A".to_string(),
+            outline: OutlineNode {
+                id: "root".to_string(),
+                title: "Root".to_string(),
+                content: Some("Abstract.".to_string()),
+                children,
+                scratches: vec![],
+            },
+            settings: ProjectSettings {
+                template_id: "t".to_string(),
+                output_dir: "out".to_string(),
+            },
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            archived: false,
+        }
+    }
+
+    #[test]
+    fn test_build_sections_and_links() {
+        let scratches = vec![scratch("a", "Body A"), scratch("b", "Note B")];
+        let mut included = link("a", IntegrationMode::Include, [true, false, false, false]);
+        included.included = Some(IncludedCopy::of("Included A"));
+        let project = project(vec![OutlineNode {
+            id: "intro".to_string(),
+            title: "Intro_1".to_string(),
+            content: None,
+            children: vec![OutlineNode {
+                id: "bg".to_string(),
+                title: "Background".to_string(),
+                content: Some("Text.".to_string()),
+                children: vec![],
+                scratches: vec![
+                    link("b", IntegrationMode::Link, [false, true, true, true]),
+                    link("gone", IntegrationMode::Link, [true, false, false, false]),
+                ],
+            }],
+            scratches: vec![included],
+        }]);
+
+        let source = LatexSourceBuilder::new(None, &scratches).build(&project);
+
+        assert!(source.tex.starts_with("\\documentclass{article}"));
+        assert!(source.tex.contains("\\title{Q\\&A}"));
+        assert!(source.tex.contains("Abstract.\n"));
+        assert!(source.tex.contains("\\section{Intro\\_1}\nIncluded A\n"));
+        assert!(source
+            .tex
+            .contains("\\subsection{Background}\nText.\n\\footnote{Note B}\n\\cite{b}\n"));
+        assert!(source
+            .tex
+            .contains("\\appendix\n\n\\section{Scratch b}\nNote B\n"));
+        assert!(source.tex.ends_with("\\end{document}\n"));
+        assert!(source.bib.unwrap().contains("@misc{b,"));
+        assert_eq!(source.missing_scratches, vec!["gone".to_string()]);
+    }
+
+    #[test]
+    fn test_template_placeholders() {
+        let template = Template {
+            id: "t".to_string(),
+            name: "Custom".to_string(),
+            content: "% {{title}}\nBEGIN\n{{body}}END\n".to_string(),
+        };
+        let source = LatexSourceBuilder::new(Some(&template), &[]).build(&project(vec![]));
+        assert_eq!(source.tex, "% Q\\&A\nBEGIN\nAbstract.\nEND\n");
+        assert!(source.bib.is_none());
+    }
+
+    #[test]
+    fn test_document_class() {
+        assert_eq!(
+            DocumentClass::of_template("\\documentclass[12pt]{ book }\n"),
+            DocumentClass::Book
+        );
+        assert_eq!(
+            DocumentClass::of_template("\\documentclass{beamer}"),
+            DocumentClass::Article
+        );
+        assert_eq!(DocumentClass::Article.max_depth(), 5);
+        assert_eq!(DocumentClass::Book.max_depth(), 6);
+
+        let template = Template {
+            id: "t".to_string(),
+            name: "Book".to_string(),
+            content: "\\documentclass{book}".to_string(),
+        };
+        let chapter = OutlineNode {
+            id: "c".to_string(),
+            title: "One".to_string(),
+            content: None,
+            children: vec![],
+            scratches: vec![],
+        };
+        let source = LatexSourceBuilder::new(Some(&template), &[]).build(&project(vec![chapter]));
+        assert!(source.tex.contains("\\chapter{One}"));
+    }
+}
@@ -1,8 +1,53 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::result;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 use uuid::Uuid;
 
+mod backlinks;
+mod blocking;
+mod blueprint;
+mod cache;
+mod diff;
+mod drift;
+mod error;
+mod events;
+mod history;
+mod journal;
+mod latex;
+mod listing;
+mod outline_diff;
+mod references;
+mod release;
+mod search;
+mod settings;
+mod trash;
+mod validation;
+mod windows;
+
+pub use backlinks::*;
+pub use blocking::*;
+pub use blueprint::*;
+pub use cache::*;
+pub use diff::*;
+pub use drift::*;
+pub use error::*;
+pub use events::*;
+pub use history::*;
+pub use journal::*;
+pub use latex::*;
+pub use listing::*;
+pub use outline_diff::*;
+pub use references::*;
+pub use release::*;
+pub use search::*;
+pub use settings::*;
+pub use trash::*;
+pub use validation::*;
+pub use windows::*;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Scratch {
     pub id: String,
@@ -22,9 +67,13 @@ pub struct Project {
     pub settings: ProjectSettings,
     pub created_at: DateTime<Utc>,
     pub modified_at: DateTime<Utc>,
+    /// Archived projects are left out of project lists unless asked for,
+    /// but are still found by search.
+    #[serde(default)]
+    pub archived: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OutlineNode {
     pub id: String,
     pub title: String,
@@ -33,20 +82,56 @@ pub struct OutlineNode {
     pub scratches: Vec<ScratchLink>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl OutlineNode {
+    pub fn find(&self, id: &str) -> Option<&OutlineNode> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(id))
+    }
+
+    pub fn find_mut(&mut self, id: &str) -> Option<&mut OutlineNode> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children
+            .iter_mut()
+            .find_map(|child| child.find_mut(id))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ScratchLink {
     pub scratch_id: String,
     pub mode: IntegrationMode,
     pub insertion: InsertionFlags,
+    /// Copy of the scratch body, kept for `Include` links.
+    #[serde(default)]
+    pub included: Option<IncludedCopy>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum IntegrationMode {
     Include,
     Link,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl FromStr for IntegrationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
+        match s {
+            "Include" => Ok(IntegrationMode::Include),
+            "Link" => Ok(IntegrationMode::Link),
+            other => Err(format!(
+                "unknown integration mode \"{}\"; expected \"Include\" or \"Link\"",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct InsertionFlags {
     pub body: bool,
     pub footnote: bool,
@@ -67,23 +152,6 @@ pub struct ProjectSettings {
     pub output_dir: String,
 }
 
-#[derive(Debug)]
-pub enum CoreError {
-    Storage(String),
-    NotFound(String),
-}
-
-impl std::fmt::Display for CoreError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            CoreError::Storage(s) => write!(f, "Storage error: {}", s),
-            CoreError::NotFound(s) => write!(f, "Not found: {}", s),
-        }
-    }
-}
-
-impl std::error::Error for CoreError {}
-
 pub type Result<T> = result::Result<T, CoreError>;
 
 pub trait ScratchRepository: Send + Sync {
@@ -107,14 +175,176 @@ pub trait TemplateRepository: Send + Sync {
     fn delete(&self, id: &str) -> Result<()>;
 }
 
+// A shared repository, such as a `CachingRepository`, can be handed to
+// several managers.
+impl<R: ScratchRepository + ?Sized> ScratchRepository for Arc<R> {
+    fn save(&self, scratch: &Scratch) -> Result<()> {
+        (**self).save(scratch)
+    }
+    fn load(&self, id: &str) -> Result<Scratch> {
+        (**self).load(id)
+    }
+    fn list(&self) -> Result<Vec<Scratch>> {
+        (**self).list()
+    }
+    fn delete(&self, id: &str) -> Result<()> {
+        (**self).delete(id)
+    }
+}
+
+impl<R: ProjectRepository + ?Sized> ProjectRepository for Arc<R> {
+    fn save(&self, project: &Project) -> Result<()> {
+        (**self).save(project)
+    }
+    fn load(&self, id: &str) -> Result<Project> {
+        (**self).load(id)
+    }
+    fn list(&self) -> Result<Vec<Project>> {
+        (**self).list()
+    }
+    fn delete(&self, id: &str) -> Result<()> {
+        (**self).delete(id)
+    }
+}
+
+impl<R: TemplateRepository + ?Sized> TemplateRepository for Arc<R> {
+    fn save(&self, template: &Template) -> Result<()> {
+        (**self).save(template)
+    }
+    fn load(&self, id: &str) -> Result<Template> {
+        (**self).load(id)
+    }
+    fn list(&self) -> Result<Vec<Template>> {
+        (**self).list()
+    }
+    fn delete(&self, id: &str) -> Result<()> {
+        (**self).delete(id)
+    }
+}
+
 // Managers
 pub struct ScratchManager {
     repo: Box<dyn ScratchRepository>,
+    trash: Option<Box<dyn TrashRepository>>,
+    projects: Option<Box<dyn ProjectRepository>>,
+    events: Option<Arc<EventBus>>,
+    history: Option<(Box<dyn HistoryRepository>, HistorySettings)>,
 }
 
 impl ScratchManager {
     pub fn new(repo: Box<dyn ScratchRepository>) -> Self {
-        Self { repo }
+        Self {
+            repo,
+            trash: None,
+            projects: None,
+            events: None,
+            history: None,
+        }
+    }
+
+    /// Routes deletions through `trash` instead of removing scratches outright.
+    pub fn with_trash(mut self, trash: Box<dyn TrashRepository>) -> Self {
+        self.trash = Some(trash);
+        self
+    }
+
+    /// Lets the manager find and fix up outline links to the scratches it deletes.
+    pub fn with_projects(mut self, projects: Box<dyn ProjectRepository>) -> Self {
+        self.projects = Some(projects);
+        self
+    }
+
+    /// Publishes every change this manager makes to `events`.
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Records a version of every scratch saved, pruned according to
+    /// `settings`.
+    pub fn with_history(
+        mut self,
+        history: Box<dyn HistoryRepository>,
+        settings: HistorySettings,
+    ) -> Self {
+        self.history = Some((history, settings));
+        self
+    }
+
+    fn publish(&self, event: WorkspaceEvent) {
+        if let Some(events) = &self.events {
+            events.publish(event);
+        }
+    }
+
+    fn save_project(&self, project: &Project) -> Result<()> {
+        match &self.projects {
+            Some(projects) => projects.save(project),
+            None => Ok(()),
+        }
+    }
+
+    /// Appends `scratch` to its history. A scratch saved before history was
+    /// kept gets `previous`, its state before the edit, as first version.
+    ///
+    /// With `merge`, a save within the merge window of the last version
+    /// replaces it. The first version is never replaced, so the state a
+    /// scratch started from stays in the history.
+    fn record_version(
+        &self,
+        scratch: &Scratch,
+        previous: Option<&Scratch>,
+        merge: bool,
+    ) -> Result<()> {
+        let Some((history, settings)) = &self.history else {
+            return Ok(());
+        };
+        let mut versions = history.list(&scratch.id)?;
+        if versions.is_empty() {
+            if let Some(first) = previous.and_then(|p| ScratchVersion::next(p, None)) {
+                history.put(&scratch.id, &first)?;
+                versions.push(first);
+            }
+        }
+        if ScratchVersion::next(scratch, versions.last()).is_none() {
+            return Ok(());
+        }
+        let replaced = match versions.last() {
+            Some(last)
+                if merge
+                    && versions.len() > 1
+                    && settings.merges_into(last, scratch.modified_at) =>
+            {
+                versions.pop()
+            }
+            _ => None,
+        };
+        match ScratchVersion::next(scratch, versions.last()) {
+            Some(mut version) => {
+                if let Some(replaced) = &replaced {
+                    version.version = replaced.version;
+                }
+                history.put(&scratch.id, &version)?;
+                versions.push(version);
+            }
+            // The merged edits were undone; the version before stands.
+            None => {
+                let replaced = replaced.map(|v| v.version);
+                history.remove(&scratch.id, replaced.as_slice())?;
+            }
+        }
+
+        let stored: Vec<u32> = versions.iter().map(|v| v.version).collect();
+        settings.prune(&mut versions, scratch.modified_at);
+        let dropped: Vec<u32> = stored
+            .into_iter()
+            .filter(|n| !versions.iter().any(|v| v.version == *n))
+            .collect();
+        if dropped.is_empty() {
+            Ok(())
+        } else {
+            history.remove(&scratch.id, &dropped)
+        }
     }
 
     pub fn create(
@@ -126,7 +356,7 @@ impl ScratchManager {
     ) -> Result<Scratch> {
         let id = Uuid::new_v4().to_string();
         let now = Utc::now();
-        let scratch = Scratch {
+        let mut scratch = Scratch {
             id: id.clone(),
             title,
             content,
@@ -135,7 +365,13 @@ impl ScratchManager {
             tags,
             source,
         };
+        validate_scratch(&mut scratch)?;
         self.repo.save(&scratch)?;
+        self.record_version(&scratch, None, false)?;
+        self.publish(WorkspaceEvent::ScratchCreated {
+            id: scratch.id.clone(),
+            revision: scratch.modified_at,
+        });
         Ok(scratch)
     }
 
@@ -146,8 +382,22 @@ impl ScratchManager {
         content: Option<String>,
         tags: Option<Vec<String>>,
         source: Option<Option<String>>,
+    ) -> Result<Scratch> {
+        self.apply_update(id, title, content, tags, source, true)
+    }
+
+    // `merge` is passed on to `record_version`.
+    fn apply_update(
+        &self,
+        id: String,
+        title: Option<String>,
+        content: Option<String>,
+        tags: Option<Vec<String>>,
+        source: Option<Option<String>>,
+        merge: bool,
     ) -> Result<Scratch> {
         let mut scratch = self.repo.load(&id)?;
+        let previous = scratch.clone();
         if let Some(t) = title {
             scratch.title = t;
         }
@@ -160,8 +410,14 @@ impl ScratchManager {
         if let Some(s) = source {
             scratch.source = s;
         }
+        validate_scratch(&mut scratch)?;
         scratch.modified_at = Utc::now();
         self.repo.save(&scratch)?;
+        self.record_version(&scratch, Some(&previous), merge)?;
+        self.publish(WorkspaceEvent::ScratchUpdated {
+            id: scratch.id.clone(),
+            revision: scratch.modified_at,
+        });
         Ok(scratch)
     }
 
@@ -173,18 +429,197 @@ impl ScratchManager {
         self.repo.list()
     }
 
-    pub fn delete(&self, id: &str) -> Result<()> {
-        self.repo.delete(id)
+    /// The recorded versions of a scratch, oldest first.
+    pub fn versions(&self, id: &str) -> Result<Vec<ScratchVersion>> {
+        self.repo.load(id)?;
+        match &self.history {
+            Some((history, _)) => history.list(id),
+            None => Ok(vec![]),
+        }
+    }
+
+    pub fn version(&self, id: &str, version: u32) -> Result<ScratchVersion> {
+        find_version(self.versions(id)?, id, version)
+    }
+
+    /// Word-level changes from version `from` to version `to`.
+    pub fn diff_versions(&self, id: &str, from: u32, to: u32) -> Result<VersionDiff> {
+        let versions = self.versions(id)?;
+        Ok(VersionDiff::between(
+            &find_version(versions.clone(), id, from)?,
+            &find_version(versions, id, to)?,
+        ))
+    }
+
+    /// Brings back the fields of an earlier version. This is saved as a new
+    /// update, never merged into the last version, so the versions in
+    /// between stay in the history.
+    pub fn restore_version(&self, id: &str, version: u32) -> Result<Scratch> {
+        let version = self.version(id, version)?;
+        self.apply_update(
+            id.to_string(),
+            Some(version.title),
+            Some(version.content),
+            Some(version.tags),
+            Some(version.source),
+            false,
+        )
+    }
+
+    /// Drops the history of a scratch that is gone for good.
+    pub fn forget_history(&self, id: &str) -> Result<()> {
+        match &self.history {
+            Some((history, _)) => history.delete(id),
+            None => Ok(()),
+        }
+    }
+
+    /// Lists every outline node, across all projects, that links to the scratch.
+    pub fn references(&self, id: &str) -> Result<Vec<ScratchReference>> {
+        let Some(projects) = &self.projects else {
+            return Ok(vec![]);
+        };
+        Ok(projects
+            .list()?
+            .iter()
+            .flat_map(|project| find_references(project, id))
+            .collect())
+    }
+
+    /// Deletes a scratch, handling links to it according to `policy`.
+    ///
+    /// Links removed under `Unlink` or `Inline` are recorded in the trash
+    /// entry so that restoring the scratch re-attaches them.
+    pub fn delete(&self, id: &str, policy: DeletePolicy) -> Result<DeleteReport> {
+        let scratch = self.repo.load(id)?;
+        let references = self.references(id)?;
+        if policy == DeletePolicy::Block && !references.is_empty() {
+            return Err(CoreError::Conflict(format!(
+                "Scratch {} is referenced by {} outline node(s)",
+                id,
+                references.len()
+            )));
+        }
+
+        let mut removed_links = Vec::new();
+        let mut originals = Vec::new();
+        let mut detached = Vec::new();
+        if let Some(projects) = &self.projects {
+            let inline = (policy == DeletePolicy::Inline).then_some(scratch.content.as_str());
+            let mut project_ids: Vec<&str> =
+                references.iter().map(|r| r.project_id.as_str()).collect();
+            project_ids.dedup();
+            for project_id in project_ids {
+                let original = projects.load(project_id)?;
+                let mut project = original.clone();
+                removed_links.extend(detach_links(project_id, &mut project.outline, id, inline));
+                project.modified_at = Utc::now();
+                originals.push(original);
+                detached.push(project);
+            }
+        }
+
+        // The projects are rewritten first, so that no link is left pointing
+        // at a trashed scratch, and put back if the scratch cannot be
+        // removed after all.
+        let removed = detached
+            .iter()
+            .try_for_each(|project| self.save_project(project))
+            .and_then(|()| match &self.trash {
+                Some(trash) => trash.trash(&TrashEntry::for_scratch(&scratch, removed_links)),
+                None => self.repo.delete(id).and_then(|()| self.forget_history(id)),
+            });
+        if let Err(e) = removed {
+            for original in &originals {
+                // The error that made the deletion fail is the one to report.
+                let _ = self.save_project(original);
+            }
+            return Err(e);
+        }
+        for project in detached {
+            self.publish(WorkspaceEvent::ProjectSaved {
+                id: project.id,
+                revision: project.modified_at,
+            });
+        }
+        self.publish(WorkspaceEvent::ScratchDeleted { id: id.to_string() });
+        Ok(DeleteReport {
+            scratch_id: id.to_string(),
+            references,
+        })
     }
 }
 
+fn find_version(versions: Vec<ScratchVersion>, id: &str, version: u32) -> Result<ScratchVersion> {
+    versions
+        .into_iter()
+        .find(|v| v.version == version)
+        .ok_or_else(|| {
+            CoreError::not_found(EntityKind::ScratchVersion, format!("{}/{}", id, version))
+        })
+}
+
 pub struct ProjectManager {
     repo: Box<dyn ProjectRepository>,
+    trash: Option<Box<dyn TrashRepository>>,
+    scratches: Option<Box<dyn ScratchRepository>>,
+    templates: Option<Box<dyn TemplateRepository>>,
+    blueprints: Option<Box<dyn BlueprintRepository>>,
+    events: Option<Arc<EventBus>>,
+    // Built on first use, then kept current by `create`, `save` and `delete`.
+    backlinks: RwLock<Option<BacklinkIndex>>,
 }
 
 impl ProjectManager {
     pub fn new(repo: Box<dyn ProjectRepository>) -> Self {
-        Self { repo }
+        Self {
+            repo,
+            trash: None,
+            scratches: None,
+            templates: None,
+            blueprints: None,
+            events: None,
+            backlinks: RwLock::new(None),
+        }
+    }
+
+    /// Routes deletions through `trash` instead of removing projects outright.
+    pub fn with_trash(mut self, trash: Box<dyn TrashRepository>) -> Self {
+        self.trash = Some(trash);
+        self
+    }
+
+    /// Lets the manager check outline links against the stored scratches.
+    pub fn with_scratches(mut self, scratches: Box<dyn ScratchRepository>) -> Self {
+        self.scratches = Some(scratches);
+        self
+    }
+
+    /// Lets `create` and `save` limit the outline depth to what the
+    /// project's document class can typeset, rather than to the deepest
+    /// class.
+    pub fn with_templates(mut self, templates: Box<dyn TemplateRepository>) -> Self {
+        self.templates = Some(templates);
+        self
+    }
+
+    /// Lets users save projects as blueprints. Without it only the built-in
+    /// blueprints are offered.
+    pub fn with_blueprints(mut self, blueprints: Box<dyn BlueprintRepository>) -> Self {
+        self.blueprints = Some(blueprints);
+        self
+    }
+
+    /// Publishes every change this manager makes to `events`.
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn publish(&self, event: WorkspaceEvent) {
+        if let Some(events) = &self.events {
+            events.publish(event);
+        }
     }
 
     pub fn create(
@@ -193,11 +628,9 @@ impl ProjectManager {
         template_id: String,
         output_dir: String,
     ) -> Result<Project> {
-        let id = Uuid::new_v4().to_string();
-        let now = Utc::now();
         let outline = OutlineNode {
             id: Uuid::new_v4().to_string(),
-            title: "Root".to_string(),
+            title: ROOT_TITLE.to_string(),
             content: None,
             children: vec![],
             scratches: vec![],
@@ -206,24 +639,335 @@ impl ProjectManager {
             template_id,
             output_dir,
         };
-        let project = Project {
+        self.create_with_outline(title, outline, settings)
+    }
+
+    /// Creates a project that starts out with `outline` instead of an empty
+    /// root node.
+    pub fn create_with_outline(
+        &self,
+        title: String,
+        outline: OutlineNode,
+        settings: ProjectSettings,
+    ) -> Result<Project> {
+        self.create_project(Uuid::new_v4().to_string(), title, outline, settings)
+    }
+
+    fn create_project(
+        &self,
+        id: String,
+        title: String,
+        outline: OutlineNode,
+        settings: ProjectSettings,
+    ) -> Result<Project> {
+        let now = Utc::now();
+        let mut project = Project {
             id: id.clone(),
             title,
             outline,
             settings,
             created_at: now,
             modified_at: now,
+            archived: false,
         };
+        let class = self.document_class(&project);
+        validate_project(&mut project, class)?;
+        self.fill_included_copies(&mut project)?;
         self.repo.save(&project)?;
+        self.update_backlinks(&project);
+        self.publish(WorkspaceEvent::ProjectCreated {
+            id: project.id.clone(),
+            revision: project.modified_at,
+        });
         Ok(project)
     }
 
+    /// Copies a project under a new title. Every outline node gets a fresh
+    /// id; `Include` links to a scratch in `scratch_copies` are pointed at the
+    /// copy it maps to. The copy is never archived.
+    pub fn duplicate(
+        &self,
+        id: &str,
+        title: String,
+        scratch_copies: &HashMap<String, String>,
+    ) -> Result<Project> {
+        let project = self.repo.load(id)?;
+        let mut outline = project.outline;
+        renew_node_ids(&mut outline);
+        for (_, link) in included_links_mut(&mut outline) {
+            if let Some(copy) = scratch_copies.get(&link.scratch_id) {
+                link.scratch_id = copy.clone();
+            }
+        }
+        self.create_with_outline(title, outline, project.settings)
+    }
+
+    pub fn rename(&self, id: &str, title: String) -> Result<Project> {
+        let mut project = self.repo.load(id)?;
+        project.title = title;
+        project.modified_at = Utc::now();
+        self.save(&project)?;
+        self.repo.load(id)
+    }
+
+    /// Hides the project from project lists, or brings it back.
+    pub fn set_archived(&self, id: &str, archived: bool) -> Result<Project> {
+        let mut project = self.repo.load(id)?;
+        if project.archived == archived {
+            return Ok(project);
+        }
+        project.archived = archived;
+        project.modified_at = Utc::now();
+        self.save(&project)?;
+        Ok(project)
+    }
+
+    /// The built-in blueprints, then the saved ones by name.
+    pub fn blueprints(&self) -> Result<Vec<Blueprint>> {
+        let mut blueprints = builtin_blueprints();
+        if let Some(repo) = &self.blueprints {
+            let mut saved = repo.list()?;
+            saved.sort_by_key(|b| b.name.to_lowercase());
+            blueprints.extend(saved);
+        }
+        Ok(blueprints)
+    }
+
+    pub fn blueprint(&self, id: &str) -> Result<Blueprint> {
+        if let Some(blueprint) = builtin_blueprints().into_iter().find(|b| b.id == id) {
+            return Ok(blueprint);
+        }
+        match &self.blueprints {
+            Some(repo) => repo.load(id),
+            None => Err(CoreError::not_found(EntityKind::Blueprint, id)),
+        }
+    }
+
+    /// Creates a project from a blueprint, first adding the blueprint's
+    /// template to the workspace if no template has its id.
+    pub fn create_from_blueprint(&self, blueprint_id: &str, title: String) -> Result<Project> {
+        let blueprint = self.blueprint(blueprint_id)?;
+        if let (Some(template), Some(templates)) = (&blueprint.template, &self.templates) {
+            match templates.load(&template.id) {
+                Ok(_) => {}
+                Err(CoreError::NotFound { .. }) => templates.save(template)?,
+                Err(e) => return Err(e),
+            }
+        }
+        let id = Uuid::new_v4().to_string();
+        let settings = ProjectSettings {
+            output_dir: blueprint.output_dir_for(&id),
+            ..blueprint.settings.clone()
+        };
+        let mut outline = blueprint.outline;
+        renew_node_ids(&mut outline);
+        self.create_project(id, title, outline, settings)
+    }
+
+    /// Saves the outline, template and settings of a project as a blueprint
+    /// for new projects.
+    pub fn save_as_blueprint(
+        &self,
+        project_id: &str,
+        name: String,
+        description: String,
+    ) -> Result<Blueprint> {
+        let Some(repo) = &self.blueprints else {
+            return Err(CoreError::Storage(
+                "Blueprints cannot be saved in this workspace".to_string(),
+            ));
+        };
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(CoreError::invalid("name", "must not be empty"));
+        }
+        let project = self.repo.load(project_id)?;
+        let template = match &self.templates {
+            Some(templates) => match templates.load(&project.settings.template_id) {
+                Ok(template) => Some(template),
+                Err(CoreError::NotFound { .. }) => None,
+                Err(e) => return Err(e),
+            },
+            None => None,
+        };
+        let blueprint = Blueprint::from_project(&project, template, name, description);
+        repo.save(&blueprint)?;
+        Ok(blueprint)
+    }
+
+    /// Deletes a saved blueprint. Built-in ones cannot be deleted.
+    pub fn delete_blueprint(&self, id: &str) -> Result<()> {
+        let blueprint = self.blueprint(id)?;
+        if blueprint.builtin {
+            return Err(CoreError::Conflict(format!(
+                "{} is a built-in blueprint",
+                blueprint.name
+            )));
+        }
+        match &self.blueprints {
+            Some(repo) => repo.delete(id),
+            None => Ok(()),
+        }
+    }
+
     pub fn load(&self, id: &str) -> Result<Project> {
         self.repo.load(id)
     }
 
+    /// Loads a project together with the links in its outline whose scratch
+    /// no longer exists.
+    pub fn load_checked(&self, id: &str) -> Result<(Project, Vec<DanglingLink>)> {
+        let project = self.repo.load(id)?;
+        let dangling = self.dangling_links(&project);
+        Ok((project, dangling))
+    }
+
+    pub fn dangling_links(&self, project: &Project) -> Vec<DanglingLink> {
+        let Some(scratches) = &self.scratches else {
+            return vec![];
+        };
+        let exists = |id: &str| !matches!(scratches.load(id), Err(CoreError::NotFound { .. }));
+        let mut dangling = Vec::new();
+        collect_dangling(&project.outline, &exists, &mut dangling);
+        dangling
+    }
+
+    /// Saves a project. `Include` links that carry no copy of their scratch
+    /// keep the copy from the stored version, or get a fresh one.
     pub fn save(&self, project: &Project) -> Result<()> {
-        self.repo.save(project)
+        let mut project = project.clone();
+        let class = self.document_class(&project);
+        validate_project(&mut project, class)?;
+        self.fill_included_copies(&mut project)?;
+        self.repo.save(&project)?;
+        self.update_backlinks(&project);
+        self.publish(WorkspaceEvent::ProjectSaved {
+            id: project.id,
+            revision: project.modified_at,
+        });
+        Ok(())
+    }
+
+    /// Lists `Include` links whose scratch changed after it was included.
+    pub fn drifted_links(&self, project: &Project) -> Vec<DriftedLink> {
+        let Some(scratches) = &self.scratches else {
+            return vec![];
+        };
+        let current = |id: &str| scratches.load(id).ok().map(|s| s.content);
+        let mut drifted = Vec::new();
+        collect_drift(&project.outline, &current, &mut drifted);
+        drifted
+    }
+
+    pub fn drift_view(
+        &self,
+        project_id: &str,
+        node_id: &str,
+        scratch_id: &str,
+    ) -> Result<DriftView> {
+        let project = self.repo.load(project_id)?;
+        let copy = project
+            .outline
+            .find(node_id)
+            .and_then(|node| node.scratches.iter().find(|l| l.scratch_id == scratch_id))
+            .and_then(|link| link.included.clone())
+            .ok_or_else(|| {
+                CoreError::not_found(EntityKind::Link, format!("{}/{}", node_id, scratch_id))
+            })?;
+        Ok(DriftView {
+            node_id: node_id.to_string(),
+            scratch_id: scratch_id.to_string(),
+            original: copy.original,
+            current: self.current_content(scratch_id)?,
+            local: copy.text,
+        })
+    }
+
+    /// Reconciles a drifted include and saves the project.
+    pub fn resolve_drift(
+        &self,
+        project_id: &str,
+        node_id: &str,
+        scratch_id: &str,
+        resolution: DriftResolution,
+    ) -> Result<Project> {
+        let current = self.current_content(scratch_id)?;
+        let mut project = self.repo.load(project_id)?;
+        let copy = project
+            .outline
+            .find_mut(node_id)
+            .and_then(|node| {
+                node.scratches
+                    .iter_mut()
+                    .find(|l| l.scratch_id == scratch_id)
+            })
+            .and_then(|link| link.included.as_mut())
+            .ok_or_else(|| {
+                CoreError::not_found(EntityKind::Link, format!("{}/{}", node_id, scratch_id))
+            })?;
+        let text = match resolution {
+            DriftResolution::AcceptUpdate => current.clone(),
+            DriftResolution::KeepLocal => copy.text.clone(),
+            DriftResolution::Merge(text) => text,
+        };
+        copy.resync(&current, text);
+        project.modified_at = Utc::now();
+        self.save(&project)?;
+        Ok(project)
+    }
+
+    /// The document class of the project's template, if templates are
+    /// available and the template exists.
+    fn document_class(&self, project: &Project) -> Option<DocumentClass> {
+        let template = self
+            .templates
+            .as_ref()?
+            .load(&project.settings.template_id)
+            .ok()?;
+        Some(DocumentClass::of_template(&template.content))
+    }
+
+    fn current_content(&self, scratch_id: &str) -> Result<String> {
+        match &self.scratches {
+            Some(scratches) => Ok(scratches.load(scratch_id)?.content),
+            None => Err(CoreError::not_found(EntityKind::Scratch, scratch_id)),
+        }
+    }
+
+    fn fill_included_copies(&self, project: &mut Project) -> Result<()> {
+        let mut missing = included_links_mut(&mut project.outline);
+        missing.retain(|(_, link)| link.included.is_none());
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let previous = match self.repo.load(&project.id) {
+            Ok(previous) => Some(previous),
+            Err(CoreError::NotFound { .. }) => None,
+            Err(e) => return Err(e),
+        };
+        for (node_id, link) in missing {
+            let kept = previous
+                .as_ref()
+                .and_then(|p| p.outline.find(&node_id))
+                .and_then(|node| {
+                    node.scratches
+                        .iter()
+                        .find(|l| l.scratch_id == link.scratch_id)
+                })
+                .and_then(|l| l.included.clone());
+            link.included = match kept {
+                Some(copy) => Some(copy),
+                None => match &self.scratches {
+                    Some(scratches) => match scratches.load(&link.scratch_id) {
+                        Ok(scratch) => Some(IncludedCopy::of(&scratch.content)),
+                        Err(CoreError::NotFound { .. }) => None,
+                        Err(e) => return Err(e),
+                    },
+                    None => None,
+                },
+            };
+        }
+        Ok(())
     }
 
     pub fn list(&self) -> Result<Vec<Project>> {
@@ -231,7 +975,60 @@ impl ProjectManager {
     }
 
     pub fn delete(&self, id: &str) -> Result<()> {
-        self.repo.delete(id)
+        match &self.trash {
+            Some(trash) => {
+                let project = self.repo.load(id)?;
+                trash.trash(&TrashEntry::for_project(&project))?;
+            }
+            None => self.repo.delete(id)?,
+        }
+        if let Some(index) = self.backlinks.write().unwrap().as_mut() {
+            index.remove_project(id);
+        }
+        self.publish(WorkspaceEvent::ProjectDeleted { id: id.to_string() });
+        Ok(())
+    }
+
+    /// Lists every outline node, across all projects, that links to the scratch.
+    pub fn scratch_usages(&self, scratch_id: &str) -> Result<Vec<ScratchReference>> {
+        self.with_backlinks(|index| index.usages(scratch_id))
+    }
+
+    pub fn used_scratch_ids(&self) -> Result<HashSet<String>> {
+        self.with_backlinks(BacklinkIndex::used_scratch_ids)
+    }
+
+    /// Drops the backlink index so it is rebuilt on next use. Call this after
+    /// projects were changed without going through this manager.
+    pub fn invalidate_backlinks(&self) {
+        *self.backlinks.write().unwrap() = None;
+    }
+
+    fn with_backlinks<T>(&self, f: impl FnOnce(&BacklinkIndex) -> T) -> Result<T> {
+        if let Some(index) = self.backlinks.read().unwrap().as_ref() {
+            return Ok(f(index));
+        }
+        // Built under the write lock so that a save landing meanwhile waits
+        // for the index instead of updating a copy that is about to be replaced.
+        let mut backlinks = self.backlinks.write().unwrap();
+        let index = match backlinks.as_ref() {
+            Some(index) => index,
+            None => backlinks.insert(BacklinkIndex::build(&self.repo.list()?)),
+        };
+        Ok(f(index))
+    }
+
+    fn update_backlinks(&self, project: &Project) {
+        if let Some(index) = self.backlinks.write().unwrap().as_mut() {
+            index.update_project(project);
+        }
+    }
+}
+
+fn renew_node_ids(node: &mut OutlineNode) {
+    node.id = Uuid::new_v4().to_string();
+    for child in &mut node.children {
+        renew_node_ids(child);
     }
 }
 
@@ -255,6 +1052,8 @@ pub struct ProjectDto {
     pub settings: ProjectSettingsDto,
     pub created_at: String,
     pub modified_at: String,
+    #[serde(default)]
+    pub archived: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -271,6 +1070,8 @@ pub struct ScratchLinkDto {
     pub scratch_id: String,
     pub mode: String, // "Include" or "Link"
     pub insertion: InsertionFlagsDto,
+    #[serde(default)]
+    pub included: Option<IncludedCopy>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -317,6 +1118,7 @@ impl From<Project> for ProjectDto {
             settings: p.settings.into(),
             created_at: p.created_at.to_rfc3339(),
             modified_at: p.modified_at.to_rfc3339(),
+            archived: p.archived,
         }
     }
 }
@@ -342,6 +1144,7 @@ impl From<ScratchLink> for ScratchLinkDto {
                 IntegrationMode::Link => "Link".to_string(),
             },
             insertion: s.insertion.into(),
+            included: s.included,
         }
     }
 }
@@ -366,20 +1169,26 @@ impl From<ProjectSettings> for ProjectSettingsDto {
     }
 }
 
-impl From<ScratchLinkDto> for ScratchLink {
-    fn from(dto: ScratchLinkDto) -> Self {
+impl From<Template> for TemplateDto {
+    fn from(t: Template) -> Self {
         Self {
-            scratch_id: dto.scratch_id,
-            mode: match dto.mode.as_str() {
-                "Include" => IntegrationMode::Include,
-                "Link" => IntegrationMode::Link,
-                _ => IntegrationMode::Include,
-            },
-            insertion: dto.insertion.into(),
+            id: t.id,
+            name: t.name,
+            content: t.content,
         }
     }
 }
 
+impl TryFrom<ScratchLinkDto> for ScratchLink {
+    type Error = CoreError;
+    fn try_from(dto: ScratchLinkDto) -> Result<Self> {
+        let mut violations = Violations::default();
+        let link = link_from_dto(dto, "", &mut violations);
+        violations.into_result()?;
+        Ok(link)
+    }
+}
+
 impl From<InsertionFlagsDto> for InsertionFlags {
     fn from(dto: InsertionFlagsDto) -> Self {
         Self {
@@ -401,43 +1210,81 @@ impl From<ProjectSettingsDto> for ProjectSettings {
 }
 
 impl TryFrom<OutlineNodeDto> for OutlineNode {
-    type Error = String;
-    fn try_from(dto: OutlineNodeDto) -> std::result::Result<Self, Self::Error> {
-        let children = dto
-            .children
-            .into_iter()
-            .map(TryInto::try_into)
-            .collect::<std::result::Result<Vec<_>, _>>()?;
-        let scratches = dto.scratches.into_iter().map(Into::into).collect();
-        Ok(OutlineNode {
-            id: dto.id,
-            title: dto.title,
-            content: dto.content,
-            children,
-            scratches,
-        })
+    type Error = CoreError;
+    fn try_from(dto: OutlineNodeDto) -> Result<Self> {
+        let mut violations = Violations::default();
+        let node = node_from_dto(dto, "outline", &mut violations);
+        violations.into_result()?;
+        Ok(node)
     }
 }
 
+/// Converts a project from the frontend and validates it, reporting every
+/// violation at once. The outline depth is checked later, on save, against
+/// the project's template.
 impl TryFrom<ProjectDto> for Project {
-    type Error = String;
-    fn try_from(dto: ProjectDto) -> std::result::Result<Self, Self::Error> {
-        let created_at = chrono::DateTime::parse_from_rfc3339(&dto.created_at)
-            .map_err(|e| e.to_string())?
-            .with_timezone(&Utc);
-        let modified_at = chrono::DateTime::parse_from_rfc3339(&dto.modified_at)
-            .map_err(|e| e.to_string())?
-            .with_timezone(&Utc);
-        let outline = dto.outline.try_into()?;
-        let settings = dto.settings.into();
-        Ok(Project {
+    type Error = CoreError;
+    fn try_from(dto: ProjectDto) -> Result<Self> {
+        let mut violations = Violations::default();
+        let mut timestamp = |field: &str, value: &str| {
+            chrono::DateTime::parse_from_rfc3339(value)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|e| {
+                    violations.push(field, e.to_string());
+                    DateTime::default()
+                })
+        };
+        let created_at = timestamp("created_at", &dto.created_at);
+        let modified_at = timestamp("modified_at", &dto.modified_at);
+        let outline = node_from_dto(dto.outline, "outline", &mut violations);
+        let mut project = Project {
             id: dto.id,
             title: dto.title,
             outline,
-            settings,
+            settings: dto.settings.into(),
             created_at,
             modified_at,
-        })
+            archived: dto.archived,
+        };
+        check_project(&mut project, None, &mut violations);
+        violations.into_result()?;
+        Ok(project)
+    }
+}
+
+fn node_from_dto(dto: OutlineNodeDto, path: &str, violations: &mut Violations) -> OutlineNode {
+    let children = dto
+        .children
+        .into_iter()
+        .enumerate()
+        .map(|(i, child)| node_from_dto(child, &format!("{}.children[{}]", path, i), violations))
+        .collect();
+    let scratches = dto
+        .scratches
+        .into_iter()
+        .enumerate()
+        .map(|(i, link)| link_from_dto(link, &format!("{}.scratches[{}].", path, i), violations))
+        .collect();
+    OutlineNode {
+        id: dto.id,
+        title: dto.title,
+        content: dto.content,
+        children,
+        scratches,
+    }
+}
+
+/// `prefix` is prepended to field names, e.g. `outline.scratches[0].`.
+fn link_from_dto(dto: ScratchLinkDto, prefix: &str, violations: &mut Violations) -> ScratchLink {
+    let mode = dto.mode.parse().unwrap_or_else(|e: String| {
+        violations.push(format!("{}mode", prefix), e);
+        IntegrationMode::Link
+    });
+    ScratchLink {
+        scratch_id: dto.scratch_id,
+        mode,
+        insertion: dto.insertion.into(),
+        included: dto.included,
     }
 }
 
@@ -445,6 +1292,7 @@ impl TryFrom<ProjectDto> for Project {
 mod tests {
     use super::*;
     use chrono::Utc;
+    use std::sync::Mutex;
 
     #[test]
     fn test_scratch_creation() {
@@ -462,7 +1310,7 @@ mod tests {
             }
 
             fn load(&self, _id: &str) -> Result<Scratch> {
-                Err(CoreError::NotFound("Not implemented".to_string()))
+                Err(CoreError::not_found(EntityKind::Scratch, "Not implemented"))
             }
 
             fn list(&self) -> Result<Vec<Scratch>> {
@@ -494,7 +1342,7 @@ mod tests {
     fn test_project_creation() {
         let title = "Test Project".to_string();
         let template_id = "template1".to_string();
-        let output_dir = "/tmp/output".to_string();
+        let output_dir = "output".to_string();
 
         // Mock repository
         struct MockProjectRepo;
@@ -505,7 +1353,7 @@ mod tests {
             }
 
             fn load(&self, _id: &str) -> Result<Project> {
-                Err(CoreError::NotFound("Not implemented".to_string()))
+                Err(CoreError::not_found(EntityKind::Project, "Not implemented"))
             }
 
             fn list(&self) -> Result<Vec<Project>> {
@@ -564,7 +1412,7 @@ mod tests {
             title: "Test Project".to_string(),
             outline: OutlineNode {
                 id: "outline-id".to_string(),
-                title: "Root".to_string(),
+                title: ROOT_TITLE.to_string(),
                 content: None,
                 children: vec![],
                 scratches: vec![],
@@ -575,6 +1423,7 @@ mod tests {
             },
             created_at: Utc::now(),
             modified_at: Utc::now(),
+            archived: false,
         };
 
         let dto: ProjectDto = project.clone().into();
@@ -585,4 +1434,254 @@ mod tests {
         assert_eq!(dto.settings.template_id, project.settings.template_id);
         assert_eq!(dto.settings.output_dir, project.settings.output_dir);
     }
+
+    #[test]
+    fn test_project_dto_rejects_invalid_input() {
+        let link = |mode: &str| ScratchLinkDto {
+            scratch_id: "s".to_string(),
+            mode: mode.to_string(),
+            insertion: InsertionFlagsDto {
+                body: true,
+                footnote: false,
+                reference: false,
+                appendix: false,
+            },
+            included: None,
+        };
+        let dto = ProjectDto {
+            id: "p".to_string(),
+            title: String::new(),
+            outline: OutlineNodeDto {
+                id: "root".to_string(),
+                title: ROOT_TITLE.to_string(),
+                content: None,
+                children: vec![],
+                scratches: vec![link("Link"), link("Embed")],
+            },
+            settings: ProjectSettingsDto {
+                template_id: "t".to_string(),
+                output_dir: "out".to_string(),
+            },
+            created_at: "yesterday".to_string(),
+            modified_at: Utc::now().to_rfc3339(),
+            archived: false,
+        };
+
+        let Err(CoreError::Validation(errors)) = Project::try_from(dto) else {
+            panic!("expected validation error");
+        };
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["created_at", "outline.scratches[1].mode", "title"]
+        );
+    }
+
+    #[derive(Default)]
+    struct MemoryProjects(Mutex<Vec<Project>>);
+
+    impl ProjectRepository for Arc<MemoryProjects> {
+        fn save(&self, project: &Project) -> Result<()> {
+            let mut projects = self.0.lock().unwrap();
+            projects.retain(|p| p.id != project.id);
+            projects.push(project.clone());
+            Ok(())
+        }
+
+        fn load(&self, id: &str) -> Result<Project> {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|p| p.id == id)
+                .cloned()
+                .ok_or_else(|| CoreError::not_found(EntityKind::Project, id))
+        }
+
+        fn list(&self) -> Result<Vec<Project>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn delete(&self, id: &str) -> Result<()> {
+            self.0.lock().unwrap().retain(|p| p.id != id);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_project_lifecycle() {
+        // Moves projects out of the repository, as the trash directory does.
+        struct MemoryTrash(Arc<MemoryProjects>, Mutex<Vec<TrashEntry>>);
+
+        impl TrashRepository for Arc<MemoryTrash> {
+            fn trash(&self, entry: &TrashEntry) -> Result<()> {
+                self.0.delete(&entry.id)?;
+                self.1.lock().unwrap().push(entry.clone());
+                Ok(())
+            }
+
+            fn list(&self) -> Result<Vec<TrashEntry>> {
+                Ok(self.1.lock().unwrap().clone())
+            }
+
+            fn restore(&self, _kind: TrashKind, id: &str) -> Result<TrashEntry> {
+                Err(CoreError::not_found(EntityKind::TrashEntry, id))
+            }
+
+            fn purge(&self, _kind: TrashKind, _id: &str) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        let repo = Arc::new(MemoryProjects::default());
+        let trash = Arc::new(MemoryTrash(repo.clone(), Mutex::new(vec![])));
+        let manager =
+            ProjectManager::new(Box::new(repo.clone())).with_trash(Box::new(trash.clone()));
+        let mut project = manager
+            .create("Paper".to_string(), "t".to_string(), "out".to_string())
+            .unwrap();
+        let include = |scratch_id: &str| ScratchLink {
+            scratch_id: scratch_id.to_string(),
+            mode: IntegrationMode::Include,
+            insertion: InsertionFlags {
+                body: true,
+                footnote: false,
+                reference: false,
+                appendix: false,
+            },
+            included: Some(IncludedCopy::of("Text")),
+        };
+        project.outline.children.push(OutlineNode {
+            id: "method".to_string(),
+            title: "Method".to_string(),
+            content: None,
+            children: vec![],
+            scratches: vec![include("s1"), include("s2")],
+        });
+        manager.save(&project).unwrap();
+
+        let copies = HashMap::from([("s1".to_string(), "s1-copy".to_string())]);
+        let copy = manager
+            .duplicate(&project.id, "Paper, again".to_string(), &copies)
+            .unwrap();
+        assert_ne!(copy.id, project.id);
+        assert_ne!(copy.outline.id, project.outline.id);
+        let node = &copy.outline.children[0];
+        assert_ne!(node.id, "method");
+        assert_eq!(node.title, "Method");
+        let ids: Vec<_> = node
+            .scratches
+            .iter()
+            .map(|l| l.scratch_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["s1-copy", "s2"]);
+
+        let renamed = manager
+            .rename(&copy.id, " Paper, revised ".to_string())
+            .unwrap();
+        assert_eq!(renamed.title, "Paper, revised");
+        assert!(manager.rename(&copy.id, String::new()).is_err());
+
+        assert!(manager.set_archived(&copy.id, true).unwrap().archived);
+        assert!(manager.load(&copy.id).unwrap().archived);
+        let again = manager
+            .duplicate(&copy.id, "Third".to_string(), &HashMap::new())
+            .unwrap();
+        assert!(!again.archived);
+
+        manager.delete(&copy.id).unwrap();
+        assert!(matches!(
+            manager.load(&copy.id),
+            Err(CoreError::NotFound { .. })
+        ));
+        assert_eq!(trash.list().unwrap()[0].title, "Paper, revised");
+    }
+
+    #[test]
+    fn test_projects_from_blueprints() {
+        #[derive(Default)]
+        struct MemoryBlueprints(Mutex<Vec<Blueprint>>);
+
+        impl BlueprintRepository for MemoryBlueprints {
+            fn save(&self, blueprint: &Blueprint) -> Result<()> {
+                self.0.lock().unwrap().push(blueprint.clone());
+                Ok(())
+            }
+
+            fn load(&self, id: &str) -> Result<Blueprint> {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|b| b.id == id)
+                    .cloned()
+                    .ok_or_else(|| CoreError::not_found(EntityKind::Blueprint, id))
+            }
+
+            fn list(&self) -> Result<Vec<Blueprint>> {
+                Ok(self.0.lock().unwrap().clone())
+            }
+
+            fn delete(&self, id: &str) -> Result<()> {
+                self.0.lock().unwrap().retain(|b| b.id != id);
+                Ok(())
+            }
+        }
+
+        let manager = ProjectManager::new(Box::new(Arc::new(MemoryProjects::default())))
+            .with_blueprints(Box::<MemoryBlueprints>::default());
+        assert_eq!(manager.blueprints().unwrap().len(), 5);
+
+        let mut paper = manager
+            .create_from_blueprint("imrad-paper", "Paper".to_string())
+            .unwrap();
+        let builtin = manager.blueprint("imrad-paper").unwrap();
+        assert_eq!(paper.settings.template_id, "article");
+        // Every project gets an output directory of its own.
+        assert_eq!(paper.settings.output_dir, format!("output/{}", paper.id));
+        assert_eq!(paper.outline.children.len(), builtin.outline.children.len());
+        assert_ne!(paper.outline.children[0].id, builtin.outline.children[0].id);
+        assert_eq!(
+            paper.outline.children[0].content,
+            builtin.outline.children[0].content
+        );
+
+        paper.outline.children[0].scratches.push(ScratchLink {
+            scratch_id: "s1".to_string(),
+            mode: IntegrationMode::Link,
+            insertion: InsertionFlags {
+                body: true,
+                footnote: false,
+                reference: false,
+                appendix: false,
+            },
+            included: None,
+        });
+        manager.save(&paper).unwrap();
+        let saved = manager
+            .save_as_blueprint(&paper.id, "Lab paper".to_string(), String::new())
+            .unwrap();
+        assert!(!saved.builtin);
+        assert!(saved.outline.children[0].scratches.is_empty());
+        assert_eq!(manager.blueprints().unwrap()[5].name, "Lab paper");
+        let next = manager
+            .create_from_blueprint(&saved.id, "Next paper".to_string())
+            .unwrap();
+        assert_eq!(next.outline.children.len(), paper.outline.children.len());
+        assert_eq!(saved.settings.output_dir, "output");
+        assert_eq!(next.settings.output_dir, format!("output/{}", next.id));
+
+        assert!(matches!(
+            manager.delete_blueprint("imrad-paper"),
+            Err(CoreError::Conflict(_))
+        ));
+        manager.delete_blueprint(&saved.id).unwrap();
+        assert!(matches!(
+            manager.create_from_blueprint(&saved.id, "Gone".to_string()),
+            Err(CoreError::NotFound {
+                kind: EntityKind::Blueprint,
+                ..
+            })
+        ));
+    }
 }
@@ -0,0 +1,384 @@
+use crate::{CoreError, OutlineNode, Project, Result, Scratch};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+pub const MAX_PAGE_SIZE: usize = 1000;
+/// Length of `ScratchSummary::excerpt`, in characters.
+pub const EXCERPT_LEN: usize = 160;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SortKey {
+    #[default]
+    Modified,
+    Created,
+    Title,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "Modified" => Ok(SortKey::Modified),
+            "Created" => Ok(SortKey::Created),
+            "Title" => Ok(SortKey::Title),
+            other => Err(format!("Unknown sort key: {}", other)),
+        }
+    }
+}
+
+/// How a list is filtered and ordered, and which page of it to return.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListQuery {
+    pub sort: SortKey,
+    /// Defaults to newest first for timestamps and A to Z for titles.
+    pub descending: Option<bool>,
+    /// Case-insensitive text the title must contain.
+    pub text: Option<String>,
+    /// Tag an item must carry. Only scratches have tags.
+    pub tag: Option<String>,
+    /// Lists archived projects too. Only projects can be archived.
+    pub include_archived: bool,
+    /// `next_cursor` of the previous page; `None` for the first page.
+    pub cursor: Option<String>,
+    /// Defaults to `DEFAULT_PAGE_SIZE`, capped at `MAX_PAGE_SIZE`.
+    pub limit: Option<usize>,
+}
+
+impl ListQuery {
+    fn descending(&self) -> bool {
+        self.descending.unwrap_or(self.sort != SortKey::Title)
+    }
+
+    fn matches_title(&self, title: &str) -> bool {
+        match &self.text {
+            Some(text) => title.to_lowercase().contains(&text.to_lowercase()),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass as `ListQuery::cursor` to get the next page; `None` on the last.
+    pub next_cursor: Option<String>,
+    /// Number of items matching the query, across all pages.
+    pub total: usize,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            total: self.total,
+        }
+    }
+}
+
+/// What a list of scratches shows, without the content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScratchSummary {
+    pub id: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub modified_at: DateTime<Utc>,
+    /// Start of the content, with whitespace collapsed.
+    pub excerpt: String,
+    pub word_count: usize,
+}
+
+impl From<&Scratch> for ScratchSummary {
+    fn from(s: &Scratch) -> Self {
+        let words: Vec<&str> = s.content.split_whitespace().collect();
+        let joined = words.join(" ");
+        let mut excerpt: String = joined.chars().take(EXCERPT_LEN).collect();
+        if excerpt.len() < joined.len() {
+            excerpt.push('…');
+        }
+        Self {
+            id: s.id.clone(),
+            title: s.title.clone(),
+            tags: s.tags.clone(),
+            created_at: s.created_at,
+            modified_at: s.modified_at,
+            excerpt,
+            word_count: words.len(),
+        }
+    }
+}
+
+/// What a list of projects shows, without the outline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectSummary {
+    pub id: String,
+    pub title: String,
+    pub template_id: String,
+    pub created_at: DateTime<Utc>,
+    pub modified_at: DateTime<Utc>,
+    /// Outline nodes below the root.
+    pub section_count: usize,
+    /// Scratch links across the outline.
+    pub link_count: usize,
+    pub archived: bool,
+}
+
+impl From<&Project> for ProjectSummary {
+    fn from(p: &Project) -> Self {
+        fn count(node: &OutlineNode) -> (usize, usize) {
+            node.children
+                .iter()
+                .map(count)
+                .fold((node.children.len(), node.scratches.len()), |acc, c| {
+                    (acc.0 + c.0, acc.1 + c.1)
+                })
+        }
+        let (section_count, link_count) = count(&p.outline);
+        Self {
+            id: p.id.clone(),
+            title: p.title.clone(),
+            template_id: p.settings.template_id.clone(),
+            created_at: p.created_at,
+            modified_at: p.modified_at,
+            section_count,
+            link_count,
+            archived: p.archived,
+        }
+    }
+}
+
+/// Sorts, filters and pages scratch summaries.
+pub fn page_scratches(scratches: &[Scratch], query: &ListQuery) -> Result<Page<ScratchSummary>> {
+    let summaries = scratches
+        .iter()
+        .filter(|s| query.matches_title(&s.title))
+        .filter(|s| match &query.tag {
+            Some(tag) => s.tags.contains(tag),
+            None => true,
+        })
+        .map(ScratchSummary::from)
+        .collect();
+    paginate(
+        summaries,
+        query,
+        |s| sort_value(query.sort, &s.title, s.created_at, s.modified_at),
+        |s| &s.id,
+    )
+}
+
+/// Sorts, filters and pages project summaries.
+pub fn page_projects(projects: &[Project], query: &ListQuery) -> Result<Page<ProjectSummary>> {
+    let summaries = projects
+        .iter()
+        .filter(|p| query.include_archived || !p.archived)
+        .filter(|p| query.matches_title(&p.title))
+        .map(ProjectSummary::from)
+        .collect();
+    paginate(
+        summaries,
+        query,
+        |p| sort_value(query.sort, &p.title, p.created_at, p.modified_at),
+        |p| &p.id,
+    )
+}
+
+/// The value an item is sorted by, as a string that sorts the same way.
+fn sort_value(
+    sort: SortKey,
+    title: &str,
+    created_at: DateTime<Utc>,
+    modified_at: DateTime<Utc>,
+) -> String {
+    match sort {
+        SortKey::Modified => modified_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+        SortKey::Created => created_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+        SortKey::Title => title.to_lowercase(),
+    }
+}
+
+/// Orders `items` by sort value, then id, and returns the page after the
+/// cursor. The cursor holds the sort value and id of the last item returned,
+/// so pages stay consistent while items are added or removed.
+fn paginate<T>(
+    items: Vec<T>,
+    query: &ListQuery,
+    value: impl Fn(&T) -> String,
+    id: impl Fn(&T) -> &str,
+) -> Result<Page<T>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let mut keyed: Vec<(String, T)> = items.into_iter().map(|item| (value(&item), item)).collect();
+    keyed.sort_by(|(a, x), (b, y)| a.cmp(b).then_with(|| id(x).cmp(id(y))));
+    if query.descending() {
+        keyed.reverse();
+    }
+    let total = keyed.len();
+
+    let start = match &query.cursor {
+        Some(cursor) => {
+            // Ids contain no '|', so the last one separates the two parts.
+            let (after_value, after_id) = cursor.rsplit_once('|').ok_or_else(|| {
+                CoreError::invalid("cursor", "is not a cursor from a previous page")
+            })?;
+            let after = (after_value, after_id);
+            keyed
+                .iter()
+                .position(|(v, item)| {
+                    let key = (v.as_str(), id(item));
+                    if query.descending() {
+                        key < after
+                    } else {
+                        key > after
+                    }
+                })
+                .unwrap_or(total)
+        }
+        None => 0,
+    };
+
+    let page: Vec<(String, T)> = keyed.into_iter().skip(start).take(limit).collect();
+    let next_cursor = if start + page.len() < total {
+        page.last().map(|(v, item)| format!("{}|{}", v, id(item)))
+    } else {
+        None
+    };
+    Ok(Page {
+        items: page.into_iter().map(|(_, item)| item).collect(),
+        next_cursor,
+        total,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScratchSummaryDto {
+    pub id: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub created_at: String,
+    pub modified_at: String,
+    pub excerpt: String,
+    pub word_count: usize,
+}
+
+impl From<ScratchSummary> for ScratchSummaryDto {
+    fn from(s: ScratchSummary) -> Self {
+        Self {
+            id: s.id,
+            title: s.title,
+            tags: s.tags,
+            created_at: s.created_at.to_rfc3339(),
+            modified_at: s.modified_at.to_rfc3339(),
+            excerpt: s.excerpt,
+            word_count: s.word_count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSummaryDto {
+    pub id: String,
+    pub title: String,
+    pub template_id: String,
+    pub created_at: String,
+    pub modified_at: String,
+    pub section_count: usize,
+    pub link_count: usize,
+    pub archived: bool,
+}
+
+impl From<ProjectSummary> for ProjectSummaryDto {
+    fn from(p: ProjectSummary) -> Self {
+        Self {
+            id: p.id,
+            title: p.title,
+            template_id: p.template_id,
+            created_at: p.created_at.to_rfc3339(),
+            modified_at: p.modified_at.to_rfc3339(),
+            section_count: p.section_count,
+            link_count: p.link_count,
+            archived: p.archived,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn scratch(id: &str, title: &str, age_days: i64) -> Scratch {
+        let at = Utc::now() - Duration::days(age_days);
+        Scratch {
+            id: id.to_string(),
+            title: title.to_string(),
+            content: "one  two\nthree".to_string(),
+            created_at: at,
+            modified_at: at,
+            tags: vec![],
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_paging_follows_cursor() {
+        let scratches: Vec<Scratch> = (0..5)
+            .map(|i| scratch(&format!("s{}", i), &format!("Note {}", i), i))
+            .collect();
+        let mut query = ListQuery {
+            limit: Some(2),
+            ..Default::default()
+        };
+
+        let mut ids = Vec::new();
+        loop {
+            let page = page_scratches(&scratches, &query).unwrap();
+            assert_eq!(page.total, 5);
+            ids.extend(page.items.iter().map(|s| s.id.clone()));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        // Newest first by default.
+        assert_eq!(ids, vec!["s0", "s1", "s2", "s3", "s4"]);
+
+        let first = page_scratches(&scratches, &ListQuery::default()).unwrap();
+        assert_eq!(first.items[0].excerpt, "one two three");
+        assert_eq!(first.items[0].word_count, 3);
+        assert!(first.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_sort_and_filter_by_title() {
+        let scratches = vec![
+            scratch("a", "beta", 0),
+            scratch("b", "Alpha", 1),
+            scratch("c", "gamma", 2),
+        ];
+        let query = ListQuery {
+            sort: SortKey::Title,
+            ..Default::default()
+        };
+        let page = page_scratches(&scratches, &query).unwrap();
+        let titles: Vec<_> = page.items.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Alpha", "beta", "gamma"]);
+
+        let query = ListQuery {
+            text: Some("MM".to_string()),
+            ..query
+        };
+        assert_eq!(page_scratches(&scratches, &query).unwrap().total, 1);
+        let query = ListQuery {
+            cursor: Some("garbage".to_string()),
+            ..query
+        };
+        assert!(page_scratches(&scratches, &query).is_err());
+    }
+}
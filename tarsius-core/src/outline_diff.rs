@@ -0,0 +1,750 @@
+use crate::{word_diff, CoreError, DiffSpan, OutlineNode, Result, ScratchLink};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// One difference between two versions of an outline. Nodes are matched by
+/// id, so a node that was renamed and moved shows up as both.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum OutlineChange {
+    Added {
+        node_id: String,
+        parent_id: String,
+        title: String,
+    },
+    Removed {
+        node_id: String,
+        title: String,
+    },
+    /// The node has another parent, or was reordered among its siblings.
+    Moved {
+        node_id: String,
+        title: String,
+        from_parent: String,
+        to_parent: String,
+    },
+    Renamed {
+        node_id: String,
+        from: String,
+        to: String,
+    },
+    ContentEdited {
+        node_id: String,
+        title: String,
+        content: Vec<DiffSpan>,
+    },
+    LinkAttached {
+        node_id: String,
+        scratch_id: String,
+    },
+    LinkDetached {
+        node_id: String,
+        scratch_id: String,
+    },
+}
+
+/// Where a node sits in its outline.
+struct Placed<'a> {
+    node: &'a OutlineNode,
+    parent: Option<&'a str>,
+}
+
+/// Every node of an outline by id, plus the ids in pre-order.
+struct Flat<'a> {
+    nodes: HashMap<&'a str, Placed<'a>>,
+    order: Vec<&'a str>,
+}
+
+impl<'a> Flat<'a> {
+    fn new(root: &'a OutlineNode) -> Self {
+        let mut flat = Self {
+            nodes: HashMap::new(),
+            order: Vec::new(),
+        };
+        let mut stack = vec![(root, None)];
+        while let Some((node, parent)) = stack.pop() {
+            // A duplicate id keeps its first place; validation rejects them.
+            if flat.nodes.contains_key(node.id.as_str()) {
+                continue;
+            }
+            flat.nodes.insert(&node.id, Placed { node, parent });
+            flat.order.push(&node.id);
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .map(|c| (c, Some(node.id.as_str()))),
+            );
+        }
+        flat
+    }
+
+    fn get(&self, id: &str) -> Option<&Placed<'a>> {
+        self.nodes.get(id)
+    }
+
+    fn parent(&self, id: &str) -> Option<&'a str> {
+        self.get(id).and_then(|p| p.parent)
+    }
+
+    fn child_ids(&self, id: &str) -> Vec<&'a str> {
+        self.get(id)
+            .map(|p| p.node.children.iter().map(|c| c.id.as_str()).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Lists how `new` differs from `old`, new and changed nodes in the order of
+/// `new`, then removed ones.
+pub fn diff_outlines(old: &OutlineNode, new: &OutlineNode) -> Vec<OutlineChange> {
+    let before = Flat::new(old);
+    let after = Flat::new(new);
+    let reordered = reordered_nodes(&before, &after);
+
+    let mut changes = Vec::new();
+    for id in &after.order {
+        let placed = &after.nodes[id];
+        let node = placed.node;
+        let Some(previous) = before.get(id) else {
+            changes.push(OutlineChange::Added {
+                node_id: node.id.clone(),
+                parent_id: placed.parent.unwrap_or_default().to_string(),
+                title: node.title.clone(),
+            });
+            continue;
+        };
+        let old_node = previous.node;
+        if previous.parent != placed.parent || reordered.contains(id) {
+            changes.push(OutlineChange::Moved {
+                node_id: node.id.clone(),
+                title: node.title.clone(),
+                from_parent: previous.parent.unwrap_or_default().to_string(),
+                to_parent: placed.parent.unwrap_or_default().to_string(),
+            });
+        }
+        if old_node.title != node.title {
+            changes.push(OutlineChange::Renamed {
+                node_id: node.id.clone(),
+                from: old_node.title.clone(),
+                to: node.title.clone(),
+            });
+        }
+        if old_node.content != node.content {
+            changes.push(OutlineChange::ContentEdited {
+                node_id: node.id.clone(),
+                title: node.title.clone(),
+                content: word_diff(
+                    old_node.content.as_deref().unwrap_or_default(),
+                    node.content.as_deref().unwrap_or_default(),
+                ),
+            });
+        }
+        for link in &node.scratches {
+            if !has_link(&old_node.scratches, &link.scratch_id) {
+                changes.push(OutlineChange::LinkAttached {
+                    node_id: node.id.clone(),
+                    scratch_id: link.scratch_id.clone(),
+                });
+            }
+        }
+        for link in &old_node.scratches {
+            if !has_link(&node.scratches, &link.scratch_id) {
+                changes.push(OutlineChange::LinkDetached {
+                    node_id: node.id.clone(),
+                    scratch_id: link.scratch_id.clone(),
+                });
+            }
+        }
+    }
+    for id in &before.order {
+        if after.get(id).is_none() {
+            changes.push(OutlineChange::Removed {
+                node_id: id.to_string(),
+                title: before.nodes[id].node.title.clone(),
+            });
+        }
+    }
+    changes
+}
+
+fn has_link(links: &[ScratchLink], scratch_id: &str) -> bool {
+    links.iter().any(|l| l.scratch_id == scratch_id)
+}
+
+/// Nodes that kept their parent but changed places among the siblings that
+/// also kept it. The longest run of siblings still in order counts as
+/// staying put.
+fn reordered_nodes<'a>(before: &Flat<'a>, after: &Flat<'a>) -> HashSet<&'a str> {
+    let mut reordered = HashSet::new();
+    for id in &after.order {
+        let stays =
+            |child: &&str| before.parent(child) == Some(id) && after.parent(child) == Some(id);
+        let old: Vec<&str> = before.child_ids(id).into_iter().filter(stays).collect();
+        let new: Vec<&str> = after.child_ids(id).into_iter().filter(stays).collect();
+        if old == new {
+            continue;
+        }
+        let kept = longest_common_subsequence(&old, &new);
+        reordered.extend(new.into_iter().filter(|c| !kept.contains(c)));
+    }
+    reordered
+}
+
+fn longest_common_subsequence<'a>(a: &[&'a str], b: &[&'a str]) -> HashSet<&'a str> {
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut common = HashSet::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            common.insert(a[i]);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    common
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ConflictKind {
+    Title,
+    Content,
+    /// Both sides moved the node, to different parents.
+    Parent,
+    /// We deleted the node while they changed it; their version is kept.
+    DeletedByUs,
+    /// They deleted the node while we changed it; our version is kept.
+    DeletedByThem,
+}
+
+/// A node both sides changed in different ways. The merged outline holds
+/// our side of it unless `kind` says otherwise.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MergeConflict {
+    pub node_id: String,
+    pub title: String,
+    pub kind: ConflictKind,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutlineMerge {
+    pub outline: OutlineNode,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// A node of the merged outline before it is put back into a tree.
+struct MergedNode {
+    title: String,
+    content: Option<String>,
+    parent: Option<String>,
+    scratches: Vec<ScratchLink>,
+}
+
+/// Merges the changes `ours` and `theirs` each made to `base`, node by node.
+/// Changes to different nodes, or to different fields of a node, combine;
+/// where both sides changed the same thing differently, the conflict is
+/// reported and ours wins.
+pub fn merge_outlines(
+    base: &OutlineNode,
+    ours: &OutlineNode,
+    theirs: &OutlineNode,
+) -> Result<OutlineMerge> {
+    if ours.id != base.id || theirs.id != base.id {
+        return Err(CoreError::invalid(
+            "outline",
+            "only versions of the same outline can be merged",
+        ));
+    }
+    let base = Flat::new(base);
+    let ours = Flat::new(ours);
+    let theirs = Flat::new(theirs);
+
+    let mut ids: Vec<&str> = ours.order.clone();
+    let seen: HashSet<&str> = ids.iter().copied().collect();
+    ids.extend(theirs.order.iter().filter(|id| !seen.contains(*id)));
+
+    let mut merged: HashMap<&str, MergedNode> = HashMap::new();
+    let mut conflicts = Vec::new();
+    for &id in &ids {
+        let (b, o, t) = (base.get(id), ours.get(id), theirs.get(id));
+        let node = match (b, o, t) {
+            (Some(b), Some(o), None) => {
+                if !changed(b, o, &base, &ours) {
+                    continue;
+                }
+                conflicts.push(conflict(o.node, ConflictKind::DeletedByThem, None, None));
+                copy(o)
+            }
+            (Some(b), None, Some(t)) => {
+                if !changed(b, t, &base, &theirs) {
+                    continue;
+                }
+                conflicts.push(conflict(t.node, ConflictKind::DeletedByUs, None, None));
+                copy(t)
+            }
+            (_, Some(o), Some(t)) => {
+                let (title, clash) = merge3(b.map(|b| &b.node.title), &o.node.title, &t.node.title);
+                if clash {
+                    conflicts.push(conflict(
+                        o.node,
+                        ConflictKind::Title,
+                        Some(o.node.title.clone()),
+                        Some(t.node.title.clone()),
+                    ));
+                }
+                let (content, clash) =
+                    merge3(b.map(|b| &b.node.content), &o.node.content, &t.node.content);
+                if clash {
+                    conflicts.push(conflict(
+                        o.node,
+                        ConflictKind::Content,
+                        o.node.content.clone(),
+                        t.node.content.clone(),
+                    ));
+                }
+                let (parent, clash) = merge3(b.map(|b| &b.parent), &o.parent, &t.parent);
+                if clash {
+                    conflicts.push(conflict(
+                        o.node,
+                        ConflictKind::Parent,
+                        o.parent.map(str::to_string),
+                        t.parent.map(str::to_string),
+                    ));
+                }
+                MergedNode {
+                    title,
+                    content,
+                    parent: parent.map(str::to_string),
+                    scratches: merge_links(
+                        b.map(|b| b.node.scratches.as_slice()).unwrap_or_default(),
+                        &o.node.scratches,
+                        &t.node.scratches,
+                    ),
+                }
+            }
+            (None, Some(added), None) | (None, None, Some(added)) => copy(added),
+            (_, None, None) => continue,
+        };
+        merged.insert(id, node);
+    }
+
+    let root = base.order[0];
+    keep_ancestors(&mut merged, &ids, &ours, &theirs, &mut conflicts);
+    reattach_orphans(&mut merged, root, &[&ours, &theirs, &base]);
+    break_cycles(&mut merged, &ids, root, &ours, &mut conflicts);
+
+    let mut children: HashMap<String, Vec<&str>> = HashMap::new();
+    for &id in &ids {
+        if let Some(parent) = merged.get(id).and_then(|n| n.parent.clone()) {
+            children.entry(parent).or_default().push(id);
+        }
+    }
+    let outline = build_tree(root, &mut merged, &children, &base, &ours, &theirs);
+    Ok(OutlineMerge { outline, conflicts })
+}
+
+fn copy(placed: &Placed) -> MergedNode {
+    MergedNode {
+        title: placed.node.title.clone(),
+        content: placed.node.content.clone(),
+        parent: placed.parent.map(str::to_string),
+        scratches: placed.node.scratches.clone(),
+    }
+}
+
+fn conflict(
+    node: &OutlineNode,
+    kind: ConflictKind,
+    ours: Option<String>,
+    theirs: Option<String>,
+) -> MergeConflict {
+    MergeConflict {
+        node_id: node.id.clone(),
+        title: node.title.clone(),
+        kind,
+        ours,
+        theirs,
+    }
+}
+
+/// Whether a side changed a node it kept: its fields, place or children.
+fn changed(base: &Placed, side: &Placed, base_flat: &Flat, side_flat: &Flat) -> bool {
+    base.node.title != side.node.title
+        || base.node.content != side.node.content
+        || base.node.scratches != side.node.scratches
+        || base.parent != side.parent
+        || base_flat.child_ids(&base.node.id) != side_flat.child_ids(&side.node.id)
+}
+
+/// The value of a field after merging, and whether the sides clashed over it.
+fn merge3<T: PartialEq + Clone>(base: Option<&T>, ours: &T, theirs: &T) -> (T, bool) {
+    if ours == theirs || base == Some(theirs) {
+        (ours.clone(), false)
+    } else if base == Some(ours) {
+        (theirs.clone(), false)
+    } else {
+        (ours.clone(), true)
+    }
+}
+
+/// Merges links by scratch: a link stays unless a side removed it, and a
+/// link both kept takes the settings of the side that changed them.
+fn merge_links(
+    base: &[ScratchLink],
+    ours: &[ScratchLink],
+    theirs: &[ScratchLink],
+) -> Vec<ScratchLink> {
+    let find = |links: &[ScratchLink], id: &str| links.iter().find(|l| l.scratch_id == id).cloned();
+    let mut merged: Vec<ScratchLink> = Vec::new();
+    for link in ours.iter().chain(theirs) {
+        let id = link.scratch_id.as_str();
+        if has_link(&merged, id) {
+            continue;
+        }
+        let (b, o, t) = (find(base, id), find(ours, id), find(theirs, id));
+        let (kept, _) = merge3(Some(&b.is_some()), &o.is_some(), &t.is_some());
+        if !kept {
+            continue;
+        }
+        let (link, _) = match (&o, &t) {
+            (Some(o), Some(t)) => merge3(b.as_ref(), o, t),
+            (Some(only), None) | (None, Some(only)) => (only.clone(), false),
+            (None, None) => continue,
+        };
+        merged.push(link);
+    }
+    merged
+}
+
+/// Brings back the parents one side deleted of nodes the other side kept,
+/// as conflicts.
+fn keep_ancestors<'a>(
+    merged: &mut HashMap<&'a str, MergedNode>,
+    ids: &[&'a str],
+    ours: &Flat<'a>,
+    theirs: &Flat<'a>,
+    conflicts: &mut Vec<MergeConflict>,
+) {
+    let mut pending: Vec<&str> = ids.iter().rev().copied().collect();
+    while let Some(id) = pending.pop() {
+        let Some(parent) = merged.get(id).and_then(|n| n.parent.clone()) else {
+            continue;
+        };
+        if merged.contains_key(parent.as_str()) {
+            continue;
+        }
+        let (placed, kind) = match (ours.get(&parent), theirs.get(&parent)) {
+            (Some(placed), _) => (placed, ConflictKind::DeletedByThem),
+            (None, Some(placed)) => (placed, ConflictKind::DeletedByUs),
+            (None, None) => continue,
+        };
+        conflicts.push(conflict(placed.node, kind, None, None));
+        merged.insert(&placed.node.id, copy(placed));
+        pending.push(&placed.node.id);
+    }
+}
+
+/// Gives nodes whose parent was deleted the closest ancestor that is left.
+fn reattach_orphans(merged: &mut HashMap<&str, MergedNode>, root: &str, sides: &[&Flat]) {
+    let ids: Vec<&str> = merged.keys().copied().collect();
+    for id in ids {
+        let Some(mut parent) = merged[id].parent.clone() else {
+            continue;
+        };
+        let mut hops = 0;
+        while !merged.contains_key(parent.as_str()) && hops <= merged.len() {
+            parent = sides
+                .iter()
+                .find_map(|side| side.parent(&parent))
+                .unwrap_or(root)
+                .to_string();
+            hops += 1;
+        }
+        if !merged.contains_key(parent.as_str()) {
+            parent = root.to_string();
+        }
+        merged.get_mut(id).unwrap().parent = Some(parent);
+    }
+}
+
+/// Moves made by both sides can put a node below itself. Such a node goes
+/// back under our parent, or under the root if that is inside the loop too.
+fn break_cycles(
+    merged: &mut HashMap<&str, MergedNode>,
+    ids: &[&str],
+    root: &str,
+    ours: &Flat,
+    conflicts: &mut Vec<MergeConflict>,
+) {
+    for &id in ids {
+        if !merged.contains_key(id) || !is_below(merged, id, id) {
+            continue;
+        }
+        let parent = match ours.parent(id) {
+            Some(parent) if merged.contains_key(parent) && !is_below(merged, parent, id) => parent,
+            _ => root,
+        };
+        let node = merged.get_mut(id).unwrap();
+        if !conflicts
+            .iter()
+            .any(|c| c.node_id == id && c.kind == ConflictKind::Parent)
+        {
+            conflicts.push(MergeConflict {
+                node_id: id.to_string(),
+                title: node.title.clone(),
+                kind: ConflictKind::Parent,
+                ours: ours.parent(id).map(str::to_string),
+                theirs: node.parent.clone(),
+            });
+        }
+        node.parent = Some(parent.to_string());
+    }
+}
+
+/// Whether `ancestor` is a strict ancestor of `id`.
+fn is_below(merged: &HashMap<&str, MergedNode>, id: &str, ancestor: &str) -> bool {
+    let mut current = merged.get(id).and_then(|n| n.parent.as_deref());
+    let mut hops = 0;
+    while let Some(parent) = current {
+        if parent == ancestor {
+            return true;
+        }
+        hops += 1;
+        if hops > merged.len() {
+            return false;
+        }
+        current = merged.get(parent).and_then(|n| n.parent.as_deref());
+    }
+    false
+}
+
+fn build_tree(
+    id: &str,
+    merged: &mut HashMap<&str, MergedNode>,
+    children: &HashMap<String, Vec<&str>>,
+    base: &Flat,
+    ours: &Flat,
+    theirs: &Flat,
+) -> OutlineNode {
+    let node = merged.remove(id).expect("merged node");
+    let placed = children.get(id).cloned().unwrap_or_default();
+    let order = child_order(id, &placed, base, ours, theirs);
+    let mut built = Vec::new();
+    for child in order {
+        if merged.contains_key(child) {
+            built.push(build_tree(child, merged, children, base, ours, theirs));
+        }
+    }
+    OutlineNode {
+        id: id.to_string(),
+        title: node.title,
+        content: node.content,
+        children: built,
+        scratches: node.scratches,
+    }
+}
+
+/// Orders the merged children of `parent`: in the order of the side that
+/// reordered them, with the other side's additions slotted in after the
+/// sibling they followed there.
+fn child_order<'a>(
+    parent: &str,
+    placed: &[&'a str],
+    base: &Flat,
+    ours: &Flat<'a>,
+    theirs: &Flat<'a>,
+) -> Vec<&'a str> {
+    let (primary, other) = if ours.child_ids(parent) == base.child_ids(parent) {
+        (theirs, ours)
+    } else {
+        (ours, theirs)
+    };
+    let mut order: Vec<&str> = primary
+        .child_ids(parent)
+        .into_iter()
+        .filter(|c| placed.contains(c))
+        .collect();
+    let mut previous: Option<&str> = None;
+    for child in other.child_ids(parent) {
+        if placed.contains(&child) && !order.contains(&child) {
+            let at = previous
+                .and_then(|p| order.iter().position(|c| *c == p))
+                .map_or(0, |i| i + 1);
+            order.insert(at, child);
+        }
+        if order.contains(&child) {
+            previous = Some(child);
+        }
+    }
+    for child in placed {
+        if !order.contains(child) {
+            order.push(child);
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InsertionFlags, IntegrationMode};
+
+    fn node(id: &str, children: Vec<OutlineNode>) -> OutlineNode {
+        OutlineNode {
+            id: id.to_string(),
+            title: id.to_uppercase(),
+            content: None,
+            children,
+            scratches: vec![],
+        }
+    }
+
+    fn link(scratch_id: &str) -> ScratchLink {
+        ScratchLink {
+            scratch_id: scratch_id.to_string(),
+            mode: IntegrationMode::Link,
+            insertion: InsertionFlags {
+                body: true,
+                footnote: false,
+                reference: false,
+                appendix: false,
+            },
+            included: None,
+        }
+    }
+
+    fn ids(node: &OutlineNode) -> Vec<String> {
+        node.children.iter().map(|c| c.id.clone()).collect()
+    }
+
+    fn base() -> OutlineNode {
+        node(
+            "root",
+            vec![
+                node("intro", vec![]),
+                node("method", vec![node("data", vec![])]),
+                node("results", vec![]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_diff_reports_each_kind_of_change() {
+        let old = base();
+        let mut new = base();
+        new.children.swap(0, 2);
+        let mut data = new.children[1].children.remove(0);
+        data.title = "Dataset".to_string();
+        data.content = Some("Collected in 2024".to_string());
+        data.scratches.push(link("s1"));
+        new.children[0].children.push(data);
+        new.children.push(node("discussion", vec![]));
+        new.children.retain(|c| c.id != "method");
+
+        let changes = diff_outlines(&old, &new);
+        let kinds: Vec<String> = changes
+            .iter()
+            .map(|c| {
+                let json = serde_json::to_value(c).unwrap();
+                format!(
+                    "{} {}",
+                    json["type"].as_str().unwrap(),
+                    json["node_id"].as_str().unwrap()
+                )
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "Moved data",
+                "Renamed data",
+                "ContentEdited data",
+                "LinkAttached data",
+                "Moved intro",
+                "Added discussion",
+                "Removed method",
+            ]
+        );
+        // Moving one node to the front is one move, not every sibling
+        // shifting.
+        let mut reordered = base();
+        let results = reordered.children.remove(2);
+        reordered.children.insert(0, results);
+        assert_eq!(diff_outlines(&old, &reordered).len(), 1);
+        assert!(diff_outlines(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_merge_combines_changes_and_reports_conflicts() {
+        let base = base();
+        let mut ours = base.clone();
+        ours.children[0].title = "Introduction".to_string();
+        ours.children[2].content = Some("Ours".to_string());
+        ours.children.insert(1, node("background", vec![]));
+        ours.children[2].children[0].scratches.push(link("s1"));
+
+        let mut theirs = base.clone();
+        theirs.children[0].content = Some("Why this matters".to_string());
+        theirs.children[2].content = Some("Theirs".to_string());
+        theirs.children.push(node("discussion", vec![]));
+        // They delete "method", which we changed below.
+        theirs.children.remove(1);
+
+        let merge = merge_outlines(&base, &ours, &theirs).unwrap();
+        let outline = &merge.outline;
+        assert_eq!(
+            ids(outline),
+            vec!["intro", "background", "method", "results", "discussion"]
+        );
+        assert_eq!(outline.children[0].title, "Introduction");
+        assert_eq!(
+            outline.children[0].content.as_deref(),
+            Some("Why this matters")
+        );
+        assert_eq!(outline.children[3].content.as_deref(), Some("Ours"));
+        assert_eq!(outline.children[2].children[0].scratches.len(), 1);
+
+        let conflicts: Vec<(&str, ConflictKind)> = merge
+            .conflicts
+            .iter()
+            .map(|c| (c.node_id.as_str(), c.kind))
+            .collect();
+        assert_eq!(
+            conflicts,
+            vec![
+                ("data", ConflictKind::DeletedByThem),
+                ("results", ConflictKind::Content),
+                ("method", ConflictKind::DeletedByThem),
+            ]
+        );
+
+        // Moving two nodes into each other keeps the tree intact.
+        let mut ours = base.clone();
+        let intro = ours.children.remove(0);
+        ours.children[1].children.push(intro);
+        let mut theirs = base.clone();
+        let results = theirs.children.remove(2);
+        theirs.children[0].children.push(results);
+        let merge = merge_outlines(&base, &ours, &theirs).unwrap();
+        assert_eq!(ids(&merge.outline), vec!["method", "results"]);
+        assert_eq!(ids(&merge.outline.children[1]), vec!["intro"]);
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!(merge.conflicts[0].kind, ConflictKind::Parent);
+
+        assert!(merge_outlines(&base, &node("other", vec![]), &base).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use crate::{
+    InsertionFlags, InsertionFlagsDto, IntegrationMode, OutlineNode, Project, RemovedLink,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// An outline node that links to a given scratch.
+#[derive(Debug, Clone)]
+pub struct ScratchReference {
+    pub scratch_id: String,
+    pub project_id: String,
+    pub project_title: String,
+    pub node_id: String,
+    pub node_title: String,
+    pub mode: IntegrationMode,
+    pub insertion: InsertionFlags,
+}
+
+/// What to do with outline links when the scratch they point to is deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeletePolicy {
+    /// Refuse to delete a scratch that is still referenced.
+    Block,
+    /// Remove every link to the scratch.
+    Unlink,
+    /// Copy the body of `Include` links into the node content, then remove
+    /// all links to the scratch.
+    Inline,
+}
+
+impl FromStr for DeletePolicy {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "Block" => Ok(DeletePolicy::Block),
+            "Unlink" => Ok(DeletePolicy::Unlink),
+            "Inline" => Ok(DeletePolicy::Inline),
+            other => Err(format!("Unknown delete policy: {}", other)),
+        }
+    }
+}
+
+/// Outcome of `ScratchManager::delete`.
+#[derive(Debug, Clone)]
+pub struct DeleteReport {
+    pub scratch_id: String,
+    pub references: Vec<ScratchReference>,
+}
+
+/// A `ScratchLink` whose scratch no longer exists.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DanglingLink {
+    pub node_id: String,
+    pub node_title: String,
+    pub scratch_id: String,
+}
+
+pub fn find_references(project: &Project, scratch_id: &str) -> Vec<ScratchReference> {
+    let mut references = Vec::new();
+    collect_references(project, &project.outline, Some(scratch_id), &mut references);
+    references
+}
+
+/// Lists every scratch link in the project's outline.
+pub fn all_references(project: &Project) -> Vec<ScratchReference> {
+    let mut references = Vec::new();
+    collect_references(project, &project.outline, None, &mut references);
+    references
+}
+
+fn collect_references(
+    project: &Project,
+    node: &OutlineNode,
+    scratch_id: Option<&str>,
+    out: &mut Vec<ScratchReference>,
+) {
+    for link in node
+        .scratches
+        .iter()
+        .filter(|l| scratch_id.is_none_or(|id| l.scratch_id == id))
+    {
+        out.push(ScratchReference {
+            scratch_id: link.scratch_id.clone(),
+            project_id: project.id.clone(),
+            project_title: project.title.clone(),
+            node_id: node.id.clone(),
+            node_title: node.title.clone(),
+            mode: link.mode.clone(),
+            insertion: link.insertion.clone(),
+        });
+    }
+    for child in &node.children {
+        collect_references(project, child, scratch_id, out);
+    }
+}
+
+/// Removes every link to `scratch_id` from the outline.
+///
+/// With `inline_content`, `Include` links are replaced by their included
+/// copy (or the given scratch body if they have none) appended to the node
+/// content, and are not reported back; all other removed links are returned
+/// so they can be restored later.
+pub(crate) fn detach_links(
+    project_id: &str,
+    node: &mut OutlineNode,
+    scratch_id: &str,
+    inline_content: Option<&str>,
+) -> Vec<RemovedLink> {
+    let mut removed = Vec::new();
+    let mut kept = Vec::with_capacity(node.scratches.len());
+    for (index, link) in node.scratches.drain(..).enumerate() {
+        if link.scratch_id != scratch_id {
+            kept.push(link);
+            continue;
+        }
+        match (inline_content, &link.mode) {
+            (Some(body), IntegrationMode::Include) => {
+                let text = link
+                    .included
+                    .as_ref()
+                    .map_or(body, |copy| copy.text.as_str());
+                node.content = Some(match node.content.take() {
+                    Some(existing) if !existing.is_empty() => format!("{}\n\n{}", existing, text),
+                    _ => text.to_string(),
+                });
+            }
+            _ => removed.push(RemovedLink {
+                project_id: project_id.to_string(),
+                node_id: node.id.clone(),
+                index,
+                link,
+            }),
+        }
+    }
+    node.scratches = kept;
+    for child in &mut node.children {
+        removed.extend(detach_links(project_id, child, scratch_id, inline_content));
+    }
+    removed
+}
+
+pub(crate) fn collect_dangling(
+    node: &OutlineNode,
+    exists: &dyn Fn(&str) -> bool,
+    out: &mut Vec<DanglingLink>,
+) {
+    for link in &node.scratches {
+        if !exists(&link.scratch_id) {
+            out.push(DanglingLink {
+                node_id: node.id.clone(),
+                node_title: node.title.clone(),
+                scratch_id: link.scratch_id.clone(),
+            });
+        }
+    }
+    for child in &node.children {
+        collect_dangling(child, exists, out);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScratchReferenceDto {
+    pub scratch_id: String,
+    pub project_id: String,
+    pub project_title: String,
+    pub node_id: String,
+    pub node_title: String,
+    pub mode: String,
+    pub insertion: InsertionFlagsDto,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteReportDto {
+    pub scratch_id: String,
+    pub references: Vec<ScratchReferenceDto>,
+}
+
+impl From<ScratchReference> for ScratchReferenceDto {
+    fn from(r: ScratchReference) -> Self {
+        Self {
+            scratch_id: r.scratch_id,
+            project_id: r.project_id,
+            project_title: r.project_title,
+            node_id: r.node_id,
+            node_title: r.node_title,
+            mode: match r.mode {
+                IntegrationMode::Include => "Include".to_string(),
+                IntegrationMode::Link => "Link".to_string(),
+            },
+            insertion: r.insertion.into(),
+        }
+    }
+}
+
+impl From<DeleteReport> for DeleteReportDto {
+    fn from(r: DeleteReport) -> Self {
+        Self {
+            scratch_id: r.scratch_id,
+            references: r.references.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProjectSettings, ScratchLink};
+    use chrono::Utc;
+
+    fn link(scratch_id: &str, mode: IntegrationMode) -> ScratchLink {
+        ScratchLink {
+            scratch_id: scratch_id.to_string(),
+            mode,
+            insertion: InsertionFlags {
+                body: true,
+                footnote: false,
+                reference: false,
+                appendix: false,
+            },
+            included: None,
+        }
+    }
+
+    fn node(id: &str, scratches: Vec<ScratchLink>, children: Vec<OutlineNode>) -> OutlineNode {
+        OutlineNode {
+            id: id.to_string(),
+            title: id.to_uppercase(),
+            content: None,
+            children,
+            scratches,
+        }
+    }
+
+    fn project() -> Project {
+        Project {
+            id: "p1".to_string(),
+            title: "Paper".to_string(),
+            outline: node(
+                "root",
+                vec![link("s1", IntegrationMode::Link)],
+                vec![node(
+                    "intro",
+                    vec![
+                        link("other", IntegrationMode::Link),
+                        link("s1", IntegrationMode::Include),
+                    ],
+                    vec![],
+                )],
+            ),
+            settings: ProjectSettings {
+                template_id: "t".to_string(),
+                output_dir: "out".to_string(),
+            },
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            archived: false,
+        }
+    }
+
+    #[test]
+    fn test_find_references_walks_the_tree() {
+        let refs = find_references(&project(), "s1");
+        let nodes: Vec<_> = refs.iter().map(|r| r.node_id.as_str()).collect();
+        assert_eq!(nodes, vec!["root", "intro"]);
+        assert!(find_references(&project(), "missing").is_empty());
+    }
+
+    #[test]
+    fn test_detach_links_inlines_included_content() {
+        let mut project = project();
+        let removed = detach_links("p1", &mut project.outline, "s1", Some("Scratch body"));
+
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].node_id, "root");
+        assert_eq!(removed[0].index, 0);
+        assert!(find_references(&project, "s1").is_empty());
+        let intro = project.outline.find("intro").unwrap();
+        assert_eq!(intro.content.as_deref(), Some("Scratch body"));
+        assert_eq!(intro.scratches.len(), 1);
+    }
+
+    #[test]
+    fn test_collect_dangling() {
+        let mut dangling = Vec::new();
+        collect_dangling(&project().outline, &|id| id == "s1", &mut dangling);
+        assert_eq!(dangling.len(), 1);
+        assert_eq!(dangling[0].scratch_id, "other");
+        assert_eq!(dangling[0].node_id, "intro");
+    }
+}
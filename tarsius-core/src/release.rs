@@ -0,0 +1,269 @@
+use crate::{
+    all_references, diff_outlines, word_diff, DiffSpan, OutlineChange, Project, Result, Scratch,
+    Template,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A frozen copy of a project as it was sent out, together with everything
+/// needed to rebuild it. Releases are never changed once made.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    pub id: String,
+    pub project_id: String,
+    /// What the release was for, such as "Submitted to the journal".
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub project: Project,
+    /// The scratches the outline linked or included, as they were.
+    pub scratches: Vec<Scratch>,
+    pub template: Option<Template>,
+    /// Linked scratches that no longer existed when the release was made.
+    pub missing_scratches: Vec<String>,
+}
+
+impl Release {
+    /// Freezes `project` along with the scratches its outline uses, taken
+    /// from `scratches`.
+    pub fn snapshot(
+        project: &Project,
+        scratches: &[Scratch],
+        template: Option<Template>,
+        name: String,
+    ) -> Self {
+        let mut used = Vec::new();
+        let mut missing = Vec::new();
+        for reference in all_references(project) {
+            let id = reference.scratch_id;
+            if used.iter().any(|s: &Scratch| s.id == id) || missing.contains(&id) {
+                continue;
+            }
+            match scratches.iter().find(|s| s.id == id) {
+                Some(scratch) => used.push(scratch.clone()),
+                None => missing.push(id),
+            }
+        }
+        Self {
+            id: Uuid::new_v4().to_string(),
+            project_id: project.id.clone(),
+            name,
+            created_at: Utc::now(),
+            project: project.clone(),
+            scratches: used,
+            template,
+            missing_scratches: missing,
+        }
+    }
+}
+
+/// The build output stored with a release.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReleaseOutput {
+    pub tex: String,
+    pub bib: Option<String>,
+    /// The PDF compiled from the sources, if there was one.
+    pub pdf: Option<Vec<u8>>,
+}
+
+/// A release without its frozen content, for listing.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleaseSummary {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub project_title: String,
+    pub scratch_count: usize,
+}
+
+impl From<&Release> for ReleaseSummary {
+    fn from(release: &Release) -> Self {
+        Self {
+            id: release.id.clone(),
+            project_id: release.project_id.clone(),
+            name: release.name.clone(),
+            created_at: release.created_at,
+            project_title: release.project.title.clone(),
+            scratch_count: release.scratches.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A scratch whose frozen content differs between two releases.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScratchChange {
+    pub scratch_id: String,
+    /// The title in the later release, or in the earlier one if removed.
+    pub title: String,
+    pub kind: ChangeKind,
+    pub content: Vec<DiffSpan>,
+}
+
+/// How a project changed from one release to another.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReleaseDiff {
+    pub from: String,
+    pub to: String,
+    pub outline: Vec<OutlineChange>,
+    pub scratches: Vec<ScratchChange>,
+    /// The generated LaTeX.
+    pub tex: Vec<DiffSpan>,
+}
+
+impl ReleaseDiff {
+    pub fn between(
+        from: &Release,
+        from_output: &ReleaseOutput,
+        to: &Release,
+        to_output: &ReleaseOutput,
+    ) -> Self {
+        let mut scratches = Vec::new();
+        for scratch in &to.scratches {
+            match from.scratches.iter().find(|s| s.id == scratch.id) {
+                Some(old) if old.content == scratch.content => {}
+                Some(old) => scratches.push(ScratchChange {
+                    scratch_id: scratch.id.clone(),
+                    title: scratch.title.clone(),
+                    kind: ChangeKind::Changed,
+                    content: word_diff(&old.content, &scratch.content),
+                }),
+                None => scratches.push(ScratchChange {
+                    scratch_id: scratch.id.clone(),
+                    title: scratch.title.clone(),
+                    kind: ChangeKind::Added,
+                    content: word_diff("", &scratch.content),
+                }),
+            }
+        }
+        for scratch in &from.scratches {
+            if !to.scratches.iter().any(|s| s.id == scratch.id) {
+                scratches.push(ScratchChange {
+                    scratch_id: scratch.id.clone(),
+                    title: scratch.title.clone(),
+                    kind: ChangeKind::Removed,
+                    content: word_diff(&scratch.content, ""),
+                });
+            }
+        }
+        Self {
+            from: from.id.clone(),
+            to: to.id.clone(),
+            outline: diff_outlines(&from.project.outline, &to.project.outline),
+            scratches,
+            tex: word_diff(&from_output.tex, &to_output.tex),
+        }
+    }
+}
+
+/// Stores releases next to their project. There is deliberately no way to
+/// change a stored release.
+pub trait ReleaseRepository: Send + Sync {
+    /// Stores a new release with its build output. Fails with
+    /// `CoreError::Conflict` if a release with the same id exists.
+    fn create(&self, release: &Release, output: &ReleaseOutput) -> Result<()>;
+    /// The releases of a project, oldest first.
+    fn list(&self, project_id: &str) -> Result<Vec<Release>>;
+    fn load(&self, project_id: &str, release_id: &str) -> Result<Release>;
+    fn output(&self, project_id: &str, release_id: &str) -> Result<ReleaseOutput>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InsertionFlags, IntegrationMode, OutlineNode, ProjectSettings, ScratchLink};
+
+    fn scratch(id: &str, content: &str) -> Scratch {
+        Scratch {
+            id: id.to_string(),
+            title: id.to_uppercase(),
+            content: content.to_string(),
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            tags: vec![],
+            source: None,
+        }
+    }
+
+    fn project(links: &[&str]) -> Project {
+        let node = OutlineNode {
+            id: "n1".to_string(),
+            title: "Method".to_string(),
+            content: None,
+            children: vec![],
+            scratches: links
+                .iter()
+                .map(|id| ScratchLink {
+                    scratch_id: id.to_string(),
+                    mode: IntegrationMode::Link,
+                    insertion: InsertionFlags {
+                        body: true,
+                        footnote: false,
+                        reference: false,
+                        appendix: false,
+                    },
+                    included: None,
+                })
+                .collect(),
+        };
+        Project {
+            id: "p1".to_string(),
+            title: "Paper".to_string(),
+            outline: OutlineNode {
+                id: "root".to_string(),
+                title: "Root".to_string(),
+                content: None,
+                children: vec![node],
+                scratches: vec![],
+            },
+            settings: ProjectSettings {
+                template_id: "none".to_string(),
+                output_dir: "out".to_string(),
+            },
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            archived: false,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_and_diff() {
+        let scratches = vec![scratch("a", "old text"), scratch("b", "kept")];
+        let first = Release::snapshot(
+            &project(&["a", "b", "gone"]),
+            &scratches,
+            None,
+            "Draft".to_string(),
+        );
+        assert_eq!(first.scratches.len(), 2);
+        assert_eq!(first.missing_scratches, vec!["gone"]);
+
+        let scratches = vec![scratch("a", "new text"), scratch("b", "kept")];
+        let second = Release::snapshot(&project(&["a"]), &scratches, None, "Final".to_string());
+        let output = ReleaseOutput::default();
+        let diff = ReleaseDiff::between(&first, &output, &second, &output);
+
+        let kinds: Vec<(&str, ChangeKind)> = diff
+            .scratches
+            .iter()
+            .map(|c| (c.scratch_id.as_str(), c.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![("a", ChangeKind::Changed), ("b", ChangeKind::Removed)]
+        );
+        assert_eq!(diff.outline.len(), 2);
+        assert!(diff.outline.contains(&OutlineChange::LinkDetached {
+            node_id: "n1".to_string(),
+            scratch_id: "b".to_string(),
+        }));
+        assert!(diff.tex.is_empty());
+    }
+}
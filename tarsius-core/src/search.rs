@@ -0,0 +1,207 @@
+use crate::{OutlineNode, Project, Scratch};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchHitKind {
+    Scratch,
+    Project,
+    OutlineNode,
+}
+
+/// Where a search term matched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub kind: SearchHitKind,
+    /// Scratch, project or node id, depending on `kind`.
+    pub id: String,
+    /// Owning project for `Project` and `OutlineNode` hits.
+    pub project_id: Option<String>,
+    pub title: String,
+    pub snippet: String,
+}
+
+const SNIPPET_RADIUS: usize = 40;
+
+/// Case-insensitive substring search over scratch titles, bodies and tags,
+/// and over project titles and outline node titles and content.
+pub fn search(query: &str, scratches: &[Scratch], projects: &[Project]) -> Vec<SearchHit> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        return vec![];
+    }
+    let mut hits = Vec::new();
+    for scratch in scratches {
+        let tag_match = scratch
+            .tags
+            .iter()
+            .find(|tag| tag.to_lowercase().contains(&needle));
+        let snippet = if let Some(snippet) = snippet(&scratch.content, &needle) {
+            Some(snippet)
+        } else if let Some(tag) = tag_match {
+            Some(format!("#{}", tag))
+        } else if scratch.title.to_lowercase().contains(&needle) {
+            Some(excerpt(&scratch.content))
+        } else {
+            None
+        };
+        if let Some(snippet) = snippet {
+            hits.push(SearchHit {
+                kind: SearchHitKind::Scratch,
+                id: scratch.id.clone(),
+                project_id: None,
+                title: scratch.title.clone(),
+                snippet,
+            });
+        }
+    }
+    for project in projects {
+        if project.title.to_lowercase().contains(&needle) {
+            hits.push(SearchHit {
+                kind: SearchHitKind::Project,
+                id: project.id.clone(),
+                project_id: Some(project.id.clone()),
+                title: project.title.clone(),
+                snippet: String::new(),
+            });
+        }
+        search_node(project, &project.outline, &needle, &mut hits);
+    }
+    hits
+}
+
+fn search_node(project: &Project, node: &OutlineNode, needle: &str, hits: &mut Vec<SearchHit>) {
+    let content = node.content.as_deref().unwrap_or("");
+    let snippet = snippet(content, needle).or_else(|| {
+        node.title
+            .to_lowercase()
+            .contains(needle)
+            .then(|| excerpt(content))
+    });
+    if let Some(snippet) = snippet {
+        hits.push(SearchHit {
+            kind: SearchHitKind::OutlineNode,
+            id: node.id.clone(),
+            project_id: Some(project.id.clone()),
+            title: node.title.clone(),
+            snippet,
+        });
+    }
+    for child in &node.children {
+        search_node(project, child, needle, hits);
+    }
+}
+
+fn snippet(text: &str, needle: &str) -> Option<String> {
+    // Lowercasing can change the number of chars, so remember which
+    // original char every lowered one came from and slice the original.
+    let chars: Vec<char> = text.chars().collect();
+    let mut lower = String::new();
+    let mut origin = Vec::new();
+    for (index, c) in chars.iter().enumerate() {
+        for lowered in c.to_lowercase() {
+            lower.push(lowered);
+            origin.push(index);
+        }
+    }
+    let pos = lower.find(needle)?;
+    let first = lower[..pos].chars().count();
+    let last = first + needle.chars().count() - 1;
+    let start = origin[first].saturating_sub(SNIPPET_RADIUS);
+    let end = (origin[last] + 1 + SNIPPET_RADIUS).min(chars.len());
+    let mut snippet: String = chars[start..end].iter().collect();
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    Some(snippet.replace('\n', " "))
+}
+
+/// The first few words of `text`, for previews.
+pub fn excerpt(text: &str) -> String {
+    let limit = SNIPPET_RADIUS * 2;
+    let mut excerpt: String = text.chars().take(limit).collect();
+    if text.chars().count() > limit {
+        excerpt.push('…');
+    }
+    excerpt.replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectSettings;
+    use chrono::Utc;
+
+    fn scratch(id: &str, title: &str, content: &str, tags: &[&str]) -> Scratch {
+        Scratch {
+            id: id.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_search_matches_content_tags_and_outline() {
+        let scratches = vec![
+            scratch("a", "Methods", "We used a Bayesian model.", &[]),
+            scratch("b", "Ideas", "Nothing here", &["bayesian"]),
+            scratch("c", "Other", "Unrelated", &[]),
+        ];
+        let project = Project {
+            id: "p".to_string(),
+            title: "Thesis".to_string(),
+            outline: OutlineNode {
+                id: "root".to_string(),
+                title: "Root".to_string(),
+                content: None,
+                children: vec![OutlineNode {
+                    id: "n1".to_string(),
+                    title: "Bayesian inference".to_string(),
+                    content: None,
+                    children: vec![],
+                    scratches: vec![],
+                }],
+                scratches: vec![],
+            },
+            settings: ProjectSettings {
+                template_id: "t".to_string(),
+                output_dir: "out".to_string(),
+            },
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            archived: false,
+        };
+
+        let hits = search("BAYES", &scratches, &[project]);
+        let ids: Vec<_> = hits.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "n1"]);
+        assert_eq!(hits[0].snippet, "We used a Bayesian model.");
+        assert_eq!(hits[1].snippet, "#bayesian");
+        assert_eq!(hits[2].project_id.as_deref(), Some("p"));
+        assert!(search("  ", &scratches, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_snippet_is_trimmed_around_match() {
+        let text = format!("{}needle{}", "x".repeat(100), "y".repeat(100));
+        let snippet = snippet(&text, "needle").unwrap();
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert_eq!(snippet.chars().count(), 40 + 6 + 40 + 2);
+    }
+
+    #[test]
+    fn test_snippet_handles_case_folding_that_changes_length() {
+        // 'İ' lowercases to two chars, so positions in the lowered text run
+        // ahead of the original.
+        let text = format!("{}needle", "İ".repeat(100));
+        let trimmed = snippet(&text, "needle").unwrap();
+        assert_eq!(trimmed, format!("…{}needle", "İ".repeat(40)));
+        assert_eq!(snippet("İstanbul", "i̇s").unwrap(), "İstanbul");
+    }
+}
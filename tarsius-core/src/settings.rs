@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+/// Per-workspace preferences, stored alongside the workspace data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceSettings {
+    /// Days a deleted item stays in the trash before it is purged.
+    pub trash_retention_days: u32,
+    pub backup: BackupSettings,
+    pub server: ServerSettings,
+    pub cache: CacheSettings,
+    pub history: HistorySettings,
+}
+
+impl Default for WorkspaceSettings {
+    fn default() -> Self {
+        Self {
+            trash_retention_days: 30,
+            backup: BackupSettings::default(),
+            server: ServerSettings::default(),
+            cache: CacheSettings::default(),
+            history: HistorySettings::default(),
+        }
+    }
+}
+
+/// Schedule for automatic whole-workspace backups.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    /// Minimum time between two automatic backups.
+    pub interval_hours: u32,
+    /// Number of automatic backups to keep; older ones are deleted.
+    pub keep: usize,
+    /// Where backups are written. Defaults to `backups/` in the workspace.
+    pub directory: Option<String>,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: 24,
+            keep: 7,
+            directory: None,
+        }
+    }
+}
+
+/// The local API for editor and script integrations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+    pub enabled: bool,
+    /// Port on 127.0.0.1; `0` picks a free one.
+    pub port: u16,
+    /// Bearer token clients must send. A random one is generated at startup
+    /// when unset.
+    pub token: Option<String>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7733,
+            token: None,
+        }
+    }
+}
+
+/// In-memory caching of scratches, projects and templates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheSettings {
+    /// Memory the caches may use together; `0` turns caching off.
+    pub memory_budget_mb: u32,
+    /// How often the workspace is checked for files changed by other
+    /// programs.
+    pub watch_interval_ms: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            memory_budget_mb: 64,
+            watch_interval_ms: 1000,
+        }
+    }
+}
+
+impl CacheSettings {
+    pub fn memory_budget(&self) -> usize {
+        self.memory_budget_mb as usize * 1024 * 1024
+    }
+
+    pub fn watch_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.watch_interval_ms)
+    }
+}
+
+/// How many versions of each scratch are kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistorySettings {
+    /// Versions kept per scratch, counting the current one.
+    pub max_versions: usize,
+    /// Versions older than this are dropped; the current one always stays.
+    pub retention_days: u32,
+    /// A save made within this many seconds of the last version replaces
+    /// it instead of adding one, so autosaves while typing end up as a
+    /// single version. `0` keeps every save.
+    pub merge_window_secs: u32,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self {
+            max_versions: 50,
+            retention_days: 90,
+            merge_window_secs: 300,
+        }
+    }
+}
+
+impl WorkspaceSettings {
+    pub fn trash_retention(&self) -> chrono::Duration {
+        chrono::Duration::days(i64::from(self.trash_retention_days))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_fields_use_defaults() {
+        let settings: WorkspaceSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.trash_retention_days, 30);
+        assert_eq!(settings.trash_retention(), chrono::Duration::days(30));
+        assert!(settings.backup.enabled);
+        assert_eq!(settings.backup.keep, 7);
+        assert!(!settings.server.enabled);
+        assert_eq!(settings.history.max_versions, 50);
+    }
+}
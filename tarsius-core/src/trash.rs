@@ -0,0 +1,417 @@
+use crate::{CoreError, Project, ProjectRepository, Result, Scratch, ScratchLink};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrashKind {
+    Scratch,
+    Project,
+}
+
+impl FromStr for TrashKind {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "Scratch" => Ok(TrashKind::Scratch),
+            "Project" => Ok(TrashKind::Project),
+            other => Err(format!("Unknown trash kind: {}", other)),
+        }
+    }
+}
+
+/// A link that was detached from a project outline when its scratch was deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemovedLink {
+    pub project_id: String,
+    pub node_id: String,
+    /// Position of the link within the node's `scratches` list before any
+    /// link was removed from it.
+    pub index: usize,
+    pub link: ScratchLink,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub kind: TrashKind,
+    pub title: String,
+    pub deleted_at: DateTime<Utc>,
+    #[serde(default)]
+    pub removed_links: Vec<RemovedLink>,
+}
+
+impl TrashEntry {
+    pub fn for_scratch(scratch: &Scratch, removed_links: Vec<RemovedLink>) -> Self {
+        Self {
+            id: scratch.id.clone(),
+            kind: TrashKind::Scratch,
+            title: scratch.title.clone(),
+            deleted_at: Utc::now(),
+            removed_links,
+        }
+    }
+
+    pub fn for_project(project: &Project) -> Self {
+        Self {
+            id: project.id.clone(),
+            kind: TrashKind::Project,
+            title: project.title.clone(),
+            deleted_at: Utc::now(),
+            removed_links: vec![],
+        }
+    }
+}
+
+pub trait TrashRepository: Send + Sync {
+    /// Moves the live item described by `entry` into the trash.
+    fn trash(&self, entry: &TrashEntry) -> Result<()>;
+    fn list(&self) -> Result<Vec<TrashEntry>>;
+    /// Moves an item back to its original location and returns its entry.
+    fn restore(&self, kind: TrashKind, id: &str) -> Result<TrashEntry>;
+    fn purge(&self, kind: TrashKind, id: &str) -> Result<()>;
+}
+
+pub struct TrashManager {
+    trash: Box<dyn TrashRepository>,
+    projects: Box<dyn ProjectRepository>,
+    retention: Duration,
+}
+
+impl TrashManager {
+    pub fn new(
+        trash: Box<dyn TrashRepository>,
+        projects: Box<dyn ProjectRepository>,
+        retention: Duration,
+    ) -> Self {
+        Self {
+            trash,
+            projects,
+            retention,
+        }
+    }
+
+    /// Lists trashed items, most recently deleted first.
+    pub fn list(&self) -> Result<Vec<TrashEntry>> {
+        let mut entries = self.trash.list()?;
+        entries.sort_by_key(|e| std::cmp::Reverse(e.deleted_at));
+        Ok(entries)
+    }
+
+    pub fn restore(&self, kind: TrashKind, id: &str) -> Result<TrashEntry> {
+        let entry = self.trash.restore(kind, id)?;
+        if kind == TrashKind::Scratch {
+            self.restore_links(&entry)?;
+        }
+        Ok(entry)
+    }
+
+    pub fn purge(&self, kind: TrashKind, id: &str) -> Result<()> {
+        self.trash.purge(kind, id)
+    }
+
+    /// Permanently removes every item that has been in the trash longer than
+    /// the retention period.
+    pub fn purge_expired(&self, now: DateTime<Utc>) -> Result<Vec<TrashEntry>> {
+        let mut purged = Vec::new();
+        for entry in self.trash.list()? {
+            if entry.deleted_at + self.retention <= now {
+                self.trash.purge(entry.kind, &entry.id)?;
+                purged.push(entry);
+            }
+        }
+        Ok(purged)
+    }
+
+    pub fn expires_at(&self, entry: &TrashEntry) -> DateTime<Utc> {
+        entry.deleted_at + self.retention
+    }
+
+    // Re-attaches links removed at deletion time. Projects or nodes that no
+    // longer exist are skipped, as are links that were re-added meanwhile.
+    fn restore_links(&self, entry: &TrashEntry) -> Result<()> {
+        let mut by_project: BTreeMap<&str, Vec<&RemovedLink>> = BTreeMap::new();
+        for removed in &entry.removed_links {
+            by_project
+                .entry(removed.project_id.as_str())
+                .or_default()
+                .push(removed);
+        }
+        for (project_id, mut links) in by_project {
+            let mut project = match self.projects.load(project_id) {
+                Ok(project) => project,
+                Err(CoreError::NotFound { .. }) => continue,
+                Err(e) => return Err(e),
+            };
+            // Inserting in ascending order puts each link back at its
+            // original position.
+            links.sort_by_key(|removed| removed.index);
+            let mut restored_into = HashSet::new();
+            let mut changed = false;
+            for removed in links {
+                let Some(node) = project.outline.find_mut(&removed.node_id) else {
+                    continue;
+                };
+                if !restored_into.contains(&node.id)
+                    && node
+                        .scratches
+                        .iter()
+                        .any(|link| link.scratch_id == removed.link.scratch_id)
+                {
+                    continue;
+                }
+                restored_into.insert(node.id.clone());
+                let index = removed.index.min(node.scratches.len());
+                node.scratches.insert(index, removed.link.clone());
+                changed = true;
+            }
+            if changed {
+                project.modified_at = Utc::now();
+                self.projects.save(&project)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntryDto {
+    pub id: String,
+    pub kind: String, // "Scratch" or "Project"
+    pub title: String,
+    pub deleted_at: String,
+    pub removed_links: usize,
+}
+
+impl From<TrashEntry> for TrashEntryDto {
+    fn from(e: TrashEntry) -> Self {
+        Self {
+            id: e.id,
+            kind: match e.kind {
+                TrashKind::Scratch => "Scratch".to_string(),
+                TrashKind::Project => "Project".to_string(),
+            },
+            title: e.title,
+            deleted_at: e.deleted_at.to_rfc3339(),
+            removed_links: e.removed_links.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EntityKind, InsertionFlags, IntegrationMode, OutlineNode, ProjectSettings};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct MemoryTrash {
+        entries: Mutex<Vec<TrashEntry>>,
+    }
+
+    impl TrashRepository for Arc<MemoryTrash> {
+        fn trash(&self, entry: &TrashEntry) -> Result<()> {
+            self.entries.lock().unwrap().push(entry.clone());
+            Ok(())
+        }
+
+        fn list(&self) -> Result<Vec<TrashEntry>> {
+            Ok(self.entries.lock().unwrap().clone())
+        }
+
+        fn restore(&self, kind: TrashKind, id: &str) -> Result<TrashEntry> {
+            let mut entries = self.entries.lock().unwrap();
+            let pos = entries
+                .iter()
+                .position(|e| e.kind == kind && e.id == id)
+                .ok_or_else(|| CoreError::not_found(EntityKind::TrashEntry, id))?;
+            Ok(entries.remove(pos))
+        }
+
+        fn purge(&self, kind: TrashKind, id: &str) -> Result<()> {
+            self.restore(kind, id).map(|_| ())
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryProjects {
+        projects: Mutex<Vec<Project>>,
+    }
+
+    impl ProjectRepository for Arc<MemoryProjects> {
+        fn save(&self, project: &Project) -> Result<()> {
+            let mut projects = self.projects.lock().unwrap();
+            projects.retain(|p| p.id != project.id);
+            projects.push(project.clone());
+            Ok(())
+        }
+
+        fn load(&self, id: &str) -> Result<Project> {
+            self.projects
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|p| p.id == id)
+                .cloned()
+                .ok_or_else(|| CoreError::not_found(EntityKind::Project, id))
+        }
+
+        fn list(&self) -> Result<Vec<Project>> {
+            Ok(self.projects.lock().unwrap().clone())
+        }
+
+        fn delete(&self, id: &str) -> Result<()> {
+            self.projects.lock().unwrap().retain(|p| p.id != id);
+            Ok(())
+        }
+    }
+
+    fn link(scratch_id: &str) -> ScratchLink {
+        ScratchLink {
+            scratch_id: scratch_id.to_string(),
+            mode: IntegrationMode::Link,
+            insertion: InsertionFlags {
+                body: true,
+                footnote: false,
+                reference: false,
+                appendix: false,
+            },
+            included: None,
+        }
+    }
+
+    fn project_with_links(links: Vec<ScratchLink>) -> Project {
+        Project {
+            id: "p1".to_string(),
+            title: "Project".to_string(),
+            outline: OutlineNode {
+                id: "root".to_string(),
+                title: "Root".to_string(),
+                content: None,
+                children: vec![],
+                scratches: links,
+            },
+            settings: ProjectSettings {
+                template_id: "t".to_string(),
+                output_dir: "out".to_string(),
+            },
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            archived: false,
+        }
+    }
+
+    #[test]
+    fn test_restore_scratch_reattaches_links() {
+        let trash = Arc::new(MemoryTrash::default());
+        let projects = Arc::new(MemoryProjects::default());
+        projects
+            .save(&project_with_links(vec![link("a"), link("c")]))
+            .unwrap();
+        let manager = TrashManager::new(
+            Box::new(trash.clone()),
+            Box::new(projects.clone()),
+            Duration::days(30),
+        );
+
+        trash
+            .trash(&TrashEntry {
+                id: "b".to_string(),
+                kind: TrashKind::Scratch,
+                title: "B".to_string(),
+                deleted_at: Utc::now(),
+                removed_links: vec![RemovedLink {
+                    project_id: "p1".to_string(),
+                    node_id: "root".to_string(),
+                    index: 1,
+                    link: link("b"),
+                }],
+            })
+            .unwrap();
+
+        manager.restore(TrashKind::Scratch, "b").unwrap();
+
+        let project = projects.load("p1").unwrap();
+        let ids: Vec<_> = project
+            .outline
+            .scratches
+            .iter()
+            .map(|l| l.scratch_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert!(manager.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_adjacent_links_are_restored_in_order() {
+        let trash = Arc::new(MemoryTrash::default());
+        let projects = Arc::new(MemoryProjects::default());
+        let footnote = ScratchLink {
+            insertion: InsertionFlags {
+                body: false,
+                footnote: true,
+                reference: false,
+                appendix: false,
+            },
+            ..link("b")
+        };
+        let mut project =
+            project_with_links(vec![link("a"), link("b"), footnote.clone(), link("c")]);
+        let removed_links = crate::references::detach_links("p1", &mut project.outline, "b", None);
+        assert_eq!(project.outline.scratches.len(), 2);
+        projects.save(&project).unwrap();
+        let manager = TrashManager::new(
+            Box::new(trash.clone()),
+            Box::new(projects.clone()),
+            Duration::days(30),
+        );
+        trash
+            .trash(&TrashEntry {
+                id: "b".to_string(),
+                kind: TrashKind::Scratch,
+                title: "B".to_string(),
+                deleted_at: Utc::now(),
+                removed_links,
+            })
+            .unwrap();
+
+        manager.restore(TrashKind::Scratch, "b").unwrap();
+
+        let scratches = projects.load("p1").unwrap().outline.scratches;
+        let ids: Vec<_> = scratches.iter().map(|l| l.scratch_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "b", "c"]);
+        assert!(scratches[1].insertion.body);
+        assert!(scratches[2].insertion.footnote);
+    }
+
+    #[test]
+    fn test_purge_expired_respects_retention() {
+        let trash = Arc::new(MemoryTrash::default());
+        let manager = TrashManager::new(
+            Box::new(trash.clone()),
+            Box::new(Arc::new(MemoryProjects::default())),
+            Duration::days(7),
+        );
+        let now = Utc::now();
+        for (id, age) in [("old", 8), ("new", 1)] {
+            trash
+                .trash(&TrashEntry {
+                    id: id.to_string(),
+                    kind: TrashKind::Project,
+                    title: id.to_string(),
+                    deleted_at: now - Duration::days(age),
+                    removed_links: vec![],
+                })
+                .unwrap();
+        }
+
+        let purged = manager.purge_expired(now).unwrap();
+
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0].id, "old");
+        let remaining = manager.list().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, "new");
+    }
+}
@@ -0,0 +1,395 @@
+use crate::{CoreError, DocumentClass, FieldError, OutlineNode, Project, Result, Scratch};
+use std::collections::HashSet;
+use std::path::{Component, Path};
+
+/// Title of the top node of every project outline.
+pub const ROOT_TITLE: &str = "Root";
+pub const MAX_TITLE_LEN: usize = 200;
+pub const MAX_TAG_LEN: usize = 50;
+pub const MAX_ID_LEN: usize = 128;
+
+/// Top-level workspace directories that hold data. Build output may not go
+/// into them, and they are never reported as build output.
+pub const DATA_DIRS: &[&str] = &[
+    "scratches",
+    "projects",
+    "templates",
+    "trash",
+    "backups",
+    "journal",
+    "history",
+    "blueprints",
+    "quarantine",
+];
+
+/// Collects violations so that callers can report all of them at once.
+#[derive(Debug, Default)]
+pub struct Violations(Vec<FieldError>);
+
+impl Violations {
+    pub fn push(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.push(FieldError::new(field, message));
+    }
+
+    /// `Ok` if nothing was collected, otherwise a `CoreError::Validation`
+    /// listing every violation.
+    pub fn into_result(self) -> Result<()> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(CoreError::Validation(self.0))
+        }
+    }
+}
+
+/// Checks that `id` is a UUID or a slug of ASCII letters, digits, `-` and
+/// `_` starting with a letter or digit. Ids name files in the workspace, so
+/// anything that could act as a path (`..`, separators) is rejected.
+pub fn validate_id(field: &str, id: &str) -> Result<()> {
+    let mut violations = Violations::default();
+    check_id(id, field, &mut violations);
+    violations.into_result()
+}
+
+/// Trims a tag, lowercases it and joins inner whitespace runs with `-`, so
+/// that `" Machine  Learning"` becomes `"machine-learning"`.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// Normalizes every tag and drops repeats, keeping the first occurrence.
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = normalize_tag(tag);
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// Normalizes a scratch in place and checks it.
+pub fn validate_scratch(scratch: &mut Scratch) -> Result<()> {
+    let mut violations = Violations::default();
+    check_id(&scratch.id, "id", &mut violations);
+    scratch.title = scratch.title.trim().to_string();
+    check_title(&scratch.title, "title", &mut violations);
+    let mut seen = HashSet::new();
+    for (i, tag) in scratch.tags.iter().enumerate() {
+        let field = format!("tags[{}]", i);
+        check_tag(tag, &field, &mut violations);
+        let normalized = normalize_tag(tag);
+        if !normalized.is_empty() && !seen.insert(normalized.clone()) {
+            violations.push(field, format!("duplicate tag \"{}\"", normalized));
+        }
+    }
+    scratch.tags = normalize_tags(&scratch.tags);
+    if let Some(source) = &scratch.source {
+        if source.trim().is_empty() {
+            scratch.source = None;
+        }
+    }
+    violations.into_result()
+}
+
+/// Normalizes a project in place and checks it. When the document class is
+/// not known, the outline may be as deep as the deepest class allows.
+pub fn validate_project(project: &mut Project, class: Option<DocumentClass>) -> Result<()> {
+    let mut violations = Violations::default();
+    check_project(project, class, &mut violations);
+    violations.into_result()
+}
+
+pub(crate) fn check_project(
+    project: &mut Project,
+    class: Option<DocumentClass>,
+    violations: &mut Violations,
+) {
+    check_id(&project.id, "id", violations);
+    project.title = project.title.trim().to_string();
+    check_title(&project.title, "title", violations);
+    check_id(
+        &project.settings.template_id,
+        "settings.template_id",
+        violations,
+    );
+    check_output_dir(&project.settings.output_dir, violations);
+    if project.outline.title != ROOT_TITLE {
+        violations.push(
+            "outline.title",
+            format!("top node must be the root, titled \"{}\"", ROOT_TITLE),
+        );
+    }
+    let max_depth = class.unwrap_or(DocumentClass::Book).max_depth();
+    let mut ids = HashSet::new();
+    check_node(
+        &mut project.outline,
+        "outline",
+        0,
+        max_depth,
+        &mut ids,
+        violations,
+    );
+}
+
+fn check_node(
+    node: &mut OutlineNode,
+    path: &str,
+    depth: usize,
+    max_depth: usize,
+    ids: &mut HashSet<String>,
+    violations: &mut Violations,
+) {
+    if node.id.trim().is_empty() {
+        violations.push(format!("{}.id", path), "must not be empty");
+    } else if !ids.insert(node.id.clone()) {
+        violations.push(
+            format!("{}.id", path),
+            format!("duplicate node id \"{}\"", node.id),
+        );
+    }
+    if depth > 0 {
+        node.title = node.title.trim().to_string();
+        check_title(&node.title, &format!("{}.title", path), violations);
+    }
+    if depth == max_depth + 1 {
+        violations.push(
+            path,
+            format!(
+                "outline is nested too deeply; the document class allows {} levels",
+                max_depth
+            ),
+        );
+    }
+    for (i, link) in node.scratches.iter().enumerate() {
+        if link.scratch_id.trim().is_empty() {
+            violations.push(
+                format!("{}.scratches[{}].scratch_id", path, i),
+                "must not be empty",
+            );
+        }
+    }
+    for (i, child) in node.children.iter_mut().enumerate() {
+        let child_path = format!("{}.children[{}]", path, i);
+        check_node(child, &child_path, depth + 1, max_depth, ids, violations);
+    }
+}
+
+fn check_title(title: &str, field: &str, violations: &mut Violations) {
+    if title.is_empty() {
+        violations.push(field, "must not be empty");
+    } else if title.chars().count() > MAX_TITLE_LEN {
+        violations.push(
+            field,
+            format!("must be at most {} characters", MAX_TITLE_LEN),
+        );
+    }
+    if title.chars().any(char::is_control) {
+        violations.push(field, "must not contain line breaks or control characters");
+    }
+}
+
+fn check_id(id: &str, field: &str, violations: &mut Violations) {
+    if id.is_empty() {
+        violations.push(field, "must not be empty");
+    } else if id.len() > MAX_ID_LEN {
+        violations.push(field, format!("must be at most {} characters", MAX_ID_LEN));
+    } else if !id.starts_with(|c: char| c.is_ascii_alphanumeric())
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        violations.push(
+            field,
+            "must be a UUID or contain only letters, digits, '-' and '_'",
+        );
+    }
+}
+
+/// Build output goes below the workspace, so the directory must be a
+/// relative path that does not climb out of it, name the workspace itself
+/// or lie in one of its data directories.
+fn check_output_dir(dir: &str, violations: &mut Violations) {
+    const FIELD: &str = "settings.output_dir";
+    let path = Path::new(dir);
+    if dir.trim().is_empty() {
+        violations.push(FIELD, "must not be empty");
+    } else if !path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        violations.push(
+            FIELD,
+            "must be a path inside the workspace, without '..' or a leading '/'",
+        );
+    } else if let Some(top) = path.components().find_map(|c| match c {
+        Component::Normal(name) => Some(name.to_string_lossy()),
+        _ => None,
+    }) {
+        if DATA_DIRS.contains(&top.as_ref()) {
+            violations.push(
+                FIELD,
+                format!("must not be inside the workspace's {} directory", top),
+            );
+        }
+    } else {
+        violations.push(
+            FIELD,
+            "must be a directory below the workspace, not the workspace itself",
+        );
+    }
+}
+
+fn check_tag(tag: &str, field: &str, violations: &mut Violations) {
+    let normalized = normalize_tag(tag);
+    if normalized.is_empty() {
+        violations.push(field, "must not be empty");
+    } else if normalized.chars().count() > MAX_TAG_LEN {
+        violations.push(field, format!("must be at most {} characters", MAX_TAG_LEN));
+    } else if normalized.chars().any(|c| c.is_control() || c == ',') {
+        violations.push(field, "must not contain commas or control characters");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectSettings;
+    use chrono::Utc;
+
+    fn node(id: &str, title: &str, children: Vec<OutlineNode>) -> OutlineNode {
+        OutlineNode {
+            id: id.to_string(),
+            title: title.to_string(),
+            content: None,
+            children,
+            scratches: vec![],
+        }
+    }
+
+    fn project(outline: OutlineNode) -> Project {
+        Project {
+            id: "p".to_string(),
+            title: " Thesis ".to_string(),
+            outline,
+            settings: ProjectSettings {
+                template_id: "t".to_string(),
+                output_dir: "out".to_string(),
+            },
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            archived: false,
+        }
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = vec![
+            " Machine  Learning".to_string(),
+            "machine-learning".to_string(),
+            "NLP".to_string(),
+        ];
+        assert_eq!(normalize_tags(&tags), vec!["machine-learning", "nlp"]);
+    }
+
+    #[test]
+    fn test_scratch_reports_all_violations() {
+        let mut scratch = Scratch {
+            id: "s".to_string(),
+            title: "  ".to_string(),
+            content: String::new(),
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            tags: vec!["ok".to_string(), " ".to_string(), " OK".to_string()],
+            source: None,
+        };
+        let Err(CoreError::Validation(errors)) = validate_scratch(&mut scratch) else {
+            panic!("expected validation error");
+        };
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["title", "tags[1]", "tags[2]"]);
+    }
+
+    #[test]
+    fn test_ids_and_output_dir() {
+        assert!(validate_id("id", "9b2e8a5c-5d6f-4e0b-a2e4-1f3c6d7e8f90").is_ok());
+        assert!(validate_id("id", "my_note-2").is_ok());
+        for id in ["", "../x", "..", "a/b", "a\\b", ".hidden", "a.json", "-x"] {
+            assert!(validate_id("id", id).is_err(), "{:?} accepted", id);
+        }
+
+        let mut project = project(node("root", ROOT_TITLE, vec![]));
+        project.id = "../p".to_string();
+        project.settings.output_dir = "../../etc".to_string();
+        let Err(CoreError::Validation(errors)) = validate_project(&mut project, None) else {
+            panic!("expected validation error");
+        };
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["id", "settings.output_dir"]);
+
+        project.id = "p".to_string();
+        for dir in [
+            "/tmp/out",
+            "out/../../x",
+            ".",
+            "./.",
+            "scratches",
+            "./history/x",
+        ] {
+            project.settings.output_dir = dir.to_string();
+            assert!(validate_project(&mut project, None).is_err(), "{}", dir);
+        }
+        project.settings.output_dir = "build/pdf".to_string();
+        assert!(validate_project(&mut project, None).is_ok());
+    }
+
+    #[test]
+    fn test_outline_rules() {
+        let deep = node(
+            "a",
+            "A",
+            vec![node(
+                "b",
+                "B",
+                vec![node(
+                    "c",
+                    "C",
+                    vec![node(
+                        "d",
+                        "D",
+                        vec![node("e", "E", vec![node("f", "F", vec![])])],
+                    )],
+                )],
+            )],
+        );
+        let mut valid = project(node("root", ROOT_TITLE, vec![deep.clone()]));
+        assert!(validate_project(&mut valid, Some(DocumentClass::Book)).is_ok());
+        assert_eq!(valid.title, "Thesis");
+
+        // An unknown class still caps the depth, at the deepest class's limit.
+        let mut deeper = valid.clone();
+        deeper.outline.children[0] = node("z", "Z", vec![deep.clone()]);
+        assert!(validate_project(&mut deeper, None).is_err());
+
+        let mut invalid = project(node("root", "Top", vec![deep, node("a", "", vec![])]));
+        let Err(CoreError::Validation(errors)) =
+            validate_project(&mut invalid, Some(DocumentClass::Article))
+        else {
+            panic!("expected validation error");
+        };
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "outline.title",
+                "outline.children[0].children[0].children[0].children[0].children[0].children[0]",
+                "outline.children[1].id",
+                "outline.children[1].title",
+            ]
+        );
+    }
+}
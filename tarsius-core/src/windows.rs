@@ -0,0 +1,215 @@
+use crate::{CoreError, Result};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Label of the window Tauri opens at startup.
+pub const MAIN_WINDOW: &str = "main";
+
+/// What a window shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WindowKind {
+    Main,
+    Scratch,
+    Project,
+    /// A second outline of a project.
+    Outline,
+    /// The PDF preview of a project.
+    Preview,
+}
+
+impl WindowKind {
+    /// Whether windows of this kind show one scratch or project.
+    pub fn needs_target(self) -> bool {
+        self != WindowKind::Main
+    }
+
+    fn label_prefix(self) -> &'static str {
+        match self {
+            WindowKind::Main => MAIN_WINDOW,
+            WindowKind::Scratch => "scratch",
+            WindowKind::Project => "project",
+            WindowKind::Outline => "outline",
+            WindowKind::Preview => "preview",
+        }
+    }
+}
+
+impl FromStr for WindowKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "Main" => Ok(WindowKind::Main),
+            "Scratch" => Ok(WindowKind::Scratch),
+            "Project" => Ok(WindowKind::Project),
+            "Outline" => Ok(WindowKind::Outline),
+            "Preview" => Ok(WindowKind::Preview),
+            other => Err(format!("Unknown window kind: {}", other)),
+        }
+    }
+}
+
+/// Position and size of a window, in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowBounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    /// Unique among open windows; derived from the kind and target.
+    pub label: String,
+    pub kind: WindowKind,
+    /// The scratch (for `Scratch`) or project the window shows.
+    pub target_id: Option<String>,
+    /// `None` until the window was first moved or resized.
+    pub bounds: Option<WindowBounds>,
+}
+
+/// The windows open on a workspace, saved so they come back on next start.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowLayout {
+    pub windows: Vec<WindowState>,
+}
+
+/// Keeps track of which window shows what. There is at most one window per
+/// kind and target, so opening an item twice focuses the existing window.
+#[derive(Debug, Clone, Default)]
+pub struct WindowRegistry {
+    windows: Vec<WindowState>,
+}
+
+impl WindowRegistry {
+    pub fn from_layout(layout: WindowLayout) -> Self {
+        let mut registry = Self::default();
+        for window in layout.windows {
+            if registry.get(&window.label).is_none() {
+                registry.windows.push(window);
+            }
+        }
+        registry
+    }
+
+    pub fn layout(&self) -> WindowLayout {
+        WindowLayout {
+            windows: self.windows.clone(),
+        }
+    }
+
+    pub fn get(&self, label: &str) -> Option<&WindowState> {
+        self.windows.iter().find(|w| w.label == label)
+    }
+
+    /// Registers a window for `kind` and `target_id`, or returns the one
+    /// already open for them. The flag tells whether the window is new.
+    pub fn open(
+        &mut self,
+        kind: WindowKind,
+        target_id: Option<String>,
+    ) -> Result<(WindowState, bool)> {
+        if kind.needs_target() != target_id.is_some() {
+            return Err(CoreError::invalid(
+                "target_id",
+                match kind {
+                    WindowKind::Main => "the main window shows no single item".to_string(),
+                    _ => format!("a {:?} window needs the id of what it shows", kind),
+                },
+            ));
+        }
+        let label = match &target_id {
+            Some(id) => format!("{}-{}", kind.label_prefix(), id),
+            None => kind.label_prefix().to_string(),
+        };
+        if let Some(window) = self.get(&label) {
+            return Ok((window.clone(), false));
+        }
+        let window = WindowState {
+            label,
+            kind,
+            target_id,
+            bounds: None,
+        };
+        self.windows.push(window.clone());
+        Ok((window, true))
+    }
+
+    /// Forgets a closed window. Returns whether it was known.
+    pub fn close(&mut self, label: &str) -> bool {
+        let before = self.windows.len();
+        self.windows.retain(|w| w.label != label);
+        self.windows.len() != before
+    }
+
+    /// Records where a window is. Returns whether it was known.
+    pub fn set_bounds(&mut self, label: &str, bounds: WindowBounds) -> bool {
+        match self.windows.iter_mut().find(|w| w.label == label) {
+            Some(window) => {
+                window.bounds = Some(bounds);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Windows showing the given scratch or project.
+    pub fn showing(&self, target_id: &str) -> Vec<&WindowState> {
+        self.windows
+            .iter()
+            .filter(|w| w.target_id.as_deref() == Some(target_id))
+            .collect()
+    }
+
+    /// Closes windows whose scratch or project no longer exists.
+    pub fn retain_targets(&mut self, exists: impl Fn(WindowKind, &str) -> bool) {
+        self.windows.retain(|w| match &w.target_id {
+            Some(id) => exists(w.kind, id),
+            None => true,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_window_per_target() {
+        let mut registry = WindowRegistry::default();
+        let (main, _) = registry.open(WindowKind::Main, None).unwrap();
+        assert_eq!(main.label, MAIN_WINDOW);
+        let (first, new) = registry
+            .open(WindowKind::Preview, Some("p1".to_string()))
+            .unwrap();
+        assert!(new);
+        assert_eq!(first.label, "preview-p1");
+        let (again, new) = registry
+            .open(WindowKind::Preview, Some("p1".to_string()))
+            .unwrap();
+        assert!(!new);
+        assert_eq!(again, first);
+        registry
+            .open(WindowKind::Outline, Some("p1".to_string()))
+            .unwrap();
+        assert_eq!(registry.showing("p1").len(), 2);
+        assert!(registry.open(WindowKind::Scratch, None).is_err());
+
+        let bounds = WindowBounds {
+            x: 10.0,
+            y: 20.0,
+            width: 640.0,
+            height: 480.0,
+        };
+        assert!(registry.set_bounds("preview-p1", bounds));
+        let restored = WindowRegistry::from_layout(registry.layout());
+        assert_eq!(restored.get("preview-p1").unwrap().bounds, Some(bounds));
+
+        assert!(registry.close("preview-p1"));
+        assert!(!registry.close("preview-p1"));
+        registry.retain_targets(|_, _| false);
+        assert_eq!(registry.layout().windows, vec![main]);
+    }
+}
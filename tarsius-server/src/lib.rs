@@ -0,0 +1,468 @@
+//! Optional local API for editor and script integrations.
+//!
+//! The server listens on 127.0.0.1 only and every request must carry the
+//! workspace token, either as `Authorization: Bearer <token>` or, for clients
+//! such as `EventSource` that cannot set headers, as `?token=<token>`.
+//!
+//! * `POST /rpc` takes a JSON-RPC 2.0 request; see `rpc::dispatch` for the
+//!   methods.
+//! * `GET /events` is a server-sent-events stream of `WorkspaceEvent`s.
+//!
+//! The token is checked before a request body is read, headers are capped
+//! at `MAX_HEADER_BYTES`, slow clients time out and at most
+//! `MAX_CONNECTIONS` connections are served at once.
+//!
+//! While it runs, `server.json` in the workspace holds the port and token.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tarsius_app::App;
+use tarsius_core::{CoreError, Result, ServerSettings};
+
+pub mod rpc;
+
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+/// Limit for the request line and headers together.
+const MAX_HEADER_BYTES: u64 = 16 * 1024;
+/// Connections handled at once, event streams included. Further ones are
+/// turned away.
+const MAX_CONNECTIONS: usize = 32;
+/// How long a client may take to send its request, and to take each write.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// What clients read from `server.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerInfo {
+    pub port: u16,
+    pub token: String,
+    pub pid: u32,
+}
+
+/// A running server. Dropping the handle leaves it running; call `shutdown`
+/// to stop it.
+pub struct ServerHandle {
+    addr: SocketAddr,
+    token: String,
+    stopped: Arc<AtomicBool>,
+    info_path: PathBuf,
+}
+
+impl ServerHandle {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Stops accepting connections and removes `server.json`. Open event
+    /// streams end at their next keepalive.
+    pub fn shutdown(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the flag.
+        let _ = TcpStream::connect(self.addr);
+        let _ = fs::remove_file(&self.info_path);
+    }
+}
+
+/// Starts the server on a background thread.
+pub fn start(app: Arc<App>, settings: &ServerSettings) -> Result<ServerHandle> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, settings.port))
+        .map_err(|e| CoreError::Storage(format!("Failed to bind local API: {}", e)))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| CoreError::Storage(e.to_string()))?;
+    let token = settings
+        .token
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+
+    let info_path = app.workspace().server_info_path();
+    let info = ServerInfo {
+        port: addr.port(),
+        token: token.clone(),
+        pid: std::process::id(),
+    };
+    write_private(
+        &info_path,
+        &serde_json::to_string_pretty(&info).map_err(|e| CoreError::Storage(e.to_string()))?,
+    )?;
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let handle = ServerHandle {
+        addr,
+        token: token.clone(),
+        stopped: stopped.clone(),
+        info_path,
+    };
+    let token = Arc::new(token);
+    let open = Arc::new(AtomicUsize::new(0));
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(mut stream) = stream else { continue };
+            if stream.set_read_timeout(Some(IO_TIMEOUT)).is_err()
+                || stream.set_write_timeout(Some(IO_TIMEOUT)).is_err()
+            {
+                continue;
+            }
+            let Some(slot) = ConnectionSlot::take(&open) else {
+                let _ = respond(&mut stream, 503, "text/plain", b"Too many connections");
+                continue;
+            };
+            let app = app.clone();
+            let token = token.clone();
+            let stopped = stopped.clone();
+            thread::spawn(move || {
+                let _slot = slot;
+                if let Err(e) = handle_connection(&app, &token, &stopped, stream) {
+                    eprintln!("Local API request failed: {}", e);
+                }
+            });
+        }
+    });
+    Ok(handle)
+}
+
+/// `server.json` holds a secret, so keep it readable by the owner only. The
+/// mode given at creation does not apply to a file left by an earlier run,
+/// so it is set again before anything is written.
+fn write_private(path: &std::path::Path, content: &str) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| CoreError::io(path, e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))
+            .map_err(|e| CoreError::io(path, e))?;
+    }
+    file.write_all(content.as_bytes())
+        .map_err(|e| CoreError::io(path, e))
+}
+
+/// One of the `MAX_CONNECTIONS` places, given back when dropped.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn take(open: &Arc<AtomicUsize>) -> Option<Self> {
+        open.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+            (n < MAX_CONNECTIONS).then_some(n + 1)
+        })
+        .ok()
+        .map(|_| Self(open.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A request line and headers. The body is read only once the request is
+/// authorized.
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    headers: HashMap<String, String>,
+}
+
+fn handle_connection(
+    app: &App,
+    token: &str,
+    stopped: &AtomicBool,
+    stream: TcpStream,
+) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let request = match read_head(&mut reader) {
+        Ok(request) => request,
+        Err(e) => return respond(&mut writer, 400, "text/plain", e.to_string().as_bytes()),
+    };
+    if !is_authorized(&request, token) {
+        return respond(&mut writer, 401, "text/plain", b"Missing or invalid token");
+    }
+    let body = match read_body(&mut reader, &request) {
+        Ok(body) => body,
+        Err(e) => return respond(&mut writer, 400, "text/plain", e.to_string().as_bytes()),
+    };
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/rpc") => {
+            let body = String::from_utf8_lossy(&body);
+            let response = rpc::handle(app, &body);
+            respond(
+                &mut writer,
+                200,
+                "application/json",
+                response.to_string().as_bytes(),
+            )
+        }
+        ("GET", "/events") => stream_events(app, stopped, &mut writer),
+        _ => respond(&mut writer, 404, "text/plain", b"Not found"),
+    }
+}
+
+fn invalid(msg: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn read_head(reader: &mut impl BufRead) -> std::io::Result<HttpRequest> {
+    let mut reader = reader.take(MAX_HEADER_BYTES);
+    let mut read_line = |line: &mut String| {
+        let read = reader.read_line(line)?;
+        if reader.limit() == 0 && !line.ends_with('\n') {
+            return Err(invalid("Request header too large"));
+        }
+        Ok(read)
+    };
+
+    let mut line = String::new();
+    read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().ok_or_else(|| invalid("Empty request"))?;
+    let target = parts
+        .next()
+        .ok_or_else(|| invalid("Missing request target"))?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    Ok(HttpRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        query,
+        headers,
+    })
+}
+
+fn read_body(reader: &mut impl Read, request: &HttpRequest) -> std::io::Result<Vec<u8>> {
+    let length: usize = match request.headers.get("content-length") {
+        Some(value) => value
+            .parse()
+            .map_err(|_| invalid("Invalid Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(invalid("Request body too large"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(body)
+}
+
+fn is_authorized(request: &HttpRequest, token: &str) -> bool {
+    let supplied = request
+        .headers
+        .get("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| request.query.get("token").map(String::as_str));
+    supplied.is_some_and(|supplied| constant_time_eq(supplied.as_bytes(), token.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn respond(
+    writer: &mut impl Write,
+    status: u16,
+    content_type: &str,
+    body: &[u8],
+) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        503 => "Service Unavailable",
+        _ => "Not Found",
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        content_type,
+        body.len()
+    )?;
+    writer.write_all(body)?;
+    writer.flush()
+}
+
+fn stream_events(app: &App, stopped: &AtomicBool, writer: &mut impl Write) -> std::io::Result<()> {
+    let events = app.subscribe();
+    write!(
+        writer,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n"
+    )?;
+    writer.flush()?;
+    while !stopped.load(Ordering::SeqCst) {
+        match events.recv_timeout(KEEPALIVE_INTERVAL) {
+            Ok(event) => {
+                let data = serde_json::to_string(&event)?;
+                write!(writer, "data: {}\n\n", data)?;
+            }
+            // A failed keepalive write is how we notice the client left.
+            Err(RecvTimeoutError::Timeout) => write!(writer, ": keepalive\n\n")?,
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    fn request(addr: SocketAddr, raw: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(raw.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_rpc_over_http_requires_token() {
+        let dir = TempDir::new().unwrap();
+        let app = Arc::new(App::open(dir.path()).unwrap());
+        let settings = ServerSettings {
+            enabled: true,
+            port: 0,
+            token: Some("secret".to_string()),
+        };
+        let server = start(app, &settings).unwrap();
+        assert!(dir.path().join("server.json").exists());
+
+        let body = r#"{"jsonrpc":"2.0","id":7,"method":"list_scratches"}"#;
+        let unauthorized = request(
+            server.addr(),
+            &format!(
+                "POST /rpc HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+        );
+        assert!(unauthorized.starts_with("HTTP/1.1 401"));
+
+        let ok = request(
+            server.addr(),
+            &format!(
+                "POST /rpc HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+        );
+        assert!(ok.starts_with("HTTP/1.1 200"));
+        assert!(ok.ends_with(r#"{"id":7,"jsonrpc":"2.0","result":[]}"#));
+
+        server.shutdown();
+        assert!(!dir.path().join("server.json").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_server_info_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TempDir::new().unwrap();
+        let info = dir.path().join("server.json");
+        fs::write(&info, "{}").unwrap();
+        fs::set_permissions(&info, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&info, "{\"token\": \"secret\"}").unwrap();
+        let mode = fs::metadata(&info).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_requests_are_checked_before_the_body_is_read() {
+        let dir = TempDir::new().unwrap();
+        let app = Arc::new(App::open(dir.path()).unwrap());
+        let settings = ServerSettings {
+            enabled: true,
+            port: 0,
+            token: Some("secret".to_string()),
+        };
+        let server = start(app, &settings).unwrap();
+
+        // No body follows, so this only returns if the token is checked first.
+        let unauthorized = request(
+            server.addr(),
+            "POST /rpc HTTP/1.1\r\nContent-Length: 1000000\r\n\r\n",
+        );
+        assert!(unauthorized.starts_with("HTTP/1.1 401"));
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn test_event_stream_delivers_changes() {
+        let dir = TempDir::new().unwrap();
+        let app = Arc::new(App::open(dir.path()).unwrap());
+        let settings = ServerSettings {
+            enabled: true,
+            port: 0,
+            token: Some("secret".to_string()),
+        };
+        let server = start(app.clone(), &settings).unwrap();
+
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        stream
+            .write_all(b"GET /events?token=secret HTTP/1.1\r\n\r\n")
+            .unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while line != "\r\n" {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+        }
+
+        // The stream subscribes before sending its headers.
+        app.create_scratch("Note".to_string(), String::new(), vec![], None)
+            .unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert!(line.starts_with(r#"data: {"type":"ScratchCreated""#));
+        server.shutdown();
+    }
+}
@@ -0,0 +1,361 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use tarsius_app::{App, UsageFilter};
+use tarsius_core::{DeletePolicy, ProjectDto, ScratchDto};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// The call was well-formed but the operation itself failed.
+pub const APP_ERROR: i64 = -32000;
+
+#[derive(Debug, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+    #[serde(default)]
+    pub id: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    /// For `APP_ERROR`, the serialized `CoreError` with its stable `code`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+/// Handles one JSON-RPC 2.0 request body and returns the response body.
+pub fn handle(app: &App, body: &str) -> Value {
+    let request: RpcRequest = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(e) => return error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string())),
+    };
+    if request.jsonrpc != "2.0" {
+        return error_response(
+            request.id,
+            RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""),
+        );
+    }
+    match dispatch(app, &request.method, request.params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+        Err(error) => error_response(request.id, error),
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": error })
+}
+
+#[derive(Deserialize)]
+struct IdParams {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct CreateScratchParams {
+    title: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    source: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UpdateScratchParams {
+    id: String,
+    title: Option<String>,
+    content: Option<String>,
+    tags: Option<Vec<String>>,
+    source: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ListScratchesParams {
+    usage: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeleteScratchParams {
+    id: String,
+    policy: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TagScratchParams {
+    id: String,
+    #[serde(default)]
+    add: Vec<String>,
+    #[serde(default)]
+    remove: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct CreateProjectParams {
+    title: String,
+    template_id: String,
+    output_dir: String,
+}
+
+#[derive(Deserialize)]
+struct ListProjectsParams {
+    #[serde(default)]
+    include_archived: bool,
+}
+
+#[derive(Deserialize)]
+struct SaveProjectParams {
+    project: ProjectDto,
+}
+
+#[derive(Deserialize)]
+struct BuildProjectParams {
+    id: String,
+    output_dir: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+}
+
+/// Method names and parameters follow the Tauri commands, with request
+/// objects flattened into the params.
+pub fn dispatch(app: &App, method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "create_scratch" => {
+            let p: CreateScratchParams = parse(params)?;
+            let scratch = app
+                .create_scratch(p.title, p.content, p.tags, p.source)
+                .map_err(app_error)?;
+            to_value(ScratchDto::from(scratch))
+        }
+        "update_scratch" => {
+            let p: UpdateScratchParams = parse(params)?;
+            let scratch = app
+                .update_scratch(p.id, p.title, p.content, p.tags, p.source.map(Some))
+                .map_err(app_error)?;
+            to_value(ScratchDto::from(scratch))
+        }
+        "tag_scratch" => {
+            let p: TagScratchParams = parse(params)?;
+            let scratch = app
+                .tag_scratch(&p.id, &p.add, &p.remove)
+                .map_err(app_error)?;
+            to_value(ScratchDto::from(scratch))
+        }
+        "load_scratch" => {
+            let p: IdParams = parse(params)?;
+            to_value(ScratchDto::from(
+                app.load_scratch(&p.id).map_err(app_error)?,
+            ))
+        }
+        "list_scratches" => {
+            let p: ListScratchesParams = parse_or_default(params)?;
+            let usage = p
+                .usage
+                .map(|usage| usage.parse::<UsageFilter>())
+                .transpose()
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e))?;
+            let scratches = app.list_scratches(usage).map_err(app_error)?;
+            to_value(
+                scratches
+                    .into_iter()
+                    .map(ScratchDto::from)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        "delete_scratch" => {
+            let p: DeleteScratchParams = parse(params)?;
+            let policy = match p.policy {
+                Some(policy) => policy
+                    .parse()
+                    .map_err(|e: String| RpcError::new(INVALID_PARAMS, e))?,
+                None => DeletePolicy::Block,
+            };
+            let report = app.delete_scratch(&p.id, policy).map_err(app_error)?;
+            to_value(tarsius_core::DeleteReportDto::from(report))
+        }
+        "scratch_references" => {
+            let p: IdParams = parse(params)?;
+            let references = app.scratch_references(&p.id).map_err(app_error)?;
+            to_value(
+                references
+                    .into_iter()
+                    .map(tarsius_core::ScratchReferenceDto::from)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        "scratch_usages" => {
+            let p: IdParams = parse(params)?;
+            let usages = app.scratch_usages(&p.id).map_err(app_error)?;
+            to_value(
+                usages
+                    .into_iter()
+                    .map(tarsius_core::ScratchReferenceDto::from)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        "create_project" => {
+            let p: CreateProjectParams = parse(params)?;
+            let project = app
+                .create_project(p.title, p.template_id, p.output_dir)
+                .map_err(app_error)?;
+            to_value(ProjectDto::from(project))
+        }
+        "load_project" => {
+            let p: IdParams = parse(params)?;
+            let loaded = app.load_project(&p.id).map_err(app_error)?;
+            Ok(json!({
+                "project": ProjectDto::from(loaded.project),
+                "warnings": loaded.warnings,
+            }))
+        }
+        "save_project" => {
+            let p: SaveProjectParams = parse(params)?;
+            let project = p.project.try_into().map_err(app_error)?;
+            app.save_project(&project).map_err(app_error)?;
+            Ok(Value::Null)
+        }
+        "list_projects" => {
+            let p: ListProjectsParams = parse_or_default(params)?;
+            let projects = app.list_projects(p.include_archived).map_err(app_error)?;
+            to_value(
+                projects
+                    .into_iter()
+                    .map(ProjectDto::from)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        "delete_project" => {
+            let p: IdParams = parse(params)?;
+            app.delete_project(&p.id).map_err(app_error)?;
+            Ok(Value::Null)
+        }
+        "project_drift" => {
+            let p: IdParams = parse(params)?;
+            to_value(app.project_drift(&p.id).map_err(app_error)?)
+        }
+        "build_project" => {
+            let p: BuildProjectParams = parse(params)?;
+            // Remote callers may only build into the workspace.
+            let output_dir = p
+                .output_dir
+                .map(|dir| app.workspace().contain(&dir))
+                .transpose()
+                .map_err(app_error)?;
+            to_value(
+                app.build_project(&p.id, output_dir.as_deref())
+                    .map_err(app_error)?,
+            )
+        }
+        "list_templates" => {
+            let templates = app.list_templates().map_err(app_error)?;
+            to_value(
+                templates
+                    .into_iter()
+                    .map(tarsius_core::TemplateDto::from)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        "load_template" => {
+            let p: IdParams = parse(params)?;
+            to_value(tarsius_core::TemplateDto::from(
+                app.load_template(&p.id).map_err(app_error)?,
+            ))
+        }
+        "search" => {
+            let p: SearchParams = parse(params)?;
+            to_value(app.search(&p.query).map_err(app_error)?)
+        }
+        other => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method: {}", other),
+        )),
+    }
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+/// Like `parse`, but a missing params member means "no parameters".
+fn parse_or_default<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    if params.is_null() {
+        parse(json!({}))
+    } else {
+        parse(params)
+    }
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::new(APP_ERROR, e.to_string()))
+}
+
+fn app_error(e: tarsius_core::CoreError) -> RpcError {
+    RpcError {
+        code: APP_ERROR,
+        message: e.to_string(),
+        data: serde_json::to_value(&e).ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_dispatch_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+
+        let created = handle(
+            &app,
+            r#"{"jsonrpc":"2.0","id":1,"method":"create_scratch","params":{"title":"Vim","content":"from the editor"}}"#,
+        );
+        let id = created["result"]["id"].as_str().unwrap().to_string();
+        assert_eq!(created["id"], 1);
+
+        let listed = dispatch(&app, "list_scratches", Value::Null).unwrap();
+        assert_eq!(listed[0]["id"], id.as_str());
+        let hits = dispatch(&app, "search", json!({ "query": "editor" })).unwrap();
+        assert_eq!(hits.as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_dispatch_errors() {
+        let dir = TempDir::new().unwrap();
+        let app = App::open(dir.path()).unwrap();
+
+        assert_eq!(handle(&app, "{")["error"]["code"], PARSE_ERROR);
+        assert_eq!(
+            handle(&app, r#"{"jsonrpc":"1.0","method":"search"}"#)["error"]["code"],
+            INVALID_REQUEST
+        );
+        let err = dispatch(&app, "format_disk", Value::Null).unwrap_err();
+        assert_eq!(err.code, METHOD_NOT_FOUND);
+        let err = dispatch(&app, "load_scratch", json!({})).unwrap_err();
+        assert_eq!(err.code, INVALID_PARAMS);
+        let err = dispatch(&app, "load_scratch", json!({ "id": "missing" })).unwrap_err();
+        assert_eq!(err.code, APP_ERROR);
+        assert_eq!(err.data.unwrap()["code"], "not_found");
+    }
+}
@@ -0,0 +1,786 @@
+use crate::{read_json, Workspace};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tarsius_core::*;
+use uuid::Uuid;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const DATA_PREFIX: &str = "workspace";
+const FORMAT_VERSION: u32 = 1;
+const AUTO_BACKUP_PREFIX: &str = "tarsius-backup-";
+const AUTO_BACKUP_SUFFIX: &str = ".tar.gz";
+const AUTO_BACKUP_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Describes the contents of a backup archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<BackupFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    /// Path relative to the workspace root, with `/` separators.
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// How to handle archive items whose id already exists in the workspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionPolicy {
+    /// Keep the existing item and drop the imported one.
+    Skip,
+    /// Replace the existing item with the imported one.
+    Overwrite,
+    /// Import the item under a fresh id, updating links that point to it.
+    Rename,
+}
+
+impl std::str::FromStr for CollisionPolicy {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "Skip" => Ok(CollisionPolicy::Skip),
+            "Overwrite" => Ok(CollisionPolicy::Overwrite),
+            "Rename" => Ok(CollisionPolicy::Rename),
+            other => Err(format!("Unknown collision policy: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub skipped: Vec<String>,
+    pub overwritten: Vec<String>,
+    /// `(old id, new id)` for every item imported under a fresh id.
+    pub renamed: Vec<(String, String)>,
+}
+
+/// A backup written by the automatic schedule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub path: String,
+    pub created_at: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// Writes the whole workspace into a gzip-compressed tar archive at `dest`.
+///
+/// Each file is read once, and the bytes that were hashed are the bytes
+/// archived, so a file changing during the export cannot make the archive
+/// disagree with its manifest. The manifest is written last.
+pub fn export_workspace(workspace: &Workspace, dest: &Path) -> Result<BackupManifest> {
+    let base = workspace.base_path();
+    // Settings that cannot be read leave backups in the default directory,
+    // which is skipped anyway.
+    let settings = workspace
+        .load_settings()
+        .map(|settings| settings.backup)
+        .unwrap_or_default();
+    let skip = backups_inside(workspace, &settings);
+    let mut relative = Vec::new();
+    collect_files(base, Path::new(""), skip.as_deref(), &mut relative)?;
+    relative.sort();
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| CoreError::io(parent, e))?;
+    }
+    // A name of its own, so that concurrent exports to the same place do
+    // not write into each other's archive.
+    let dir = dest.parent().unwrap_or(Path::new("."));
+    let file_name = dest
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = dir.join(format!(".{}.{}.tmp", file_name, Uuid::new_v4()));
+    let result = write_archive(base, &relative, &temp_path).and_then(|manifest| {
+        fs::rename(&temp_path, dest).map_err(|e| CoreError::io(dest, e))?;
+        Ok(manifest)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn write_archive(base: &Path, relative: &[PathBuf], temp_path: &Path) -> Result<BackupManifest> {
+    let file = File::create(temp_path).map_err(|e| CoreError::io(temp_path, e))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let created_at = Utc::now();
+    let header = |size: usize| {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(size as u64);
+        header.set_mode(0o644);
+        header.set_mtime(created_at.timestamp().max(0) as u64);
+        header
+    };
+
+    let mut files = Vec::with_capacity(relative.len());
+    for rel in relative {
+        let path = base.join(rel);
+        let data = fs::read(&path).map_err(|e| CoreError::io(&path, e))?;
+        builder
+            .append_data(
+                &mut header(data.len()),
+                Path::new(DATA_PREFIX).join(rel),
+                data.as_slice(),
+            )
+            .map_err(|e| CoreError::io(temp_path, e))?;
+        files.push(BackupFile {
+            path: to_archive_path(rel),
+            size: data.len() as u64,
+            sha256: sha256_hex(&data),
+        });
+    }
+    let manifest = BackupManifest {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at,
+        files,
+    };
+    let manifest_json =
+        serde_json::to_vec_pretty(&manifest).map_err(|e| CoreError::Storage(e.to_string()))?;
+    builder
+        .append_data(
+            &mut header(manifest_json.len()),
+            MANIFEST_NAME,
+            manifest_json.as_slice(),
+        )
+        .map_err(|e| CoreError::io(temp_path, e))?;
+    let encoder = builder
+        .into_inner()
+        .map_err(|e| CoreError::io(temp_path, e))?;
+    let file = encoder.finish().map_err(|e| CoreError::io(temp_path, e))?;
+    file.sync_all().map_err(|e| CoreError::io(temp_path, e))?;
+    Ok(manifest)
+}
+
+/// Reads the manifest of an archive without importing it.
+pub fn read_manifest(archive: &Path) -> Result<BackupManifest> {
+    let file = File::open(archive).map_err(|e| CoreError::io(archive, e))?;
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+    for entry in tar.entries().map_err(|e| CoreError::io(archive, e))? {
+        let entry = entry.map_err(|e| CoreError::io(archive, e))?;
+        let is_manifest =
+            entry.path().map_err(|e| CoreError::io(archive, e))? == Path::new(MANIFEST_NAME);
+        if is_manifest {
+            return serde_json::from_reader(entry).map_err(|e| CoreError::Parse {
+                file: format!("{}/{}", archive.display(), MANIFEST_NAME),
+                line: e.line(),
+                column: e.column(),
+                message: e.to_string(),
+            });
+        }
+    }
+    Err(CoreError::Storage(format!(
+        "{} has no {}",
+        archive.display(),
+        MANIFEST_NAME
+    )))
+}
+
+/// Imports an archive into `workspace`, which may be empty or already hold
+/// data. Every file is checked against the manifest before anything is
+/// written to the workspace.
+pub fn import_workspace(
+    workspace: &Workspace,
+    archive: &Path,
+    policy: CollisionPolicy,
+) -> Result<ImportReport> {
+    workspace.ensure_dirs()?;
+    let staging = workspace
+        .base_path()
+        .join(format!(".import-{}", Uuid::new_v4()));
+    let result =
+        stage_archive(archive, &staging).and_then(|root| merge_staged(workspace, &root, policy));
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+/// Writes an automatic backup if the last one is older than the configured
+/// interval, then prunes old backups. Returns the path of a new backup.
+pub fn run_scheduled_backup(
+    workspace: &Workspace,
+    settings: &BackupSettings,
+    now: DateTime<Utc>,
+) -> Result<Option<PathBuf>> {
+    if !settings.enabled {
+        return Ok(None);
+    }
+    let dir = backups_dir(workspace, settings);
+    let existing = list_backups(workspace, settings)?;
+    let interval = Duration::hours(i64::from(settings.interval_hours));
+    let due = existing
+        .first()
+        .is_none_or(|latest| latest.created_at + interval <= now);
+    let mut created = None;
+    if due {
+        let path = dir.join(format!(
+            "{}{}{}",
+            AUTO_BACKUP_PREFIX,
+            now.format(AUTO_BACKUP_TIME_FORMAT),
+            AUTO_BACKUP_SUFFIX
+        ));
+        export_workspace(workspace, &path)?;
+        created = Some(path);
+    }
+    for old in list_backups(workspace, settings)?
+        .into_iter()
+        .skip(settings.keep.max(1))
+    {
+        fs::remove_file(&old.path).map_err(|e| CoreError::io(Path::new(&old.path), e))?;
+    }
+    Ok(created)
+}
+
+/// Lists automatic backups, newest first.
+pub fn list_backups(workspace: &Workspace, settings: &BackupSettings) -> Result<Vec<BackupInfo>> {
+    let dir = backups_dir(workspace, settings);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+        let entry = entry.map_err(|e| CoreError::io(&dir, e))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(stamp) = name
+            .strip_prefix(AUTO_BACKUP_PREFIX)
+            .and_then(|rest| rest.strip_suffix(AUTO_BACKUP_SUFFIX))
+        else {
+            continue;
+        };
+        let Ok(created_at) = NaiveDateTime::parse_from_str(stamp, AUTO_BACKUP_TIME_FORMAT) else {
+            continue;
+        };
+        let size = entry
+            .metadata()
+            .map_err(|e| CoreError::io(&entry.path(), e))?
+            .len();
+        backups.push(BackupInfo {
+            path: entry.path().to_string_lossy().to_string(),
+            created_at: created_at.and_utc(),
+            size,
+        });
+    }
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    Ok(backups)
+}
+
+pub fn backups_dir(workspace: &Workspace, settings: &BackupSettings) -> PathBuf {
+    match &settings.directory {
+        Some(dir) => PathBuf::from(dir),
+        None => workspace.backups_dir(),
+    }
+}
+
+/// The configured backups directory relative to the workspace, if it lies
+/// inside it. Backing it up would put every earlier backup into the next.
+fn backups_inside(workspace: &Workspace, settings: &BackupSettings) -> Option<PathBuf> {
+    let dir = backups_dir(workspace, settings).canonicalize().ok()?;
+    let base = workspace.base_path().canonicalize().ok()?;
+    dir.strip_prefix(&base).ok().map(Path::to_path_buf)
+}
+
+fn is_excluded(rel: &Path) -> bool {
+    let name = rel.to_string_lossy();
+    rel == Path::new("backups")
+        || rel == Path::new("journal")
+        || rel == Path::new("server.json")
+        || rel == Path::new("windows.json")
+        || rel == Path::new("workspace.lock")
+        || name.starts_with(".import-")
+        || rel.extension().and_then(|s| s.to_str()) == Some("tmp")
+}
+
+fn collect_files(
+    base: &Path,
+    rel: &Path,
+    skip: Option<&Path>,
+    out: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let dir = base.join(rel);
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+        let entry = entry.map_err(|e| CoreError::io(&dir, e))?;
+        let child = rel.join(entry.file_name());
+        if is_excluded(&child) || skip == Some(child.as_path()) {
+            continue;
+        }
+        let file_type = entry
+            .file_type()
+            .map_err(|e| CoreError::io(&entry.path(), e))?;
+        if file_type.is_dir() {
+            collect_files(base, &child, skip, out)?;
+        } else if file_type.is_file() {
+            out.push(child);
+        }
+    }
+    Ok(())
+}
+
+fn to_archive_path(rel: &Path) -> String {
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+// Unpacks the archive and verifies it against its manifest. Returns the
+// directory holding the workspace files. Archives with files the manifest
+// does not list, or with links or other special entries, are rejected, so
+// that only verified files reach the workspace.
+fn stage_archive(archive: &Path, staging: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(staging).map_err(|e| CoreError::io(staging, e))?;
+    let file = File::open(archive).map_err(|e| CoreError::io(archive, e))?;
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+    let mut unpacked = HashSet::new();
+    for entry in tar.entries().map_err(|e| CoreError::io(archive, e))? {
+        let mut entry = entry.map_err(|e| CoreError::io(archive, e))?;
+        let path = entry
+            .path()
+            .map_err(|e| CoreError::io(archive, e))?
+            .into_owned();
+        match entry.header().entry_type() {
+            tar::EntryType::Regular => {
+                unpacked.insert(to_archive_path(&path));
+            }
+            tar::EntryType::Directory => {}
+            _ => {
+                return Err(CoreError::Storage(format!(
+                    "Archive entry {} is not a regular file",
+                    path.display()
+                )))
+            }
+        }
+        entry
+            .unpack_in(staging)
+            .map_err(|e| CoreError::io(&staging.join(&path), e))?;
+    }
+
+    let manifest_path = staging.join(MANIFEST_NAME);
+    if !manifest_path.exists() {
+        return Err(CoreError::Storage(format!(
+            "{} has no {}",
+            archive.display(),
+            MANIFEST_NAME
+        )));
+    }
+    let manifest: BackupManifest = read_json(&manifest_path)?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(CoreError::Storage(format!(
+            "Unsupported backup format version {}",
+            manifest.format_version
+        )));
+    }
+    let listed: HashSet<String> = manifest
+        .files
+        .iter()
+        .map(|file| format!("{}/{}", DATA_PREFIX, file.path))
+        .chain([MANIFEST_NAME.to_string()])
+        .collect();
+    if let Some(extra) = unpacked.difference(&listed).next() {
+        return Err(CoreError::Storage(format!(
+            "Archive entry {} is not in the manifest",
+            extra
+        )));
+    }
+    let root = staging.join(DATA_PREFIX);
+    for file in &manifest.files {
+        let path = root.join(&file.path);
+        let data = fs::read(&path).map_err(|e| CoreError::io(&path, e))?;
+        if data.len() as u64 != file.size || sha256_hex(&data) != file.sha256 {
+            return Err(CoreError::Storage(format!(
+                "Checksum mismatch for {}",
+                file.path
+            )));
+        }
+    }
+    Ok(root)
+}
+
+fn merge_staged(
+    workspace: &Workspace,
+    root: &Path,
+    policy: CollisionPolicy,
+) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let mut scratch_ids = HashMap::new();
+    let mut template_ids = HashMap::new();
+
+    for (id, path) in staged_json_files(&root.join("scratches"))? {
+        let target = |id: &str| workspace.scratches_dir().join(format!("{}.json", id));
+        let Some(new_id) = resolve_id(&id, target(&id).exists(), policy, &mut report) else {
+            continue;
+        };
+        let mut scratch: Scratch = read_json(&path)?;
+        scratch.id = new_id.clone();
+        write_json(&target(&new_id), &scratch)?;
+        scratch_ids.insert(id, new_id);
+    }
+
+    for (id, path) in staged_json_files(&root.join("templates"))? {
+        let target = |id: &str| workspace.templates_dir().join(format!("{}.json", id));
+        let Some(new_id) = resolve_id(&id, target(&id).exists(), policy, &mut report) else {
+            continue;
+        };
+        let mut template: Template = read_json(&path)?;
+        template.id = new_id.clone();
+        write_json(&target(&new_id), &template)?;
+        template_ids.insert(id, new_id);
+    }
+
+    let staged_projects = root.join("projects");
+    if staged_projects.exists() {
+        for entry in
+            fs::read_dir(&staged_projects).map_err(|e| CoreError::io(&staged_projects, e))?
+        {
+            let entry = entry.map_err(|e| CoreError::io(&staged_projects, e))?;
+            if !entry.path().is_dir() {
+                continue;
+            }
+            let id = entry.file_name().to_string_lossy().to_string();
+            validate_id("id", &id)?;
+            let target = workspace.projects_dir().join(&id);
+            let Some(new_id) = resolve_id(&id, target.exists(), policy, &mut report) else {
+                continue;
+            };
+            let target = workspace.projects_dir().join(&new_id);
+            if target.exists() {
+                fs::remove_dir_all(&target).map_err(|e| CoreError::io(&target, e))?;
+            }
+            copy_dir(&entry.path(), &target)?;
+            let project_path = target.join("project.json");
+            if project_path.exists() {
+                let mut project: Project = read_json(&project_path)?;
+                project.id = new_id;
+                if let Some(template_id) = template_ids.get(&project.settings.template_id) {
+                    project.settings.template_id = template_id.clone();
+                }
+                remap_links(&mut project.outline, &scratch_ids);
+                write_json(&project_path, &project)?;
+            }
+        }
+    }
+
+    // Everything else (settings, trash, attachments) is copied file by file;
+    // only `Overwrite` replaces files that already exist.
+    let mut others = Vec::new();
+    collect_files(root, Path::new(""), None, &mut others)?;
+    for rel in others {
+        let top = rel.components().next().map(|c| c.as_os_str().to_owned());
+        if matches!(
+            top.as_deref().and_then(|s| s.to_str()),
+            Some("scratches" | "templates" | "projects")
+        ) {
+            continue;
+        }
+        let target = workspace.base_path().join(&rel);
+        let name = to_archive_path(&rel);
+        if target.exists() {
+            if policy != CollisionPolicy::Overwrite {
+                report.skipped.push(name);
+                continue;
+            }
+            report.overwritten.push(name);
+        } else {
+            report.imported += 1;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| CoreError::io(parent, e))?;
+        }
+        fs::copy(root.join(&rel), &target).map_err(|e| CoreError::io(&target, e))?;
+    }
+    Ok(report)
+}
+
+// Decides under which id an item is imported, or `None` to skip it.
+fn resolve_id(
+    id: &str,
+    exists: bool,
+    policy: CollisionPolicy,
+    report: &mut ImportReport,
+) -> Option<String> {
+    if !exists {
+        report.imported += 1;
+        return Some(id.to_string());
+    }
+    match policy {
+        CollisionPolicy::Skip => {
+            report.skipped.push(id.to_string());
+            None
+        }
+        CollisionPolicy::Overwrite => {
+            report.overwritten.push(id.to_string());
+            Some(id.to_string())
+        }
+        CollisionPolicy::Rename => {
+            let new_id = Uuid::new_v4().to_string();
+            report.renamed.push((id.to_string(), new_id.clone()));
+            report.imported += 1;
+            Some(new_id)
+        }
+    }
+}
+
+fn remap_links(node: &mut OutlineNode, scratch_ids: &HashMap<String, String>) {
+    for link in &mut node.scratches {
+        if let Some(new_id) = scratch_ids.get(&link.scratch_id) {
+            link.scratch_id = new_id.clone();
+        }
+    }
+    for child in &mut node.children {
+        remap_links(child, scratch_ids);
+    }
+}
+
+fn staged_json_files(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| CoreError::io(dir, e))? {
+        let entry = entry.map_err(|e| CoreError::io(dir, e))?;
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("json") {
+            if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+                validate_id("id", id)?;
+                files.push((id.to_string(), path.clone()));
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let json =
+        serde_json::to_string_pretty(value).map_err(|e| CoreError::Storage(e.to_string()))?;
+    crate::write_atomic(path, json)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to).map_err(|e| CoreError::io(to, e))?;
+    for entry in fs::read_dir(from).map_err(|e| CoreError::io(from, e))? {
+        let entry = entry.map_err(|e| CoreError::io(from, e))?;
+        let target = to.join(entry.file_name());
+        if entry.path().is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target).map_err(|e| CoreError::io(&target, e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FilesystemProjectRepository, FilesystemScratchRepository};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn seeded_workspace(dir: &Path) -> (Arc<Workspace>, Scratch, Project) {
+        let workspace = Arc::new(Workspace::new(dir));
+        workspace.ensure_dirs().unwrap();
+        let scratches = ScratchManager::new(Box::new(FilesystemScratchRepository::new(
+            workspace.clone(),
+        )));
+        let projects = ProjectManager::new(Box::new(FilesystemProjectRepository::new(
+            workspace.clone(),
+        )));
+        let scratch = scratches
+            .create("Note".to_string(), "Body".to_string(), vec![], None)
+            .unwrap();
+        let mut project = projects
+            .create("Paper".to_string(), "t".to_string(), "out".to_string())
+            .unwrap();
+        project.outline.scratches.push(ScratchLink {
+            scratch_id: scratch.id.clone(),
+            mode: IntegrationMode::Link,
+            insertion: InsertionFlags {
+                body: true,
+                footnote: false,
+                reference: false,
+                appendix: false,
+            },
+            included: None,
+        });
+        projects.save(&project).unwrap();
+        fs::create_dir_all(dir.join("attachments")).unwrap();
+        fs::write(dir.join("attachments").join("figure.png"), [1u8, 2, 3]).unwrap();
+        (workspace, scratch, project)
+    }
+
+    #[test]
+    fn test_export_and_import_into_empty_workspace() {
+        let source_dir = TempDir::new().unwrap();
+        let (source, scratch, project) = seeded_workspace(source_dir.path());
+        let archive = source_dir.path().join("backups").join("full.tar.gz");
+
+        let manifest = export_workspace(&source, &archive).unwrap();
+        assert!(manifest
+            .files
+            .iter()
+            .any(|f| f.path == "attachments/figure.png"));
+        assert!(!manifest.files.iter().any(|f| f.path.starts_with("backups")));
+        assert_eq!(
+            read_manifest(&archive).unwrap().files.len(),
+            manifest.files.len()
+        );
+
+        let target_dir = TempDir::new().unwrap();
+        let target = Workspace::new(target_dir.path());
+        let report = import_workspace(&target, &archive, CollisionPolicy::Skip).unwrap();
+        assert!(report.skipped.is_empty());
+        assert!(target
+            .scratches_dir()
+            .join(format!("{}.json", scratch.id))
+            .exists());
+        assert!(target
+            .projects_dir()
+            .join(&project.id)
+            .join("project.json")
+            .exists());
+        assert_eq!(
+            fs::read(target_dir.path().join("attachments").join("figure.png")).unwrap(),
+            vec![1u8, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_import_renames_colliding_ids_and_relinks() {
+        let dir = TempDir::new().unwrap();
+        let (workspace, scratch, project) = seeded_workspace(dir.path());
+        let archive = dir.path().join("backups").join("full.tar.gz");
+        export_workspace(&workspace, &archive).unwrap();
+
+        let report = import_workspace(&workspace, &archive, CollisionPolicy::Rename).unwrap();
+        let renamed: HashMap<_, _> = report.renamed.iter().cloned().collect();
+        let new_scratch_id = &renamed[&scratch.id];
+        let new_project_id = &renamed[&project.id];
+
+        let projects = FilesystemProjectRepository::new(workspace.clone());
+        let copy = tarsius_core::ProjectRepository::load(&projects, new_project_id).unwrap();
+        assert_eq!(&copy.id, new_project_id);
+        assert_eq!(&copy.outline.scratches[0].scratch_id, new_scratch_id);
+        assert!(report
+            .skipped
+            .contains(&"attachments/figure.png".to_string()));
+    }
+
+    #[test]
+    fn test_import_rejects_corrupted_archive() {
+        let dir = TempDir::new().unwrap();
+        let (workspace, _, _) = seeded_workspace(dir.path());
+        let archive = dir.path().join("backups").join("full.tar.gz");
+        export_workspace(&workspace, &archive).unwrap();
+        let mut bytes = fs::read(&archive).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xff;
+        fs::write(&archive, bytes).unwrap();
+
+        let target_dir = TempDir::new().unwrap();
+        let target = Workspace::new(target_dir.path());
+        assert!(import_workspace(&target, &archive, CollisionPolicy::Skip).is_err());
+        assert_eq!(fs::read_dir(target.scratches_dir()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_export_skips_configured_backups_directory() {
+        let dir = TempDir::new().unwrap();
+        let (workspace, _, _) = seeded_workspace(dir.path());
+        let mut settings = WorkspaceSettings::default();
+        settings.backup.directory = Some(dir.path().join("archive").display().to_string());
+        workspace.save_settings(&settings).unwrap();
+
+        let first = run_scheduled_backup(&workspace, &settings.backup, Utc::now())
+            .unwrap()
+            .unwrap();
+        assert!(first.starts_with(dir.path().join("archive")));
+        let manifest = export_workspace(&workspace, &dir.path().join("full.tar.gz")).unwrap();
+        assert!(!manifest.files.iter().any(|f| f.path.starts_with("archive")));
+    }
+
+    #[test]
+    fn test_import_rejects_files_missing_from_manifest() {
+        let dir = TempDir::new().unwrap();
+        let (workspace, _, _) = seeded_workspace(dir.path());
+        let archive = dir.path().join("backups").join("full.tar.gz");
+        let manifest = export_workspace(&workspace, &archive).unwrap();
+
+        let tampered = dir.path().join("backups").join("tampered.tar.gz");
+        let file = File::create(&tampered).unwrap();
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let manifest_json = serde_json::to_vec(&manifest).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest_json.len() as u64);
+        builder
+            .append_data(&mut header, MANIFEST_NAME, manifest_json.as_slice())
+            .unwrap();
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&archive).unwrap()));
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().into_owned();
+            if path != Path::new(MANIFEST_NAME) {
+                let mut header = entry.header().clone();
+                builder.append_data(&mut header, path, &mut entry).unwrap();
+            }
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        builder
+            .append_data(&mut header, "workspace/settings.json", &b"{}"[..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let target_dir = TempDir::new().unwrap();
+        let target = Workspace::new(target_dir.path());
+        let error = import_workspace(&target, &tampered, CollisionPolicy::Skip).unwrap_err();
+        assert!(
+            error.to_string().contains("not in the manifest"),
+            "{}",
+            error
+        );
+        assert!(!target.settings_path().exists());
+    }
+
+    #[test]
+    fn test_scheduled_backup_respects_interval_and_retention() {
+        let dir = TempDir::new().unwrap();
+        let (workspace, _, _) = seeded_workspace(dir.path());
+        let settings = BackupSettings {
+            keep: 2,
+            ..Default::default()
+        };
+        let start = Utc::now();
+
+        assert!(run_scheduled_backup(&workspace, &settings, start)
+            .unwrap()
+            .is_some());
+        assert!(
+            run_scheduled_backup(&workspace, &settings, start + Duration::hours(1))
+                .unwrap()
+                .is_none()
+        );
+        for day in 1..=3 {
+            run_scheduled_backup(&workspace, &settings, start + Duration::days(day)).unwrap();
+        }
+        assert_eq!(list_backups(&workspace, &settings).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{read_json, read_or_quarantine, write_atomic, Workspace};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tarsius_core::*;
+
+/// Keeps each saved blueprint in `blueprints/<id>.json`.
+pub struct FilesystemBlueprintRepository {
+    workspace: Arc<Workspace>,
+}
+
+impl FilesystemBlueprintRepository {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    fn blueprint_path(&self, id: &str) -> Result<PathBuf> {
+        validate_id("id", id)?;
+        self.workspace
+            .contain(&self.workspace.blueprints_dir().join(format!("{}.json", id)))
+    }
+}
+
+impl BlueprintRepository for FilesystemBlueprintRepository {
+    fn save(&self, blueprint: &Blueprint) -> Result<()> {
+        let dir = self.workspace.blueprints_dir();
+        fs::create_dir_all(&dir).map_err(|e| CoreError::io(&dir, e))?;
+        let path = self.blueprint_path(&blueprint.id)?;
+        let json = serde_json::to_string_pretty(blueprint)
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+        write_atomic(&path, json)
+    }
+
+    fn load(&self, id: &str) -> Result<Blueprint> {
+        let path = self.blueprint_path(id)?;
+        if !path.exists() {
+            return Err(CoreError::not_found(EntityKind::Blueprint, id));
+        }
+        read_json(&path)
+    }
+
+    fn list(&self) -> Result<Vec<Blueprint>> {
+        let dir = self.workspace.blueprints_dir();
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut blueprints = Vec::new();
+        for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+            let entry = entry.map_err(|e| CoreError::io(&dir, e))?;
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) == Some("json") {
+                blueprints.extend(read_or_quarantine(
+                    &self.workspace,
+                    EntityKind::Blueprint,
+                    &path,
+                    &path,
+                )?);
+            }
+        }
+        Ok(blueprints)
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        let path = self.blueprint_path(id)?;
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| CoreError::io(&path, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_list_and_delete() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path()));
+        workspace.ensure_dirs().unwrap();
+        let blueprints = FilesystemBlueprintRepository::new(workspace.clone());
+        assert!(blueprints.list().unwrap().is_empty());
+
+        let mut blueprint = builtin_blueprints().remove(0);
+        blueprint.id = "lab-paper".to_string();
+        blueprint.builtin = false;
+        blueprints.save(&blueprint).unwrap();
+        assert_eq!(blueprints.load("lab-paper").unwrap().name, blueprint.name);
+
+        // A damaged file is set aside instead of failing the list.
+        fs::write(workspace.blueprints_dir().join("broken.json"), "{").unwrap();
+        let listed = blueprints.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, "lab-paper");
+
+        blueprints.delete("lab-paper").unwrap();
+        assert!(matches!(
+            blueprints.load("lab-paper"),
+            Err(CoreError::NotFound {
+                kind: EntityKind::Blueprint,
+                ..
+            })
+        ));
+    }
+}
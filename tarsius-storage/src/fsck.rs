@@ -0,0 +1,736 @@
+use crate::{read_json, write_atomic, Workspace};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tarsius_core::*;
+use uuid::Uuid;
+
+/// Something wrong with the files of a workspace. Paths are relative to the
+/// workspace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Problem {
+    /// The `id` inside a file is not the one its file name gives it.
+    IdMismatch {
+        kind: EntityKind,
+        path: String,
+        id: String,
+        expected: String,
+    },
+    /// A temporary file left behind by an interrupted write.
+    TempFile { path: String },
+    /// An outline node links to a scratch that does not exist.
+    DanglingLink {
+        project_id: String,
+        node_id: String,
+        scratch_id: String,
+    },
+    /// Several outline nodes of a project share an id.
+    DuplicateNodeId { project_id: String, node_id: String },
+    /// A project uses a template that does not exist.
+    MissingTemplate {
+        project_id: String,
+        template_id: String,
+    },
+    /// A directory Tarsius built into that no project builds into any more.
+    OrphanedOutput { path: String },
+    /// A file that this version cannot read.
+    SchemaMismatch {
+        kind: EntityKind,
+        path: String,
+        message: String,
+    },
+    /// A file with fields this version does not know, most likely written by
+    /// a newer one. Saving the item drops them.
+    UnknownFields {
+        kind: EntityKind,
+        path: String,
+        fields: Vec<String>,
+    },
+}
+
+/// A problem as reported to the user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    #[serde(flatten)]
+    pub problem: Problem,
+    pub message: String,
+    /// What `Workspace::repair` does about the problem, if it can fix it.
+    pub fix: Option<String>,
+    /// A question to ask before applying the fix, for fixes that delete
+    /// files the user may still want. `Workspace::repair` skips them unless
+    /// told the user agreed.
+    pub confirm: Option<String>,
+}
+
+impl From<Problem> for Finding {
+    fn from(problem: Problem) -> Self {
+        let confirm = match &problem {
+            Problem::OrphanedOutput { path } => Some(format!(
+                "Delete {} and everything in it? It may hold files other than LaTeX output.",
+                path
+            )),
+            _ => None,
+        };
+        let (message, fix) = match &problem {
+            Problem::IdMismatch {
+                path, id, expected, ..
+            } => (
+                format!(
+                    "{} has id \"{}\" but its file name says \"{}\"",
+                    path, id, expected
+                ),
+                Some(format!("Change the id to \"{}\"", expected)),
+            ),
+            Problem::TempFile { path } => (
+                format!("{} was left behind by an interrupted write", path),
+                Some("Delete the file".to_string()),
+            ),
+            Problem::DanglingLink {
+                project_id,
+                node_id,
+                scratch_id,
+            } => (
+                format!(
+                    "Node {} of project {} links to the missing scratch {}",
+                    node_id, project_id, scratch_id
+                ),
+                Some("Remove the link".to_string()),
+            ),
+            Problem::DuplicateNodeId {
+                project_id,
+                node_id,
+            } => (
+                format!(
+                    "Several nodes of project {} have the id {}",
+                    project_id, node_id
+                ),
+                Some("Give all but the first node a new id".to_string()),
+            ),
+            Problem::MissingTemplate {
+                project_id,
+                template_id,
+            } => (
+                format!(
+                    "Project {} uses the missing template {}",
+                    project_id, template_id
+                ),
+                None,
+            ),
+            Problem::OrphanedOutput { path } => (
+                format!(
+                    "{} was built into but no project builds into it any more",
+                    path
+                ),
+                Some("Delete the directory".to_string()),
+            ),
+            Problem::SchemaMismatch { path, message, .. } => (
+                format!("{} cannot be read: {}", path, message),
+                Some("Move it to the quarantine".to_string()),
+            ),
+            Problem::UnknownFields { path, fields, .. } => (
+                format!(
+                    "{} has fields this version does not know, which are lost when it is saved: {}",
+                    path,
+                    fields.join(", ")
+                ),
+                None,
+            ),
+        };
+        Self {
+            problem,
+            message,
+            fix,
+            confirm,
+        }
+    }
+}
+
+impl Workspace {
+    /// Audits the workspace without changing anything.
+    pub fn check(&self) -> Result<Vec<Finding>> {
+        let mut problems = Vec::new();
+        for path in self.temp_files()? {
+            problems.push(Problem::TempFile {
+                path: self.relative(&path),
+            });
+        }
+
+        let scratch_ids =
+            self.check_files::<Scratch>(EntityKind::Scratch, &self.scratches_dir(), &mut problems)?;
+        let template_ids = self.check_files::<Template>(
+            EntityKind::Template,
+            &self.templates_dir(),
+            &mut problems,
+        )?;
+        self.check_files::<Blueprint>(
+            EntityKind::Blueprint,
+            &self.blueprints_dir(),
+            &mut problems,
+        )?;
+
+        let mut outputs = HashSet::new();
+        for (id, path) in self.project_files()? {
+            let Some(project) =
+                self.check_file::<Project>(EntityKind::Project, &path, &id, &mut problems)?
+            else {
+                continue;
+            };
+            check_outline(&id, &project.outline, &scratch_ids, &mut problems);
+            if !template_ids.contains(&project.settings.template_id) {
+                problems.push(Problem::MissingTemplate {
+                    project_id: id.clone(),
+                    template_id: project.settings.template_id.clone(),
+                });
+            }
+            if let Ok(dir) = self.contain(Path::new(&project.settings.output_dir)) {
+                outputs.insert(dir);
+            }
+        }
+
+        for dir in self.recorded_outputs()? {
+            if self.is_orphaned_output(&dir, &outputs) {
+                problems.push(Problem::OrphanedOutput { path: dir });
+            }
+        }
+
+        Ok(problems.into_iter().map(Finding::from).collect())
+    }
+
+    /// Applies the automatic fixes for `problems`, in order. Only problems
+    /// that a fresh check still finds are acted on, since the paths in them
+    /// come from the caller. Fixes that ask for confirmation are skipped
+    /// unless `confirmed`. Fails for problems that have no fix.
+    pub fn repair(&self, problems: &[Problem], confirmed: bool) -> Result<()> {
+        let found: Vec<Finding> = self.check()?;
+        for problem in problems {
+            let Some(finding) = found.iter().find(|f| &f.problem == problem) else {
+                continue;
+            };
+            if finding.confirm.is_none() || confirmed {
+                self.repair_one(problem)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remembers that a build was written to `dir`, so that `check` can
+    /// report it once no project builds there any more. Directories outside
+    /// the workspace are not tracked.
+    pub fn record_output(&self, dir: &Path) -> Result<()> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+        let Ok(dir) = self.contain(dir) else {
+            return Ok(());
+        };
+        let base = self
+            .base_path()
+            .canonicalize()
+            .map_err(|e| CoreError::io(self.base_path(), e))?;
+        let Ok(relative) = dir.strip_prefix(&base) else {
+            return Ok(());
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        let mut outputs = self.recorded_outputs()?;
+        if relative.is_empty() || outputs.contains(&relative) {
+            return Ok(());
+        }
+        outputs.push(relative);
+        write_pretty(&self.outputs_path(), &outputs)
+    }
+
+    fn recorded_outputs(&self) -> Result<Vec<String>> {
+        let path = self.outputs_path();
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+        read_json(&path)
+    }
+
+    fn forget_output(&self, dir: &str) -> Result<()> {
+        let mut outputs = self.recorded_outputs()?;
+        outputs.retain(|output| output != dir);
+        write_pretty(&self.outputs_path(), &outputs)
+    }
+
+    fn repair_one(&self, problem: &Problem) -> Result<()> {
+        match problem {
+            Problem::IdMismatch { path, expected, .. } => {
+                let path = self.contain(Path::new(path))?;
+                let mut value: Value = read_json(&path)?;
+                let Some(object) = value.as_object_mut() else {
+                    return Err(CoreError::invalid("path", "is not a JSON object"));
+                };
+                object.insert("id".to_string(), Value::String(expected.clone()));
+                write_pretty(&path, &value)
+            }
+            Problem::TempFile { path } => {
+                let path = self.contain(Path::new(path))?;
+                if path.exists() {
+                    fs::remove_file(&path).map_err(|e| CoreError::io(&path, e))?;
+                }
+                Ok(())
+            }
+            Problem::DanglingLink {
+                project_id,
+                node_id,
+                scratch_id,
+            } => self.update_project(project_id, |project| {
+                if let Some(node) = find_node_mut(&mut project.outline, node_id) {
+                    node.scratches.retain(|link| &link.scratch_id != scratch_id);
+                }
+            }),
+            Problem::DuplicateNodeId {
+                project_id,
+                node_id,
+            } => self.update_project(project_id, |project| {
+                let mut seen = false;
+                renumber_duplicates(&mut project.outline, node_id, &mut seen);
+            }),
+            Problem::OrphanedOutput { path: dir } => {
+                let path = self.contain(Path::new(dir))?;
+                if path.exists() {
+                    fs::remove_dir_all(&path).map_err(|e| CoreError::io(&path, e))?;
+                }
+                self.forget_output(dir)
+            }
+            Problem::SchemaMismatch {
+                kind,
+                path,
+                message,
+            } => {
+                let path = self.contain(Path::new(path))?;
+                // A project is quarantined together with its directory.
+                let item = match kind {
+                    EntityKind::Project => path.parent().unwrap_or(&path).to_path_buf(),
+                    _ => path.clone(),
+                };
+                let error = CoreError::Parse {
+                    file: path.display().to_string(),
+                    line: 0,
+                    column: 0,
+                    message: message.clone(),
+                };
+                self.quarantine(*kind, &item, &error).map(|_| ())
+            }
+            Problem::MissingTemplate { .. } | Problem::UnknownFields { .. } => Err(
+                CoreError::invalid("problem", "cannot be fixed automatically"),
+            ),
+        }
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(self.base_path())
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    /// Checks every `<id>.json` in `dir` and returns the ids found, readable
+    /// or not, so that links to an unreadable item are not reported as
+    /// dangling as well.
+    fn check_files<T: Serialize + DeserializeOwned>(
+        &self,
+        kind: EntityKind,
+        dir: &Path,
+        problems: &mut Vec<Problem>,
+    ) -> Result<HashSet<String>> {
+        let mut ids = HashSet::new();
+        if !dir.exists() {
+            return Ok(ids);
+        }
+        for entry in fs::read_dir(dir).map_err(|e| CoreError::io(dir, e))? {
+            let entry = entry.map_err(|e| CoreError::io(dir, e))?;
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if validate_id("id", id).is_ok() {
+                self.check_file::<T>(kind, &path, id, problems)?;
+                ids.insert(id.to_string());
+            }
+        }
+        Ok(ids)
+    }
+
+    /// `(id, project.json)` of every project directory.
+    fn project_files(&self) -> Result<Vec<(String, PathBuf)>> {
+        let dir = self.projects_dir();
+        let mut files = Vec::new();
+        if !dir.exists() {
+            return Ok(files);
+        }
+        for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+            let entry = entry.map_err(|e| CoreError::io(&dir, e))?;
+            let id = entry.file_name().to_string_lossy().to_string();
+            let path = entry.path().join("project.json");
+            if validate_id("id", &id).is_ok() && path.exists() {
+                files.push((id, path));
+            }
+        }
+        Ok(files)
+    }
+
+    fn check_file<T: Serialize + DeserializeOwned>(
+        &self,
+        kind: EntityKind,
+        path: &Path,
+        expected_id: &str,
+        problems: &mut Vec<Problem>,
+    ) -> Result<Option<T>> {
+        let bytes = fs::read(path).map_err(|e| CoreError::io(path, e))?;
+        let content = match String::from_utf8(bytes) {
+            Ok(content) => content,
+            Err(e) => {
+                problems.push(Problem::SchemaMismatch {
+                    kind,
+                    path: self.relative(path),
+                    message: format!("not UTF-8: {}", e.utf8_error()),
+                });
+                return Ok(None);
+            }
+        };
+        let (value, item) = match serde_json::from_str::<Value>(&content)
+            .and_then(|value| Ok((value, serde_json::from_str::<T>(&content)?)))
+        {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                problems.push(Problem::SchemaMismatch {
+                    kind,
+                    path: self.relative(path),
+                    message: e.to_string(),
+                });
+                return Ok(None);
+            }
+        };
+
+        if let Some(id) = value.get("id").and_then(Value::as_str) {
+            if id != expected_id {
+                problems.push(Problem::IdMismatch {
+                    kind,
+                    path: self.relative(path),
+                    id: id.to_string(),
+                    expected: expected_id.to_string(),
+                });
+            }
+        }
+        let known = serde_json::to_value(&item).map_err(|e| CoreError::Storage(e.to_string()))?;
+        let mut fields = Vec::new();
+        unknown_fields(&value, &known, "", &mut fields);
+        if !fields.is_empty() {
+            problems.push(Problem::UnknownFields {
+                kind,
+                path: self.relative(path),
+                fields,
+            });
+        }
+        Ok(Some(item))
+    }
+
+    /// Whether the recorded output directory `dir` still exists and neither
+    /// is, contains nor lies inside a project's output directory. Workspace
+    /// data is never reported, whatever was recorded.
+    fn is_orphaned_output(&self, dir: &str, outputs: &HashSet<PathBuf>) -> bool {
+        let top = Path::new(dir).components().next();
+        let is_data = top.is_some_and(|top| {
+            let top = top.as_os_str().to_string_lossy();
+            top.starts_with('.') || DATA_DIRS.contains(&top.as_ref())
+        });
+        let Ok(path) = self.contain(Path::new(dir)) else {
+            return false;
+        };
+        !is_data
+            && path.is_dir()
+            && !outputs
+                .iter()
+                .any(|output| output.starts_with(&path) || path.starts_with(output))
+    }
+
+    fn update_project(&self, id: &str, change: impl FnOnce(&mut Project)) -> Result<()> {
+        validate_id("project_id", id)?;
+        let path = self.contain(&self.projects_dir().join(id).join("project.json"))?;
+        let mut project: Project = read_json(&path)?;
+        change(&mut project);
+        write_pretty(&path, &project)
+    }
+}
+
+fn check_outline(
+    project_id: &str,
+    root: &OutlineNode,
+    scratch_ids: &HashSet<String>,
+    problems: &mut Vec<Problem>,
+) {
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if !seen.insert(node.id.as_str()) && !duplicates.contains(&node.id) {
+            duplicates.push(node.id.clone());
+        }
+        let mut missing: Vec<&str> = node
+            .scratches
+            .iter()
+            .map(|link| link.scratch_id.as_str())
+            .filter(|id| !scratch_ids.contains(*id))
+            .collect();
+        missing.dedup();
+        for scratch_id in missing {
+            problems.push(Problem::DanglingLink {
+                project_id: project_id.to_string(),
+                node_id: node.id.clone(),
+                scratch_id: scratch_id.to_string(),
+            });
+        }
+        stack.extend(node.children.iter().rev());
+    }
+    for node_id in duplicates {
+        problems.push(Problem::DuplicateNodeId {
+            project_id: project_id.to_string(),
+            node_id,
+        });
+    }
+}
+
+fn find_node_mut<'a>(node: &'a mut OutlineNode, id: &str) -> Option<&'a mut OutlineNode> {
+    if node.id == id {
+        return Some(node);
+    }
+    node.children
+        .iter_mut()
+        .find_map(|child| find_node_mut(child, id))
+}
+
+/// Gives every node with `id` after the first, in outline order, a new id.
+fn renumber_duplicates(node: &mut OutlineNode, id: &str, seen: &mut bool) {
+    if node.id == id {
+        if *seen {
+            node.id = Uuid::new_v4().to_string();
+        }
+        *seen = true;
+    }
+    for child in &mut node.children {
+        renumber_duplicates(child, id, seen);
+    }
+}
+
+/// Collects the object keys of `value` that `known`, the same data written
+/// back by this version, lacks. `null` fields are ignored, since optional
+/// fields may be written either way.
+fn unknown_fields(value: &Value, known: &Value, prefix: &str, out: &mut Vec<String>) {
+    match (value, known) {
+        (Value::Object(fields), Value::Object(known_fields)) => {
+            for (name, field) in fields {
+                let path = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                match known_fields.get(name) {
+                    Some(known_field) => unknown_fields(field, known_field, &path, out),
+                    None if !field.is_null() => out.push(path),
+                    None => {}
+                }
+            }
+        }
+        (Value::Array(items), Value::Array(known_items)) => {
+            for (i, (item, known_item)) in items.iter().zip(known_items).enumerate() {
+                unknown_fields(item, known_item, &format!("{}[{}]", prefix, i), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn write_pretty<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let json =
+        serde_json::to_string_pretty(value).map_err(|e| CoreError::Storage(e.to_string()))?;
+    write_atomic(path, json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn node(id: &str, scratches: &[&str], children: Vec<OutlineNode>) -> OutlineNode {
+        OutlineNode {
+            id: id.to_string(),
+            title: id.to_uppercase(),
+            content: None,
+            children,
+            scratches: scratches
+                .iter()
+                .map(|id| ScratchLink {
+                    scratch_id: id.to_string(),
+                    mode: IntegrationMode::Link,
+                    insertion: InsertionFlags {
+                        body: true,
+                        footnote: false,
+                        reference: false,
+                        appendix: false,
+                    },
+                    included: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_check_and_repair() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Workspace::new(temp_dir.path());
+        workspace.ensure_dirs().unwrap();
+
+        let scratch = Scratch {
+            id: "other".to_string(),
+            title: "Note".to_string(),
+            content: String::new(),
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            tags: vec![],
+            source: None,
+        };
+        let mut json = serde_json::to_value(&scratch).unwrap();
+        json["color"] = Value::String("red".to_string());
+        fs::write(workspace.scratches_dir().join("s1.json"), json.to_string()).unwrap();
+        fs::write(workspace.scratches_dir().join(".s1.json.1.tmp"), "").unwrap();
+        fs::write(
+            workspace.scratches_dir().join("s2.json"),
+            "{\"id\": \"s2\"}",
+        )
+        .unwrap();
+        fs::write(
+            workspace.scratches_dir().join("s3.json"),
+            b"{\"id\": \"\xff\"}",
+        )
+        .unwrap();
+
+        let project = Project {
+            id: "p1".to_string(),
+            title: "Thesis".to_string(),
+            outline: node(
+                "root",
+                &[],
+                vec![node("a", &["s1", "gone"], vec![]), node("a", &[], vec![])],
+            ),
+            settings: ProjectSettings {
+                template_id: "missing".to_string(),
+                output_dir: "output/p1".to_string(),
+            },
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            archived: false,
+        };
+        fs::create_dir_all(workspace.projects_dir().join("p1")).unwrap();
+        write_pretty(&workspace.projects_dir().join("p1/project.json"), &project).unwrap();
+        for dir in ["output/p1", "output/old", "notes"] {
+            fs::create_dir_all(temp_dir.path().join(dir)).unwrap();
+            fs::write(temp_dir.path().join(dir).join("main.tex"), "").unwrap();
+        }
+        for dir in ["output/p1", "output/old", "scratches"] {
+            workspace.record_output(Path::new(dir)).unwrap();
+        }
+
+        let findings = workspace.check().unwrap();
+        let problems: Vec<_> = findings.iter().map(|f| f.problem.clone()).collect();
+        let expected = [
+            Problem::TempFile {
+                path: "scratches/.s1.json.1.tmp".to_string(),
+            },
+            Problem::IdMismatch {
+                kind: EntityKind::Scratch,
+                path: "scratches/s1.json".to_string(),
+                id: "other".to_string(),
+                expected: "s1".to_string(),
+            },
+            Problem::UnknownFields {
+                kind: EntityKind::Scratch,
+                path: "scratches/s1.json".to_string(),
+                fields: vec!["color".to_string()],
+            },
+            Problem::DanglingLink {
+                project_id: "p1".to_string(),
+                node_id: "a".to_string(),
+                scratch_id: "gone".to_string(),
+            },
+            Problem::DuplicateNodeId {
+                project_id: "p1".to_string(),
+                node_id: "a".to_string(),
+            },
+            Problem::MissingTemplate {
+                project_id: "p1".to_string(),
+                template_id: "missing".to_string(),
+            },
+            Problem::OrphanedOutput {
+                path: "output/old".to_string(),
+            },
+        ];
+        for problem in &expected {
+            assert!(problems.contains(problem), "{:?} not found", problem);
+        }
+        for unreadable in ["scratches/s2.json", "scratches/s3.json"] {
+            assert!(problems.iter().any(|p| matches!(
+                p,
+                Problem::SchemaMismatch { path, .. } if path == unreadable
+            )));
+        }
+        assert_eq!(problems.len(), expected.len() + 2);
+
+        let fixable: Vec<_> = findings
+            .iter()
+            .filter(|f| f.fix.is_some())
+            .map(|f| f.problem.clone())
+            .collect();
+        workspace.repair(&fixable, false).unwrap();
+        assert!(temp_dir.path().join("output/old").exists());
+        workspace.repair(&fixable, true).unwrap();
+        assert!(!temp_dir.path().join("output/old").exists());
+        let remaining: Vec<_> = workspace
+            .check()
+            .unwrap()
+            .into_iter()
+            .map(|f| f.problem)
+            .collect();
+        assert_eq!(remaining.len(), 2, "{:?}", remaining);
+        assert!(remaining.iter().all(|p| matches!(
+            p,
+            Problem::MissingTemplate { .. } | Problem::UnknownFields { .. }
+        )));
+        assert!(temp_dir.path().join("output/p1/main.tex").exists());
+        assert!(temp_dir.path().join("notes/main.tex").exists());
+        assert_eq!(workspace.workspace_issues().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_repair_ignores_problems_it_does_not_find() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Workspace::new(temp_dir.path());
+        workspace.ensure_dirs().unwrap();
+        fs::write(workspace.scratches_dir().join("keep.json"), "{}").unwrap();
+
+        workspace
+            .repair(
+                &[
+                    Problem::TempFile {
+                        path: "scratches/keep.json".to_string(),
+                    },
+                    Problem::OrphanedOutput {
+                        path: "projects".to_string(),
+                    },
+                ],
+                true,
+            )
+            .unwrap();
+        assert!(workspace.scratches_dir().join("keep.json").exists());
+        assert!(workspace.projects_dir().exists());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{read_or_quarantine, write_atomic, Workspace};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tarsius_core::*;
+
+/// Keeps each version of a scratch in a file of its own, under
+/// `history/<scratch>/<version>.json`.
+pub struct FilesystemHistoryRepository {
+    workspace: Arc<Workspace>,
+}
+
+impl FilesystemHistoryRepository {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    fn history_dir(&self, scratch_id: &str) -> Result<PathBuf> {
+        validate_id("id", scratch_id)?;
+        self.workspace
+            .contain(&self.workspace.history_dir().join(scratch_id))
+    }
+
+    fn version_path(&self, scratch_id: &str, version: u32) -> Result<PathBuf> {
+        Ok(self
+            .history_dir(scratch_id)?
+            .join(format!("{}.json", version)))
+    }
+
+    fn read(&self, scratch_id: &str) -> Result<BTreeMap<u32, ScratchVersion>> {
+        let mut versions = BTreeMap::new();
+        let dir = self.history_dir(scratch_id)?;
+        if dir.exists() {
+            for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+                let path = entry.map_err(|e| CoreError::io(&dir, e))?.path();
+                if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                    continue;
+                }
+                // A damaged version is set aside and left out.
+                let version: Option<ScratchVersion> =
+                    read_or_quarantine(&self.workspace, EntityKind::ScratchVersion, &path, &path)?;
+                versions.extend(version.map(|v| (v.version, v)));
+            }
+        }
+        Ok(versions)
+    }
+
+    fn write(&self, scratch_id: &str, version: &ScratchVersion) -> Result<()> {
+        let dir = self.history_dir(scratch_id)?;
+        fs::create_dir_all(&dir).map_err(|e| CoreError::io(&dir, e))?;
+        let json = serde_json::to_string(version).map_err(|e| CoreError::Storage(e.to_string()))?;
+        write_atomic(self.version_path(scratch_id, version.version)?, json)
+    }
+}
+
+impl HistoryRepository for FilesystemHistoryRepository {
+    fn list(&self, scratch_id: &str) -> Result<Vec<ScratchVersion>> {
+        Ok(self.read(scratch_id)?.into_values().collect())
+    }
+
+    fn put(&self, scratch_id: &str, version: &ScratchVersion) -> Result<()> {
+        self.write(scratch_id, version)
+    }
+
+    fn remove(&self, scratch_id: &str, versions: &[u32]) -> Result<()> {
+        for &version in versions {
+            let path = self.version_path(scratch_id, version)?;
+            if path.exists() {
+                fs::remove_file(&path).map_err(|e| CoreError::io(&path, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn delete(&self, scratch_id: &str) -> Result<()> {
+        let dir = self.history_dir(scratch_id)?;
+        if dir.exists() {
+            fs::remove_dir_all(&dir).map_err(|e| CoreError::io(&dir, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn version(number: u32, content: &str) -> ScratchVersion {
+        ScratchVersion {
+            version: number,
+            recorded_at: chrono::DateTime::from_timestamp(1_700_000_000 + i64::from(number), 0)
+                .unwrap(),
+            changed: vec![ScratchField::Content],
+            title: "Note".to_string(),
+            content: content.to_string(),
+            tags: vec![],
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_put_list_and_delete() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path()));
+        workspace.ensure_dirs().unwrap();
+        let history = FilesystemHistoryRepository::new(workspace.clone());
+        assert!(history.list("s1").unwrap().is_empty());
+
+        for n in 1..=3 {
+            history.put("s1", &version(n, "text")).unwrap();
+        }
+        history.put("s1", &version(3, "merged")).unwrap();
+        history.remove("s1", &[1, 7]).unwrap();
+        assert_eq!(
+            history.list("s1").unwrap(),
+            vec![version(2, "text"), version(3, "merged")]
+        );
+
+        // A corrupt version is quarantined and left out.
+        fs::write(workspace.history_dir().join("s1").join("2.json"), "{").unwrap();
+        assert_eq!(history.list("s1").unwrap().len(), 1);
+        assert_eq!(workspace.workspace_issues().unwrap().len(), 1);
+
+        history.delete("s1").unwrap();
+        assert!(history.list("s1").unwrap().is_empty());
+        assert!(history.list("../s1").is_err());
+    }
+}
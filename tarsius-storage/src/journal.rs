@@ -0,0 +1,124 @@
+use crate::{read_or_quarantine, Workspace};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tarsius_core::*;
+
+/// Keeps one file per item with pending edits under `journal/`.
+///
+/// Entries are written to a temp file and renamed into place, so an app crash
+/// never leaves a torn entry, but they are not synced to disk: the journal
+/// guards against the app going away, and a full fsync on every keystroke
+/// would defeat its purpose.
+pub struct FilesystemJournalRepository {
+    workspace: Arc<Workspace>,
+}
+
+impl FilesystemJournalRepository {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    fn entry_path(&self, kind: JournalKind, id: &str) -> Result<PathBuf> {
+        validate_id("id", id)?;
+        let prefix = match kind {
+            JournalKind::Scratch => "scratch",
+            JournalKind::Project => "project",
+        };
+        self.workspace.contain(
+            &self
+                .workspace
+                .journal_dir()
+                .join(format!("{}-{}.json", prefix, id)),
+        )
+    }
+}
+
+impl JournalRepository for FilesystemJournalRepository {
+    fn record(&self, entry: &JournalEntry) -> Result<()> {
+        let dir = self.workspace.journal_dir();
+        fs::create_dir_all(&dir).map_err(|e| CoreError::io(&dir, e))?;
+        let path = self.entry_path(entry.kind(), &entry.id)?;
+        let json = serde_json::to_vec(entry).map_err(|e| CoreError::Storage(e.to_string()))?;
+        let temp_path = dir.join(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        fs::write(&temp_path, json).map_err(|e| CoreError::io(&temp_path, e))?;
+        fs::rename(&temp_path, &path).map_err(|e| CoreError::io(&path, e))
+    }
+
+    fn list(&self) -> Result<Vec<JournalEntry>> {
+        let dir = self.workspace.journal_dir();
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+            let entry = entry.map_err(|e| CoreError::io(&dir, e))?;
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) == Some("json") {
+                entries.extend(read_or_quarantine(
+                    &self.workspace,
+                    EntityKind::JournalEntry,
+                    &path,
+                    &path,
+                )?);
+            }
+        }
+        entries.sort_by_key(|e: &JournalEntry| e.recorded_at);
+        Ok(entries)
+    }
+
+    fn discard(&self, kind: JournalKind, id: &str) -> Result<()> {
+        let path = self.entry_path(kind, id)?;
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| CoreError::io(&path, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_replaces_and_discard_removes() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path()));
+        workspace.ensure_dirs().unwrap();
+        let journal = FilesystemJournalRepository::new(workspace);
+
+        let entry = |content: &str| JournalEntry {
+            id: "s1".to_string(),
+            recorded_at: chrono::Utc::now(),
+            draft: Draft::Scratch(ScratchDraft {
+                title: "Note".to_string(),
+                content: content.to_string(),
+                tags: vec![],
+                source: None,
+            }),
+        };
+        journal.record(&entry("first")).unwrap();
+        journal.record(&entry("second")).unwrap();
+
+        let entries = journal.list().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind(), JournalKind::Scratch);
+        let Draft::Scratch(draft) = &entries[0].draft else {
+            panic!("expected a scratch draft");
+        };
+        assert_eq!(draft.content, "second");
+
+        // A project with the same id is a separate entry.
+        journal.discard(JournalKind::Project, "s1").unwrap();
+        assert_eq!(journal.list().unwrap().len(), 1);
+        journal.discard(JournalKind::Scratch, "s1").unwrap();
+        assert!(journal.list().unwrap().is_empty());
+        assert!(journal
+            .record(&JournalEntry {
+                id: "../s1".to_string(),
+                ..entry("x")
+            })
+            .is_err());
+    }
+}
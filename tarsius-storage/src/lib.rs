@@ -1,8 +1,30 @@
-use serde_json;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use tarsius_core::*;
 
+mod backup;
+mod blueprint;
+mod fsck;
+mod history;
+mod journal;
+mod lock;
+mod quarantine;
+mod release;
+mod trash;
+mod watch;
+
+pub use backup::*;
+pub use blueprint::*;
+pub use fsck::*;
+pub use history::*;
+pub use journal::*;
+pub use lock::*;
+pub use quarantine::*;
+pub use release::*;
+pub use trash::*;
+pub use watch::*;
+
 pub trait ScratchRepository {
     fn save(&self, scratch: &Scratch) -> Result<()>;
     fn load(&self, id: &str) -> Result<Scratch>;
@@ -28,15 +50,35 @@ use std::sync::Arc;
 
 pub struct Workspace {
     base_path: PathBuf,
+    read_only: bool,
 }
 
 impl Workspace {
+    /// A relative `base_path` is resolved against the current directory
+    /// right away, so that paths handed out stay valid if it changes.
     pub fn new<P: AsRef<Path>>(base_path: P) -> Self {
+        let base_path = base_path.as_ref();
         Self {
-            base_path: base_path.as_ref().to_path_buf(),
+            base_path: std::path::absolute(base_path).unwrap_or_else(|_| base_path.to_path_buf()),
+            read_only: false,
         }
     }
 
+    /// Marks the workspace as opened without its lock. Listing then skips
+    /// unreadable files instead of moving them to the quarantine.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
     pub fn scratches_dir(&self) -> PathBuf {
         self.base_path.join("scratches")
     }
@@ -45,16 +87,139 @@ impl Workspace {
         self.base_path.join("projects")
     }
 
+    /// Frozen copies of a project, see `FilesystemReleaseRepository`.
+    pub fn releases_dir(&self, project_id: &str) -> PathBuf {
+        self.projects_dir().join(project_id).join("releases")
+    }
+
     pub fn templates_dir(&self) -> PathBuf {
         self.base_path.join("templates")
     }
 
+    pub fn trash_dir(&self) -> PathBuf {
+        self.base_path.join("trash")
+    }
+
+    pub fn backups_dir(&self) -> PathBuf {
+        self.base_path.join("backups")
+    }
+
+    /// Unsaved editor content, see `FilesystemJournalRepository`.
+    pub fn journal_dir(&self) -> PathBuf {
+        self.base_path.join("journal")
+    }
+
+    /// Past versions of scratches, see `FilesystemHistoryRepository`.
+    pub fn history_dir(&self) -> PathBuf {
+        self.base_path.join("history")
+    }
+
+    /// Blueprints saved from projects, see `FilesystemBlueprintRepository`.
+    pub fn blueprints_dir(&self) -> PathBuf {
+        self.base_path.join("blueprints")
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.base_path.join("settings.json")
+    }
+
+    /// Windows open on the workspace, see `WindowLayout`.
+    pub fn window_layout_path(&self) -> PathBuf {
+        self.base_path.join("windows.json")
+    }
+
+    /// Directories builds were written to, see `Workspace::record_output`.
+    pub fn outputs_path(&self) -> PathBuf {
+        self.base_path.join("outputs.json")
+    }
+
+    /// Address and token of the running local API server, for clients to
+    /// discover. Only present while the server runs.
+    pub fn server_info_path(&self) -> PathBuf {
+        self.base_path.join("server.json")
+    }
+
+    /// Resolves `path` (relative paths against the workspace), following
+    /// symlinks, and checks that the result stays inside the workspace. The
+    /// path does not need to exist yet.
+    pub fn contain(&self, path: &Path) -> Result<PathBuf> {
+        let base = self
+            .base_path
+            .canonicalize()
+            .map_err(|e| CoreError::io(&self.base_path, e))?;
+        let full = self.base_path.join(path);
+        let existing = full
+            .ancestors()
+            .find(|ancestor| ancestor.exists())
+            .unwrap_or(&self.base_path);
+        let rest = full.strip_prefix(existing).unwrap_or(Path::new(""));
+        let escapes = || {
+            CoreError::invalid(
+                "path",
+                format!("{} is outside the workspace", path.display()),
+            )
+        };
+        if !rest
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)))
+        {
+            return Err(escapes());
+        }
+        let mut resolved = existing
+            .canonicalize()
+            .map_err(|e| CoreError::io(existing, e))?;
+        // Joining an empty path would add a trailing separator.
+        if !rest.as_os_str().is_empty() {
+            resolved.push(rest);
+        }
+        if !resolved.starts_with(&base) {
+            return Err(escapes());
+        }
+        Ok(resolved)
+    }
+
     pub fn ensure_dirs(&self) -> Result<()> {
-        fs::create_dir_all(self.scratches_dir()).map_err(|e| CoreError::Storage(e.to_string()))?;
-        fs::create_dir_all(self.projects_dir()).map_err(|e| CoreError::Storage(e.to_string()))?;
-        fs::create_dir_all(self.templates_dir()).map_err(|e| CoreError::Storage(e.to_string()))?;
+        for dir in [
+            self.scratches_dir(),
+            self.projects_dir(),
+            self.templates_dir(),
+            self.trash_dir(),
+        ] {
+            fs::create_dir_all(&dir).map_err(|e| CoreError::io(&dir, e))?;
+        }
         Ok(())
     }
+
+    /// Reads `settings.json`, falling back to defaults when it does not exist.
+    pub fn load_settings(&self) -> Result<WorkspaceSettings> {
+        let path = self.settings_path();
+        if !path.exists() {
+            return Ok(WorkspaceSettings::default());
+        }
+        read_json(&path)
+    }
+
+    pub fn save_settings(&self, settings: &WorkspaceSettings) -> Result<()> {
+        let json = serde_json::to_string_pretty(settings)
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+        write_atomic(self.settings_path(), json)
+    }
+
+    /// Reads `windows.json`, falling back to an empty layout when it does
+    /// not exist.
+    pub fn load_window_layout(&self) -> Result<WindowLayout> {
+        let path = self.window_layout_path();
+        if !path.exists() {
+            return Ok(WindowLayout::default());
+        }
+        read_json(&path)
+    }
+
+    pub fn save_window_layout(&self, layout: &WindowLayout) -> Result<()> {
+        let json =
+            serde_json::to_string_pretty(layout).map_err(|e| CoreError::Storage(e.to_string()))?;
+        write_atomic(self.window_layout_path(), json)
+    }
 }
 
 pub struct FilesystemScratchRepository {
@@ -66,14 +231,16 @@ impl FilesystemScratchRepository {
         Self { workspace }
     }
 
-    fn scratch_path(&self, id: &str) -> PathBuf {
-        self.workspace.scratches_dir().join(format!("{}.json", id))
+    fn scratch_path(&self, id: &str) -> Result<PathBuf> {
+        validate_id("id", id)?;
+        self.workspace
+            .contain(&self.workspace.scratches_dir().join(format!("{}.json", id)))
     }
 }
 
 impl tarsius_core::ScratchRepository for FilesystemScratchRepository {
     fn save(&self, scratch: &Scratch) -> Result<()> {
-        let path = self.scratch_path(&scratch.id);
+        let path = self.scratch_path(&scratch.id)?;
         let json =
             serde_json::to_string_pretty(scratch).map_err(|e| CoreError::Storage(e.to_string()))?;
         write_atomic(&path, json)?;
@@ -81,38 +248,35 @@ impl tarsius_core::ScratchRepository for FilesystemScratchRepository {
     }
 
     fn load(&self, id: &str) -> Result<Scratch> {
-        let path = self.scratch_path(id);
+        let path = self.scratch_path(id)?;
         if !path.exists() {
-            return Err(CoreError::NotFound(format!("Scratch {}", id)));
+            return Err(CoreError::not_found(EntityKind::Scratch, id));
         }
-        let content = fs::read_to_string(path).map_err(|e| CoreError::Storage(e.to_string()))?;
-        let scratch: Scratch =
-            serde_json::from_str(&content).map_err(|e| CoreError::Storage(e.to_string()))?;
-        Ok(scratch)
+        read_json(&path)
     }
 
     fn list(&self) -> Result<Vec<Scratch>> {
         let mut scratches = Vec::new();
-        for entry in fs::read_dir(self.workspace.scratches_dir())
-            .map_err(|e| CoreError::Storage(e.to_string()))?
-        {
-            let entry = entry.map_err(|e| CoreError::Storage(e.to_string()))?;
+        let dir = self.workspace.scratches_dir();
+        for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+            let entry = entry.map_err(|e| CoreError::io(&dir, e))?;
             let path = entry.path();
             if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                let content =
-                    fs::read_to_string(&path).map_err(|e| CoreError::Storage(e.to_string()))?;
-                let scratch: Scratch = serde_json::from_str(&content)
-                    .map_err(|e| CoreError::Storage(e.to_string()))?;
-                scratches.push(scratch);
+                scratches.extend(read_or_quarantine(
+                    &self.workspace,
+                    EntityKind::Scratch,
+                    &path,
+                    &path,
+                )?);
             }
         }
         Ok(scratches)
     }
 
     fn delete(&self, id: &str) -> Result<()> {
-        let path = self.scratch_path(id);
+        let path = self.scratch_path(id)?;
         if path.exists() {
-            fs::remove_file(path).map_err(|e| CoreError::Storage(e.to_string()))?;
+            fs::remove_file(&path).map_err(|e| CoreError::io(&path, e))?;
         }
         Ok(())
     }
@@ -127,20 +291,22 @@ impl FilesystemProjectRepository {
         Self { workspace }
     }
 
-    fn project_dir(&self, id: &str) -> PathBuf {
-        self.workspace.projects_dir().join(id)
+    fn project_dir(&self, id: &str) -> Result<PathBuf> {
+        validate_id("id", id)?;
+        self.workspace
+            .contain(&self.workspace.projects_dir().join(id))
     }
 
-    fn project_path(&self, id: &str) -> PathBuf {
-        self.project_dir(id).join("project.json")
+    fn project_path(&self, id: &str) -> Result<PathBuf> {
+        Ok(self.project_dir(id)?.join("project.json"))
     }
 }
 
 impl tarsius_core::ProjectRepository for FilesystemProjectRepository {
     fn save(&self, project: &Project) -> Result<()> {
-        let dir = self.project_dir(&project.id);
-        fs::create_dir_all(&dir).map_err(|e| CoreError::Storage(e.to_string()))?;
-        let path = self.project_path(&project.id);
+        let dir = self.project_dir(&project.id)?;
+        fs::create_dir_all(&dir).map_err(|e| CoreError::io(&dir, e))?;
+        let path = self.project_path(&project.id)?;
         let json =
             serde_json::to_string_pretty(project).map_err(|e| CoreError::Storage(e.to_string()))?;
         write_atomic(&path, json)?;
@@ -148,30 +314,36 @@ impl tarsius_core::ProjectRepository for FilesystemProjectRepository {
     }
 
     fn load(&self, id: &str) -> Result<Project> {
-        let path = self.project_path(id);
+        let path = self.project_path(id)?;
         if !path.exists() {
-            return Err(CoreError::NotFound(format!("Project {}", id)));
+            return Err(CoreError::not_found(EntityKind::Project, id));
         }
-        let content = fs::read_to_string(path).map_err(|e| CoreError::Storage(e.to_string()))?;
-        let project: Project =
-            serde_json::from_str(&content).map_err(|e| CoreError::Storage(e.to_string()))?;
-        Ok(project)
+        read_json(&path)
     }
 
     fn list(&self) -> Result<Vec<Project>> {
         let mut projects = Vec::new();
-        for entry in fs::read_dir(self.workspace.projects_dir())
-            .map_err(|e| CoreError::Storage(e.to_string()))?
-        {
-            let entry = entry.map_err(|e| CoreError::Storage(e.to_string()))?;
+        let dir = self.workspace.projects_dir();
+        for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+            let entry = entry.map_err(|e| CoreError::io(&dir, e))?;
             if entry
                 .file_type()
-                .map_err(|e| CoreError::Storage(e.to_string()))?
+                .map_err(|e| CoreError::io(&entry.path(), e))?
                 .is_dir()
             {
+                // Directories that are not projects (no valid id, no
+                // `project.json`) are left alone.
                 let dir_name = entry.file_name().to_string_lossy().to_string();
-                if let Ok(project) = self.load(&dir_name) {
-                    projects.push(project);
+                let Ok(path) = self.project_path(&dir_name) else {
+                    continue;
+                };
+                if path.exists() {
+                    projects.extend(read_or_quarantine(
+                        &self.workspace,
+                        EntityKind::Project,
+                        &path,
+                        &entry.path(),
+                    )?);
                 }
             }
         }
@@ -179,9 +351,9 @@ impl tarsius_core::ProjectRepository for FilesystemProjectRepository {
     }
 
     fn delete(&self, id: &str) -> Result<()> {
-        let dir = self.project_dir(id);
+        let dir = self.project_dir(id)?;
         if dir.exists() {
-            fs::remove_dir_all(dir).map_err(|e| CoreError::Storage(e.to_string()))?;
+            fs::remove_dir_all(&dir).map_err(|e| CoreError::io(&dir, e))?;
         }
         Ok(())
     }
@@ -196,14 +368,16 @@ impl FilesystemTemplateRepository {
         Self { workspace }
     }
 
-    fn template_path(&self, id: &str) -> PathBuf {
-        self.workspace.templates_dir().join(format!("{}.json", id))
+    fn template_path(&self, id: &str) -> Result<PathBuf> {
+        validate_id("id", id)?;
+        self.workspace
+            .contain(&self.workspace.templates_dir().join(format!("{}.json", id)))
     }
 }
 
 impl tarsius_core::TemplateRepository for FilesystemTemplateRepository {
     fn save(&self, template: &Template) -> Result<()> {
-        let path = self.template_path(&template.id);
+        let path = self.template_path(&template.id)?;
         let json = serde_json::to_string_pretty(template)
             .map_err(|e| CoreError::Storage(e.to_string()))?;
         write_atomic(&path, json)?;
@@ -211,57 +385,103 @@ impl tarsius_core::TemplateRepository for FilesystemTemplateRepository {
     }
 
     fn load(&self, id: &str) -> Result<Template> {
-        let path = self.template_path(id);
+        let path = self.template_path(id)?;
         if !path.exists() {
-            return Err(CoreError::NotFound(format!("Template {}", id)));
+            return Err(CoreError::not_found(EntityKind::Template, id));
         }
-        let content = fs::read_to_string(path).map_err(|e| CoreError::Storage(e.to_string()))?;
-        let template: Template =
-            serde_json::from_str(&content).map_err(|e| CoreError::Storage(e.to_string()))?;
-        Ok(template)
+        read_json(&path)
     }
 
     fn list(&self) -> Result<Vec<Template>> {
         let mut templates = Vec::new();
-        for entry in fs::read_dir(self.workspace.templates_dir())
-            .map_err(|e| CoreError::Storage(e.to_string()))?
-        {
-            let entry = entry.map_err(|e| CoreError::Storage(e.to_string()))?;
+        let dir = self.workspace.templates_dir();
+        for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+            let entry = entry.map_err(|e| CoreError::io(&dir, e))?;
             let path = entry.path();
             if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                let content =
-                    fs::read_to_string(&path).map_err(|e| CoreError::Storage(e.to_string()))?;
-                let template: Template = serde_json::from_str(&content)
-                    .map_err(|e| CoreError::Storage(e.to_string()))?;
-                templates.push(template);
+                templates.extend(read_or_quarantine(
+                    &self.workspace,
+                    EntityKind::Template,
+                    &path,
+                    &path,
+                )?);
             }
         }
         Ok(templates)
     }
 
     fn delete(&self, id: &str) -> Result<()> {
-        let path = self.template_path(id);
+        let path = self.template_path(id)?;
         if path.exists() {
-            fs::remove_file(path).map_err(|e| CoreError::Storage(e.to_string()))?;
+            fs::remove_file(&path).map_err(|e| CoreError::io(&path, e))?;
         }
         Ok(())
     }
 }
 
+/// Replaces `path` with `content` so that readers and crashes see either the
+/// old or the new file, never a partial one.
+///
+/// The content goes to a uniquely named temp file next to `path`, so
+/// concurrent saves of the same file do not share it, and is flushed to disk
+/// before the rename. The directory is synced afterwards so the rename itself
+/// survives a crash.
 fn write_atomic<P: AsRef<Path>>(path: P, content: String) -> Result<()> {
     let path = path.as_ref();
-    let temp_path = path.with_extension("tmp");
-    fs::write(&temp_path, content).map_err(|e| CoreError::Storage(e.to_string()))?;
-    fs::rename(temp_path, path).map_err(|e| CoreError::Storage(e.to_string()))?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = dir.join(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4()));
+    let result = write_synced(&temp_path, content.as_bytes())
+        .and_then(|_| fs::rename(&temp_path, path).map_err(|e| CoreError::io(path, e)));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+    sync_dir(dir)
+}
+
+fn write_synced(path: &Path, content: &[u8]) -> Result<()> {
+    let mut file = fs::File::create(path).map_err(|e| CoreError::io(path, e))?;
+    file.write_all(content)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| CoreError::io(path, e))
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<()> {
+    fs::File::open(dir)
+        .and_then(|d| d.sync_all())
+        .map_err(|e| CoreError::io(dir, e))
+}
+
+// Directories cannot be opened as files on Windows; NTFS journals renames.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Reads and decodes a JSON file, reporting where decoding failed.
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let content = fs::read_to_string(path).map_err(|e| CoreError::io(path, e))?;
+    serde_json::from_str(&content).map_err(|e| CoreError::Parse {
+        file: path.display().to_string(),
+        line: e.line(),
+        column: e.column(),
+        message: e.to_string(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::sync::Arc;
+    use tarsius_core::{
+        OutlineNode, Project, ProjectRepository, ProjectSettings, Scratch, ScratchRepository,
+    };
     use tempfile::TempDir;
-    use tarsius_core::{ScratchRepository, ProjectRepository, Scratch, Project, OutlineNode, ProjectSettings};
 
     #[test]
     fn test_filesystem_scratch_repository() {
@@ -305,6 +525,30 @@ mod tests {
         assert!(repo.load("test-scratch").is_err());
     }
 
+    #[test]
+    fn test_relative_workspace() {
+        let temp_dir = TempDir::new_in(".").unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        let relative = temp_dir.path().strip_prefix(&cwd).unwrap();
+        let workspace = Arc::new(Workspace::new(relative));
+        workspace.ensure_dirs().unwrap();
+        assert!(workspace.base_path().is_absolute());
+
+        let repo = FilesystemScratchRepository::new(workspace.clone());
+        let scratch = Scratch {
+            id: "relative".to_string(),
+            title: "Relative".to_string(),
+            content: "Saved below a relative path".to_string(),
+            created_at: chrono::Utc::now(),
+            modified_at: chrono::Utc::now(),
+            tags: vec![],
+            source: None,
+        };
+        repo.save(&scratch).unwrap();
+        assert_eq!(repo.load("relative").unwrap().content, scratch.content);
+        assert!(relative.join("scratches").join("relative.json").exists());
+    }
+
     #[test]
     fn test_filesystem_project_repository() {
         let temp_dir = TempDir::new().unwrap();
@@ -330,6 +574,7 @@ mod tests {
             },
             created_at: chrono::Utc::now(),
             modified_at: chrono::Utc::now(),
+            archived: false,
         };
 
         // Save
@@ -354,6 +599,144 @@ mod tests {
         assert!(repo.load("test-project").is_err());
     }
 
+    #[test]
+    fn test_ids_cannot_escape_workspace() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path().join("ws")));
+        workspace.ensure_dirs().unwrap();
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("secret.json"), "{}").unwrap();
+        fs::write(workspace.projects_dir().join("keep.json"), "{}").unwrap();
+
+        let scratches = FilesystemScratchRepository::new(workspace.clone());
+        let projects = FilesystemProjectRepository::new(workspace.clone());
+        for id in [
+            "../../outside/secret",
+            "..",
+            "../projects/keep",
+            "/etc/passwd",
+        ] {
+            assert!(matches!(scratches.load(id), Err(CoreError::Validation(_))));
+            assert!(scratches.delete(id).is_err());
+            assert!(projects.delete(id).is_err());
+        }
+        assert!(projects.delete("..").is_err());
+        assert!(outside.join("secret.json").exists());
+        assert!(workspace.projects_dir().join("keep.json").exists());
+
+        // A symlinked project directory must not let deletes reach outside.
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, workspace.projects_dir().join("linked")).unwrap();
+            assert!(projects.delete("linked").is_err());
+            assert!(outside.join("secret.json").exists());
+        }
+
+        assert!(workspace.contain(Path::new("out/pdf")).is_ok());
+        assert!(workspace.contain(Path::new("../outside")).is_err());
+        assert!(workspace.contain(Path::new("out/../../outside")).is_err());
+        assert!(workspace.contain(&outside).is_err());
+    }
+
+    #[test]
+    fn test_project_save_updates_scratch_usages() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path()));
+        workspace.ensure_dirs().unwrap();
+        let manager = ProjectManager::new(Box::new(FilesystemProjectRepository::new(workspace)));
+
+        let mut project = manager
+            .create("Paper".to_string(), "t".to_string(), "out".to_string())
+            .unwrap();
+        assert!(manager.scratch_usages("s1").unwrap().is_empty());
+
+        project.outline.scratches.push(ScratchLink {
+            scratch_id: "s1".to_string(),
+            mode: IntegrationMode::Include,
+            insertion: InsertionFlags {
+                body: true,
+                footnote: false,
+                reference: false,
+                appendix: false,
+            },
+            included: None,
+        });
+        manager.save(&project).unwrap();
+        let usages = manager.scratch_usages("s1").unwrap();
+        assert_eq!(usages.len(), 1);
+        assert_eq!(usages[0].node_id, project.outline.id);
+        assert!(manager.used_scratch_ids().unwrap().contains("s1"));
+
+        manager.delete(&project.id).unwrap();
+        assert!(manager.scratch_usages("s1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_included_scratch_drift() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path()));
+        workspace.ensure_dirs().unwrap();
+        let scratches = ScratchManager::new(Box::new(FilesystemScratchRepository::new(
+            workspace.clone(),
+        )));
+        let projects = ProjectManager::new(Box::new(FilesystemProjectRepository::new(
+            workspace.clone(),
+        )))
+        .with_scratches(Box::new(FilesystemScratchRepository::new(workspace)));
+
+        let scratch = scratches
+            .create("Note".to_string(), "v1".to_string(), vec![], None)
+            .unwrap();
+        let mut project = projects
+            .create("Paper".to_string(), "t".to_string(), "out".to_string())
+            .unwrap();
+        project.outline.scratches.push(ScratchLink {
+            scratch_id: scratch.id.clone(),
+            mode: IntegrationMode::Include,
+            insertion: InsertionFlags {
+                body: true,
+                footnote: false,
+                reference: false,
+                appendix: false,
+            },
+            included: None,
+        });
+        projects.save(&project).unwrap();
+        let stored = projects.load(&project.id).unwrap();
+        assert!(projects.drifted_links(&stored).is_empty());
+
+        scratches
+            .update(scratch.id.clone(), None, Some("v2".to_string()), None, None)
+            .unwrap();
+        // Saving the copy without its snapshot must not re-baseline it.
+        projects.save(&project).unwrap();
+        let stored = projects.load(&project.id).unwrap();
+        let drifted = projects.drifted_links(&stored);
+        assert_eq!(drifted.len(), 1);
+        assert!(!drifted[0].locally_modified);
+
+        let view = projects
+            .drift_view(&project.id, &project.outline.id, &scratch.id)
+            .unwrap();
+        assert_eq!(view.original, "v1");
+        assert_eq!(view.current, "v2");
+        assert_eq!(view.local, "v1");
+
+        let resolved = projects
+            .resolve_drift(
+                &project.id,
+                &project.outline.id,
+                &scratch.id,
+                DriftResolution::Merge("v1 + v2".to_string()),
+            )
+            .unwrap();
+        assert!(projects.drifted_links(&resolved).is_empty());
+        let copy = resolved.outline.scratches[0].included.as_ref().unwrap();
+        assert_eq!(copy.text, "v1 + v2");
+        assert_eq!(copy.original, "v2");
+    }
+
     #[test]
     fn test_workspace_dirs() {
         let temp_dir = TempDir::new().unwrap();
@@ -363,6 +746,7 @@ mod tests {
         assert!(!workspace.scratches_dir().exists());
         assert!(!workspace.projects_dir().exists());
         assert!(!workspace.templates_dir().exists());
+        assert!(!workspace.trash_dir().exists());
 
         // Ensure dirs
         workspace.ensure_dirs().unwrap();
@@ -371,5 +755,22 @@ mod tests {
         assert!(workspace.scratches_dir().exists());
         assert!(workspace.projects_dir().exists());
         assert!(workspace.templates_dir().exists());
+        assert!(workspace.trash_dir().exists());
+    }
+
+    #[test]
+    fn test_workspace_settings_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Workspace::new(temp_dir.path());
+        workspace.ensure_dirs().unwrap();
+
+        assert_eq!(workspace.load_settings().unwrap().trash_retention_days, 30);
+
+        let settings = WorkspaceSettings {
+            trash_retention_days: 7,
+            ..Default::default()
+        };
+        workspace.save_settings(&settings).unwrap();
+        assert_eq!(workspace.load_settings().unwrap().trash_retention_days, 7);
     }
 }
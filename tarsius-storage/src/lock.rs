@@ -0,0 +1,169 @@
+use crate::Workspace;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use tarsius_core::*;
+
+/// Who holds a workspace lock, as recorded in the lock file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub pid: u32,
+    pub acquired_at: DateTime<Utc>,
+}
+
+/// Exclusive, advisory lock on a workspace, held until dropped.
+///
+/// The lock is taken on `workspace.lock` through the operating system, so it
+/// is released when the process exits, even after a crash; a leftover lock
+/// file on its own means nothing.
+#[derive(Debug)]
+pub struct WorkspaceLock {
+    // Keeps the OS lock alive.
+    _file: File,
+}
+
+impl Workspace {
+    pub fn lock_path(&self) -> PathBuf {
+        self.base_path().join("workspace.lock")
+    }
+
+    /// Takes the workspace lock, failing with `CoreError::Locked` if another
+    /// instance holds it.
+    pub fn lock(&self) -> Result<WorkspaceLock> {
+        let path = self.lock_path();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| CoreError::io(&path, e))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let holder = match self.lock_holder() {
+                    Some(info) => format!("in use by process {}", info.pid),
+                    None => "in use by another instance".to_string(),
+                };
+                return Err(CoreError::Locked(holder));
+            }
+            Err(TryLockError::Error(e)) => return Err(CoreError::io(&path, e)),
+        }
+
+        let info = LockInfo {
+            pid: std::process::id(),
+            acquired_at: Utc::now(),
+        };
+        let json =
+            serde_json::to_vec_pretty(&info).map_err(|e| CoreError::Storage(e.to_string()))?;
+        file.set_len(0)
+            .and_then(|_| file.seek(SeekFrom::Start(0)))
+            .and_then(|_| file.write_all(&json))
+            .and_then(|_| file.sync_all())
+            .map_err(|e| CoreError::io(&path, e))?;
+        Ok(WorkspaceLock { _file: file })
+    }
+
+    /// The instance recorded in the lock file, if the lock is currently held.
+    pub fn lock_holder(&self) -> Option<LockInfo> {
+        let path = self.lock_path();
+        let mut file = File::open(&path).ok()?;
+        if file.try_lock_shared().is_ok() {
+            // Nobody holds it; the file is left over from an earlier run.
+            return None;
+        }
+        let mut json = String::new();
+        file.read_to_string(&mut json).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Removes temporary files left behind by writes that were interrupted.
+    /// Only call this while holding the workspace lock, or it may remove a
+    /// file another instance is about to rename into place.
+    pub fn remove_stale_temp_files(&self) -> Result<usize> {
+        let files = self.temp_files()?;
+        for path in &files {
+            fs::remove_file(path).map_err(|e| CoreError::io(path, e))?;
+        }
+        Ok(files.len())
+    }
+
+    /// Temporary files left behind by interrupted writes.
+    pub(crate) fn temp_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for (dir, recursive) in [
+            (self.base_path().to_path_buf(), false),
+            (self.backups_dir(), false),
+            (self.scratches_dir(), true),
+            (self.projects_dir(), true),
+            (self.templates_dir(), true),
+            (self.trash_dir(), true),
+            (self.journal_dir(), false),
+            (self.history_dir(), true),
+            (self.blueprints_dir(), false),
+            (self.quarantine_dir(), false),
+        ] {
+            collect_temp_files(&dir, recursive, &mut files)?;
+        }
+        Ok(files)
+    }
+}
+
+fn collect_temp_files(
+    dir: &std::path::Path,
+    recursive: bool,
+    out: &mut Vec<PathBuf>,
+) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir).map_err(|e| CoreError::io(dir, e))? {
+        let entry = entry.map_err(|e| CoreError::io(dir, e))?;
+        let path = entry.path();
+        let file_type = entry.file_type().map_err(|e| CoreError::io(&path, e))?;
+        if file_type.is_dir() && recursive {
+            collect_temp_files(&path, true, out)?;
+        } else if file_type.is_file() && path.extension().and_then(|s| s.to_str()) == Some("tmp") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_second_lock_is_refused_until_released() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Workspace::new(temp_dir.path());
+        workspace.ensure_dirs().unwrap();
+
+        let lock = workspace.lock().unwrap();
+        assert_eq!(workspace.lock_holder().unwrap().pid, std::process::id());
+        assert!(matches!(workspace.lock(), Err(CoreError::Locked(_))));
+
+        drop(lock);
+        assert!(workspace.lock_holder().is_none());
+        assert!(workspace.lock().is_ok());
+    }
+
+    #[test]
+    fn test_remove_stale_temp_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Workspace::new(temp_dir.path());
+        workspace.ensure_dirs().unwrap();
+        let project_dir = workspace.projects_dir().join("p");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(workspace.scratches_dir().join(".a.json.1.tmp"), "").unwrap();
+        fs::write(project_dir.join(".project.json.2.tmp"), "").unwrap();
+        fs::write(workspace.scratches_dir().join("a.json"), "{}").unwrap();
+
+        assert_eq!(workspace.remove_stale_temp_files().unwrap(), 2);
+        assert!(workspace.scratches_dir().join("a.json").exists());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{read_json, write_atomic, Workspace};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tarsius_core::*;
+use uuid::Uuid;
+
+/// A file that could not be read and was moved out of the way.
+///
+/// Each issue lives under `quarantine/` as `<issue id>.json`, next to a
+/// `<issue id>/` directory holding the file (or, for projects, the whole
+/// project directory) as it was found.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceIssue {
+    pub id: String,
+    pub kind: EntityKind,
+    /// Where the item lived, relative to the workspace.
+    pub original_path: String,
+    /// Where it is now, relative to the workspace.
+    pub quarantined_path: String,
+    pub message: String,
+    /// 1-based position of the parse error, when known.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl Workspace {
+    pub fn quarantine_dir(&self) -> PathBuf {
+        self.base_path().join("quarantine")
+    }
+
+    /// Moves `path` (a file or directory inside the workspace) to the
+    /// quarantine and records why.
+    pub fn quarantine(
+        &self,
+        kind: EntityKind,
+        path: &Path,
+        error: &CoreError,
+    ) -> Result<WorkspaceIssue> {
+        let path = self.contain(path)?;
+        let base = self
+            .base_path()
+            .canonicalize()
+            .map_err(|e| CoreError::io(self.base_path(), e))?;
+        let relative = |p: &Path| {
+            p.strip_prefix(&base)
+                .unwrap_or(p)
+                .to_string_lossy()
+                .replace('\\', "/")
+        };
+        let id = Uuid::new_v4().to_string();
+        let dir = self.quarantine_dir().join(&id);
+        fs::create_dir_all(&dir).map_err(|e| CoreError::io(&dir, e))?;
+        let target = dir.join(path.file_name().unwrap_or_default());
+        fs::rename(&path, &target).map_err(|e| CoreError::io(&path, e))?;
+
+        let (line, column) = match error {
+            CoreError::Parse { line, column, .. } if *line > 0 => (Some(*line), Some(*column)),
+            _ => (None, None),
+        };
+        let issue = WorkspaceIssue {
+            id,
+            kind,
+            original_path: relative(&path),
+            quarantined_path: relative(&self.contain(&target)?),
+            message: error.to_string(),
+            line,
+            column,
+            detected_at: Utc::now(),
+        };
+        let json =
+            serde_json::to_string_pretty(&issue).map_err(|e| CoreError::Storage(e.to_string()))?;
+        write_atomic(self.issue_path(&issue.id)?, json)?;
+        Ok(issue)
+    }
+
+    /// Quarantined items, oldest first.
+    pub fn workspace_issues(&self) -> Result<Vec<WorkspaceIssue>> {
+        let dir = self.quarantine_dir();
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut issues = Vec::new();
+        for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+            let entry = entry.map_err(|e| CoreError::io(&dir, e))?;
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) == Some("json") {
+                issues.push(read_json::<WorkspaceIssue>(&path)?);
+            }
+        }
+        issues.sort_by_key(|issue| issue.detected_at);
+        Ok(issues)
+    }
+
+    /// Moves a repaired item back to where it was found. Fails, leaving it
+    /// quarantined, if it still does not parse or its place has been taken.
+    pub fn restore_quarantined(&self, issue_id: &str) -> Result<WorkspaceIssue> {
+        let issue_path = self.issue_path(issue_id)?;
+        if !issue_path.exists() {
+            return Err(CoreError::not_found(EntityKind::QuarantinedItem, issue_id));
+        }
+        let issue: WorkspaceIssue = read_json(&issue_path)?;
+        let quarantined = self.contain(Path::new(&issue.quarantined_path))?;
+        let original = self.contain(Path::new(&issue.original_path))?;
+        check_parses(issue.kind, &quarantined)?;
+        if original.exists() {
+            return Err(CoreError::Conflict(format!(
+                "{} already exists",
+                issue.original_path
+            )));
+        }
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent).map_err(|e| CoreError::io(parent, e))?;
+        }
+        fs::rename(&quarantined, &original).map_err(|e| CoreError::io(&quarantined, e))?;
+        let dir = self.quarantine_dir().join(issue_id);
+        fs::remove_dir_all(&dir).map_err(|e| CoreError::io(&dir, e))?;
+        fs::remove_file(&issue_path).map_err(|e| CoreError::io(&issue_path, e))?;
+        Ok(issue)
+    }
+
+    fn issue_path(&self, issue_id: &str) -> Result<PathBuf> {
+        validate_id("id", issue_id)?;
+        self.contain(&self.quarantine_dir().join(format!("{}.json", issue_id)))
+    }
+}
+
+/// Reads `path` as the JSON of a `kind` item, moving it to the quarantine if
+/// it does not parse. `quarantine_path` is what gets moved, which for
+/// projects is the whole project directory. Returns `None` for quarantined
+/// items; other errors are passed on. A read-only workspace is left as it
+/// is and the item is only skipped.
+pub(crate) fn read_or_quarantine<T: serde::de::DeserializeOwned>(
+    workspace: &Workspace,
+    kind: EntityKind,
+    path: &Path,
+    quarantine_path: &Path,
+) -> Result<Option<T>> {
+    match read_json(path) {
+        Ok(value) => Ok(Some(value)),
+        Err(e @ CoreError::Parse { .. }) => {
+            if !workspace.is_read_only() {
+                workspace.quarantine(kind, quarantine_path, &e)?;
+            }
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn check_parses(kind: EntityKind, path: &Path) -> Result<()> {
+    match kind {
+        EntityKind::Scratch => read_json::<Scratch>(path).map(|_| ()),
+        EntityKind::Project => read_json::<Project>(&path.join("project.json")).map(|_| ()),
+        EntityKind::Template => read_json::<Template>(path).map(|_| ()),
+        EntityKind::TrashEntry => read_json::<TrashEntry>(path).map(|_| ()),
+        EntityKind::JournalEntry => read_json::<JournalEntry>(path).map(|_| ()),
+        EntityKind::Release => read_json::<Release>(&path.join("release.json")).map(|_| ()),
+        EntityKind::ScratchVersion => read_json::<ScratchVersion>(path).map(|_| ()),
+        EntityKind::Blueprint => read_json::<Blueprint>(path).map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FilesystemProjectRepository, FilesystemScratchRepository};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_corrupt_files_are_quarantined_and_restored() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path()));
+        workspace.ensure_dirs().unwrap();
+        let scratches = FilesystemScratchRepository::new(workspace.clone());
+        let projects = FilesystemProjectRepository::new(workspace.clone());
+
+        let good = Scratch {
+            id: "good".to_string(),
+            title: "Good".to_string(),
+            content: String::new(),
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            tags: vec![],
+            source: None,
+        };
+        ScratchRepository::save(&scratches, &good).unwrap();
+        let bad_scratch = workspace.scratches_dir().join("bad.json");
+        fs::write(&bad_scratch, "{\n  \"id\": \"bad\",\n  oops\n}").unwrap();
+        let bad_project = workspace.projects_dir().join("broken");
+        fs::create_dir_all(&bad_project).unwrap();
+        fs::write(bad_project.join("project.json"), "not json").unwrap();
+        fs::write(bad_project.join("figure.png"), [1u8, 2]).unwrap();
+
+        let listed = ScratchRepository::list(&scratches).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, "good");
+        assert!(ProjectRepository::list(&projects).unwrap().is_empty());
+        assert!(!bad_scratch.exists());
+        assert!(!bad_project.exists());
+
+        let issues = workspace.workspace_issues().unwrap();
+        assert_eq!(issues.len(), 2);
+        let scratch_issue = issues
+            .iter()
+            .find(|issue| issue.kind == EntityKind::Scratch)
+            .unwrap();
+        assert_eq!(scratch_issue.original_path, "scratches/bad.json");
+        assert_eq!(scratch_issue.line, Some(3));
+        let project_issue = issues
+            .iter()
+            .find(|issue| issue.kind == EntityKind::Project)
+            .unwrap();
+        assert!(workspace
+            .base_path()
+            .join(&project_issue.quarantined_path)
+            .join("figure.png")
+            .exists());
+
+        // Still broken: stays in quarantine.
+        assert!(workspace.restore_quarantined(&scratch_issue.id).is_err());
+        let repaired = workspace.base_path().join(&scratch_issue.quarantined_path);
+        fs::write(
+            &repaired,
+            serde_json::to_string(&Scratch {
+                id: "bad".to_string(),
+                ..good
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        workspace.restore_quarantined(&scratch_issue.id).unwrap();
+        assert_eq!(ScratchRepository::list(&scratches).unwrap().len(), 2);
+        assert_eq!(workspace.workspace_issues().unwrap().len(), 1);
+    }
+}
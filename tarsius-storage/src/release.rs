@@ -0,0 +1,179 @@
+use crate::{read_json, read_or_quarantine, write_atomic, write_synced, Workspace};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tarsius_core::*;
+
+const RELEASE_FILE: &str = "release.json";
+const TEX_FILE: &str = "main.tex";
+const BIB_FILE: &str = "references.bib";
+const PDF_FILE: &str = "main.pdf";
+
+/// Keeps each release in `projects/<id>/releases/<release>/`: the frozen
+/// project and scratches in `release.json`, next to the build output.
+///
+/// `release.json` is written last, so a release interrupted halfway is never
+/// listed.
+pub struct FilesystemReleaseRepository {
+    workspace: Arc<Workspace>,
+}
+
+impl FilesystemReleaseRepository {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    fn releases_dir(&self, project_id: &str) -> Result<PathBuf> {
+        validate_id("project_id", project_id)?;
+        self.workspace
+            .contain(&self.workspace.releases_dir(project_id))
+    }
+
+    fn release_dir(&self, project_id: &str, release_id: &str) -> Result<PathBuf> {
+        validate_id("release_id", release_id)?;
+        Ok(self.releases_dir(project_id)?.join(release_id))
+    }
+
+    fn existing_release_dir(&self, project_id: &str, release_id: &str) -> Result<PathBuf> {
+        let dir = self.release_dir(project_id, release_id)?;
+        if !dir.join(RELEASE_FILE).exists() {
+            return Err(CoreError::not_found(
+                EntityKind::Release,
+                format!("{}/{}", project_id, release_id),
+            ));
+        }
+        Ok(dir)
+    }
+}
+
+impl ReleaseRepository for FilesystemReleaseRepository {
+    fn create(&self, release: &Release, output: &ReleaseOutput) -> Result<()> {
+        let dir = self.release_dir(&release.project_id, &release.id)?;
+        if dir.exists() {
+            return Err(CoreError::Conflict(format!(
+                "Release {} already exists",
+                release.id
+            )));
+        }
+        fs::create_dir_all(&dir).map_err(|e| CoreError::io(&dir, e))?;
+        write_synced(&dir.join(TEX_FILE), output.tex.as_bytes())?;
+        if let Some(bib) = &output.bib {
+            write_synced(&dir.join(BIB_FILE), bib.as_bytes())?;
+        }
+        if let Some(pdf) = &output.pdf {
+            write_synced(&dir.join(PDF_FILE), pdf)?;
+        }
+        let json =
+            serde_json::to_string_pretty(release).map_err(|e| CoreError::Storage(e.to_string()))?;
+        write_atomic(dir.join(RELEASE_FILE), json)
+    }
+
+    fn list(&self, project_id: &str) -> Result<Vec<Release>> {
+        let dir = self.releases_dir(project_id)?;
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut releases = Vec::new();
+        for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+            let entry = entry.map_err(|e| CoreError::io(&dir, e))?;
+            let path = entry.path().join(RELEASE_FILE);
+            if path.exists() {
+                releases.extend(read_or_quarantine(
+                    &self.workspace,
+                    EntityKind::Release,
+                    &path,
+                    &entry.path(),
+                )?);
+            }
+        }
+        releases.sort_by_key(|r: &Release| r.created_at);
+        Ok(releases)
+    }
+
+    fn load(&self, project_id: &str, release_id: &str) -> Result<Release> {
+        let dir = self.existing_release_dir(project_id, release_id)?;
+        read_json(&dir.join(RELEASE_FILE))
+    }
+
+    fn output(&self, project_id: &str, release_id: &str) -> Result<ReleaseOutput> {
+        let dir = self.existing_release_dir(project_id, release_id)?;
+        let read = |name: &str| -> Result<Option<Vec<u8>>> {
+            let path = dir.join(name);
+            match fs::read(&path) {
+                Ok(content) => Ok(Some(content)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(CoreError::io(&path, e)),
+            }
+        };
+        let text = |content: Vec<u8>| String::from_utf8_lossy(&content).into_owned();
+        Ok(ReleaseOutput {
+            tex: read(TEX_FILE)?.map(text).unwrap_or_default(),
+            bib: read(BIB_FILE)?.map(text),
+            pdf: read(PDF_FILE)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tarsius_core::ProjectRepository;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_releases_are_stored_with_their_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path()));
+        workspace.ensure_dirs().unwrap();
+        let project = Project {
+            id: "p1".to_string(),
+            title: "Paper".to_string(),
+            outline: OutlineNode {
+                id: "root".to_string(),
+                title: "Root".to_string(),
+                content: None,
+                children: vec![],
+                scratches: vec![],
+            },
+            settings: ProjectSettings {
+                template_id: "none".to_string(),
+                output_dir: "out".to_string(),
+            },
+            created_at: chrono::Utc::now(),
+            modified_at: chrono::Utc::now(),
+            archived: false,
+        };
+        crate::FilesystemProjectRepository::new(workspace.clone())
+            .save(&project)
+            .unwrap();
+        let releases = FilesystemReleaseRepository::new(workspace.clone());
+        assert!(releases.list("p1").unwrap().is_empty());
+
+        let release = Release::snapshot(&project, &[], None, "Draft".to_string());
+        let output = ReleaseOutput {
+            tex: "\\section{Root}".to_string(),
+            bib: None,
+            pdf: Some(vec![0x25, 0x50, 0x44, 0x46]),
+        };
+        releases.create(&release, &output).unwrap();
+        assert!(matches!(
+            releases.create(&release, &output),
+            Err(CoreError::Conflict(_))
+        ));
+
+        let listed = releases.list("p1").unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "Draft");
+        assert_eq!(releases.output("p1", &release.id).unwrap(), output);
+        assert!(matches!(
+            releases.load("p1", "missing"),
+            Err(CoreError::NotFound {
+                kind: EntityKind::Release,
+                ..
+            })
+        ));
+        // Releases live in the project directory but are not projects.
+        let projects = crate::FilesystemProjectRepository::new(workspace);
+        assert_eq!(projects.list().unwrap().len(), 1);
+    }
+}
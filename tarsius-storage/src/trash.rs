@@ -0,0 +1,296 @@
+use crate::{read_json, read_or_quarantine, write_atomic, Workspace};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tarsius_core::*;
+
+/// Keeps deleted scratches and projects under `trash/` until they are
+/// restored or purged.
+///
+/// Each trashed item is moved as-is (a scratch file or a whole project
+/// directory) next to a `<id>.meta.json` file holding its `TrashEntry`.
+pub struct FilesystemTrashRepository {
+    workspace: Arc<Workspace>,
+}
+
+impl FilesystemTrashRepository {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    fn kind_dir(&self, kind: TrashKind) -> PathBuf {
+        match kind {
+            TrashKind::Scratch => self.workspace.trash_dir().join("scratches"),
+            TrashKind::Project => self.workspace.trash_dir().join("projects"),
+        }
+    }
+
+    fn meta_path(&self, kind: TrashKind, id: &str) -> Result<PathBuf> {
+        validate_id("id", id)?;
+        self.workspace
+            .contain(&self.kind_dir(kind).join(format!("{}.meta.json", id)))
+    }
+
+    fn live_path(&self, kind: TrashKind, id: &str) -> Result<PathBuf> {
+        validate_id("id", id)?;
+        let path = match kind {
+            TrashKind::Scratch => self.workspace.scratches_dir().join(format!("{}.json", id)),
+            TrashKind::Project => self.workspace.projects_dir().join(id),
+        };
+        self.workspace.contain(&path)
+    }
+
+    fn trashed_path(&self, kind: TrashKind, id: &str) -> Result<PathBuf> {
+        validate_id("id", id)?;
+        let path = match kind {
+            TrashKind::Scratch => self.kind_dir(kind).join(format!("{}.json", id)),
+            TrashKind::Project => self.kind_dir(kind).join(id),
+        };
+        self.workspace.contain(&path)
+    }
+
+    fn load_entry(&self, kind: TrashKind, id: &str) -> Result<TrashEntry> {
+        let path = self.meta_path(kind, id)?;
+        if !path.exists() {
+            return Err(CoreError::not_found(EntityKind::TrashEntry, id));
+        }
+        read_json(&path)
+    }
+}
+
+impl TrashRepository for FilesystemTrashRepository {
+    fn trash(&self, entry: &TrashEntry) -> Result<()> {
+        let live = self.live_path(entry.kind, &entry.id)?;
+        if !live.exists() {
+            let kind = match entry.kind {
+                TrashKind::Scratch => EntityKind::Scratch,
+                TrashKind::Project => EntityKind::Project,
+            };
+            return Err(CoreError::not_found(kind, entry.id.clone()));
+        }
+        let dir = self.kind_dir(entry.kind);
+        fs::create_dir_all(&dir).map_err(|e| CoreError::io(&dir, e))?;
+
+        // An older copy with the same id is superseded by this deletion.
+        self.purge(entry.kind, &entry.id)?;
+
+        let json =
+            serde_json::to_string_pretty(entry).map_err(|e| CoreError::Storage(e.to_string()))?;
+        let meta = self.meta_path(entry.kind, &entry.id)?;
+        write_atomic(&meta, json)?;
+        let trashed = self.trashed_path(entry.kind, &entry.id)?;
+        if let Err(e) = fs::rename(&live, trashed) {
+            // Without this the entry would be listed for an item never trashed.
+            let _ = fs::remove_file(&meta);
+            return Err(CoreError::io(&live, e));
+        }
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<TrashEntry>> {
+        let mut entries = Vec::new();
+        for kind in [TrashKind::Scratch, TrashKind::Project] {
+            let dir = self.kind_dir(kind);
+            if !dir.exists() {
+                continue;
+            }
+            for entry in fs::read_dir(&dir).map_err(|e| CoreError::io(&dir, e))? {
+                let entry = entry.map_err(|e| CoreError::io(&dir, e))?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                if let Some(id) = name.strip_suffix(".meta.json") {
+                    entries.extend(read_or_quarantine(
+                        &self.workspace,
+                        EntityKind::TrashEntry,
+                        &self.meta_path(kind, id)?,
+                        &entry.path(),
+                    )?);
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    fn restore(&self, kind: TrashKind, id: &str) -> Result<TrashEntry> {
+        let entry = self.load_entry(kind, id)?;
+        let live = self.live_path(kind, id)?;
+        if live.exists() {
+            return Err(CoreError::Conflict(format!(
+                "Cannot restore {:?} {}: an item with the same id exists",
+                kind, id
+            )));
+        }
+        let trashed = self.trashed_path(kind, id)?;
+        if trashed.exists() {
+            fs::rename(&trashed, live).map_err(|e| CoreError::io(&trashed, e))?;
+        }
+        let meta = self.meta_path(kind, id)?;
+        fs::remove_file(&meta).map_err(|e| CoreError::io(&meta, e))?;
+        Ok(entry)
+    }
+
+    fn purge(&self, kind: TrashKind, id: &str) -> Result<()> {
+        let trashed = self.trashed_path(kind, id)?;
+        if trashed.is_dir() {
+            fs::remove_dir_all(&trashed).map_err(|e| CoreError::io(&trashed, e))?;
+        } else if trashed.exists() {
+            fs::remove_file(&trashed).map_err(|e| CoreError::io(&trashed, e))?;
+        }
+        let meta = self.meta_path(kind, id)?;
+        if meta.exists() {
+            fs::remove_file(&meta).map_err(|e| CoreError::io(&meta, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FilesystemProjectRepository, FilesystemScratchRepository};
+    use tempfile::TempDir;
+
+    fn scratch(id: &str) -> Scratch {
+        Scratch {
+            id: id.to_string(),
+            title: "Trashed".to_string(),
+            content: "Body".to_string(),
+            created_at: chrono::Utc::now(),
+            modified_at: chrono::Utc::now(),
+            tags: vec![],
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_scratch_trash_and_restore() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path()));
+        workspace.ensure_dirs().unwrap();
+        let manager = ScratchManager::new(Box::new(FilesystemScratchRepository::new(
+            workspace.clone(),
+        )))
+        .with_trash(Box::new(FilesystemTrashRepository::new(workspace.clone())));
+        let trash = FilesystemTrashRepository::new(workspace.clone());
+
+        let repo = FilesystemScratchRepository::new(workspace.clone());
+        ScratchRepository::save(&repo, &scratch("s1")).unwrap();
+
+        manager.delete("s1", DeletePolicy::Block).unwrap();
+        assert!(manager.load("s1").is_err());
+        let entries = trash.list().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind, TrashKind::Scratch);
+        assert_eq!(entries[0].title, "Trashed");
+
+        trash.restore(TrashKind::Scratch, "s1").unwrap();
+        assert_eq!(manager.load("s1").unwrap().content, "Body");
+        assert!(trash.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unlinked_scratch_links_come_back_on_restore() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path()));
+        workspace.ensure_dirs().unwrap();
+        let scratches = ScratchManager::new(Box::new(FilesystemScratchRepository::new(
+            workspace.clone(),
+        )))
+        .with_trash(Box::new(FilesystemTrashRepository::new(workspace.clone())))
+        .with_projects(Box::new(FilesystemProjectRepository::new(
+            workspace.clone(),
+        )));
+        let projects = ProjectManager::new(Box::new(FilesystemProjectRepository::new(
+            workspace.clone(),
+        )))
+        .with_scratches(Box::new(FilesystemScratchRepository::new(
+            workspace.clone(),
+        )));
+        let trash_manager = TrashManager::new(
+            Box::new(FilesystemTrashRepository::new(workspace.clone())),
+            Box::new(FilesystemProjectRepository::new(workspace.clone())),
+            chrono::Duration::days(30),
+        );
+
+        let scratch = scratches
+            .create("Note".to_string(), "Body".to_string(), vec![], None)
+            .unwrap();
+        let mut project = projects
+            .create("Paper".to_string(), "t".to_string(), "out".to_string())
+            .unwrap();
+        project.outline.scratches.push(ScratchLink {
+            scratch_id: scratch.id.clone(),
+            mode: IntegrationMode::Link,
+            insertion: InsertionFlags {
+                body: false,
+                footnote: true,
+                reference: false,
+                appendix: false,
+            },
+            included: None,
+        });
+        projects.save(&project).unwrap();
+
+        assert!(matches!(
+            scratches.delete(&scratch.id, DeletePolicy::Block),
+            Err(CoreError::Conflict(_))
+        ));
+
+        // When the scratch cannot be trashed, the project keeps its link.
+        let blocked = workspace.trash_dir().join("scratches");
+        fs::write(&blocked, "").unwrap();
+        assert!(scratches.delete(&scratch.id, DeletePolicy::Unlink).is_err());
+        let kept = projects.load(&project.id).unwrap();
+        assert_eq!(kept.outline.scratches.len(), 1);
+        fs::remove_file(&blocked).unwrap();
+
+        let report = scratches.delete(&scratch.id, DeletePolicy::Unlink).unwrap();
+        assert_eq!(report.references.len(), 1);
+        let (loaded, dangling) = projects.load_checked(&project.id).unwrap();
+        assert!(loaded.outline.scratches.is_empty());
+        assert!(dangling.is_empty());
+
+        trash_manager
+            .restore(TrashKind::Scratch, &scratch.id)
+            .unwrap();
+        let restored = projects.load(&project.id).unwrap();
+        assert_eq!(restored.outline.scratches.len(), 1);
+        assert_eq!(restored.outline.scratches[0].scratch_id, scratch.id);
+    }
+
+    #[test]
+    fn test_project_trash_and_purge() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path()));
+        workspace.ensure_dirs().unwrap();
+        let manager = ProjectManager::new(Box::new(FilesystemProjectRepository::new(
+            workspace.clone(),
+        )))
+        .with_trash(Box::new(FilesystemTrashRepository::new(workspace.clone())));
+        let trash = FilesystemTrashRepository::new(workspace.clone());
+
+        let project = manager
+            .create("Draft".to_string(), "t".to_string(), "out".to_string())
+            .unwrap();
+        fs::write(
+            workspace.projects_dir().join(&project.id).join("main.tex"),
+            "x",
+        )
+        .unwrap();
+
+        manager.delete(&project.id).unwrap();
+        assert!(manager.list().unwrap().is_empty());
+        assert!(trash
+            .trashed_path(TrashKind::Project, &project.id)
+            .unwrap()
+            .join("main.tex")
+            .exists());
+
+        trash.purge(TrashKind::Project, &project.id).unwrap();
+        assert!(trash.list().unwrap().is_empty());
+        assert!(!trash
+            .trashed_path(TrashKind::Project, &project.id)
+            .unwrap()
+            .exists());
+        assert!(trash.restore(TrashKind::Project, &project.id).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::Workspace;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+use tarsius_core::EntityKind;
+
+/// A scratch, project or template whose file was added, changed or removed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WorkspaceChange {
+    pub kind: EntityKind,
+    pub id: String,
+}
+
+type Snapshot = HashMap<(EntityKind, String), (Option<SystemTime>, u64)>;
+
+/// Watches the workspace for changed files by polling their modification
+/// times, so that it works the same on every platform and file system.
+///
+/// Changes made by this process are reported too. The watcher stops when
+/// dropped.
+pub struct WorkspaceWatcher {
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl WorkspaceWatcher {
+    /// Calls `on_change` from a background thread with the changes found in
+    /// each round, every `interval`.
+    pub fn start(
+        workspace: Arc<Workspace>,
+        interval: Duration,
+        on_change: impl Fn(Vec<WorkspaceChange>) + Send + 'static,
+    ) -> Self {
+        let (stop, stopped) = channel::<()>();
+        let mut last = snapshot(&workspace);
+        let handle = thread::spawn(move || {
+            // Dropping the sender disconnects the channel and ends the loop.
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let current = snapshot(&workspace);
+                let changes = diff(&last, &current);
+                last = current;
+                if !changes.is_empty() {
+                    on_change(changes);
+                }
+            }
+        });
+        Self {
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for WorkspaceWatcher {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn snapshot(workspace: &Workspace) -> Snapshot {
+    let mut files = Snapshot::new();
+    for (kind, dir) in [
+        (EntityKind::Scratch, workspace.scratches_dir()),
+        (EntityKind::Template, workspace.templates_dir()),
+    ] {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+                files.insert((kind, id.to_string()), stamp(&path));
+            }
+        }
+    }
+    if let Ok(entries) = fs::read_dir(workspace.projects_dir()) {
+        for entry in entries.flatten() {
+            let path = entry.path().join("project.json");
+            if path.exists() {
+                let id = entry.file_name().to_string_lossy().to_string();
+                files.insert((EntityKind::Project, id), stamp(&path));
+            }
+        }
+    }
+    files
+}
+
+fn stamp(path: &Path) -> (Option<SystemTime>, u64) {
+    match fs::metadata(path) {
+        Ok(metadata) => (metadata.modified().ok(), metadata.len()),
+        Err(_) => (None, 0),
+    }
+}
+
+fn diff(before: &Snapshot, after: &Snapshot) -> Vec<WorkspaceChange> {
+    let changed = after
+        .iter()
+        .filter(|(key, stamp)| before.get(key) != Some(stamp))
+        .map(|(key, _)| key);
+    let removed = before.keys().filter(|key| !after.contains_key(key));
+    changed
+        .chain(removed)
+        .map(|(kind, id)| WorkspaceChange {
+            kind: *kind,
+            id: id.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reports_added_and_removed_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Arc::new(Workspace::new(temp_dir.path()));
+        workspace.ensure_dirs().unwrap();
+        let (tx, rx) = channel();
+        let _watcher =
+            WorkspaceWatcher::start(workspace.clone(), Duration::from_millis(10), move |c| {
+                let _ = tx.send(c);
+            });
+
+        let path = workspace.scratches_dir().join("s1.json");
+        fs::write(&path, "{}").unwrap();
+        let expected = vec![WorkspaceChange {
+            kind: EntityKind::Scratch,
+            id: "s1".to_string(),
+        }];
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), expected);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), expected);
+    }
+}
@@ -0,0 +1,20 @@
+// Opens a scratch or project in a window of its own, or focuses the window
+// already showing it. `kind` is "Scratch", "Project", "Outline" or "Preview".
+export async function openWindow(kind, targetId) {
+  if (!window.__TAURI__) return;
+  try {
+    const { invoke } = await import("@tauri-apps/api/tauri");
+    await invoke("open_window", { kind, targetId });
+  } catch (e) {
+    console.error("Error opening window:", e);
+  }
+}
+
+// What this window shows, as recorded by the backend; null for the main
+// window.
+export async function currentWindow() {
+  const label = new URLSearchParams(window.location.search).get("window");
+  if (!label || !window.__TAURI__) return null;
+  const { invoke } = await import("@tauri-apps/api/tauri");
+  return await invoke("window_state", { label });
+}
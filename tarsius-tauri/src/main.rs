@@ -4,14 +4,19 @@
 )]
 
 use std::sync::Arc;
-use tarsius_core::{ProjectManager, ProjectRepository, ScratchManager, ScratchRepository};
-use tarsius_storage::{FilesystemProjectRepository, FilesystemScratchRepository, Workspace};
-use tauri::State;
+use tarsius_app::{App, UsageFilter};
+use tarsius_core::{
+    Blueprint, CoreError, DeletePolicy, DriftResolution, JournalKind, ListQuery, OutlineChange,
+    OutlineMerge, Page, Release, ReleaseDiff, ReleaseSummary, Result, ScratchVersion, TrashKind,
+    VersionDiff, WindowBounds, WindowKind, WindowState, MAIN_WINDOW,
+};
+use tarsius_storage::CollisionPolicy;
+use tauri::{Manager, State, WindowEvent};
 
-struct AppState {
-    scratch_manager: Arc<ScratchManager>,
-    project_manager: Arc<ProjectManager>,
-}
+type AppState = Arc<App>;
+
+/// Name of the Tauri event that carries every `WorkspaceEvent` to the windows.
+const WORKSPACE_EVENT: &str = "workspace-event";
 
 #[derive(serde::Deserialize)]
 struct CreateScratchRequest {
@@ -30,6 +35,12 @@ struct UpdateScratchRequest {
     source: Option<Option<String>>,
 }
 
+#[derive(serde::Serialize)]
+struct LoadedProjectDto {
+    project: tarsius_core::ProjectDto,
+    warnings: Vec<tarsius_core::DanglingLink>,
+}
+
 #[derive(serde::Deserialize)]
 struct CreateProjectRequest {
     title: String,
@@ -39,147 +50,819 @@ struct CreateProjectRequest {
 
 fn main() {
     let workspace_path = std::env::current_dir().unwrap().join("workspace");
-    let workspace = Arc::new(Workspace::new(workspace_path));
-    workspace.ensure_dirs().unwrap();
-
-    let scratch_repo: Box<dyn ScratchRepository> =
-        Box::new(FilesystemScratchRepository::new(workspace.clone()));
-    let project_repo: Box<dyn ProjectRepository> =
-        Box::new(FilesystemProjectRepository::new(workspace.clone()));
+    // A second instance on the same workspace gets a read-only view; the
+    // frontend learns about it from `workspace_status`.
+    let app = match App::open(&workspace_path) {
+        Err(CoreError::Locked(reason)) => {
+            eprintln!("Workspace is locked ({}); opening read-only", reason);
+            App::open_read_only(&workspace_path)
+        }
+        app => app,
+    };
+    let app = match app {
+        Ok(app) => Arc::new(app),
+        Err(e) => {
+            eprintln!(
+                "Failed to open workspace {}: {}",
+                workspace_path.display(),
+                e
+            );
+            std::process::exit(1);
+        }
+    };
+    if !app.status().read_only {
+        if let Err(e) = app.purge_expired_trash() {
+            eprintln!("Failed to purge expired trash: {}", e);
+        }
+    }
 
-    let scratch_manager = Arc::new(ScratchManager::new(scratch_repo));
-    let project_manager = Arc::new(ProjectManager::new(project_repo));
+    let backup_app = app.clone();
+    std::thread::spawn(move || loop {
+        if let Err(e) = backup_app.run_scheduled_backup() {
+            eprintln!("Automatic backup failed: {}", e);
+        }
+        std::thread::sleep(std::time::Duration::from_secs(60 * 60));
+    });
 
-    let state = AppState {
-        scratch_manager,
-        project_manager,
+    let server = if app.settings().server.enabled && !app.status().read_only {
+        match tarsius_server::start(app.clone(), &app.settings().server) {
+            Ok(server) => Some(server),
+            Err(e) => {
+                eprintln!("Failed to start local API: {}", e);
+                None
+            }
+        }
+    } else {
+        None
     };
 
+    let events = app.subscribe();
+
     tauri::Builder::default()
-        .manage(state)
+        .manage(app)
+        .setup(move |tauri_app| {
+            // Every window gets every change, so views stay in step without
+            // polling the list commands.
+            let handle = tauri_app.handle();
+            restore_windows(&handle);
+            std::thread::spawn(move || {
+                for event in events {
+                    if let Err(e) = handle.emit_all(WORKSPACE_EVENT, &event) {
+                        eprintln!("Failed to forward workspace event: {}", e);
+                    }
+                }
+            });
+            Ok(())
+        })
+        .on_window_event(|event| {
+            let window = event.window();
+            let state = window.state::<AppState>();
+            match event.event() {
+                WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+                    if let Ok(bounds) = window_bounds(window) {
+                        state.move_window(window.label(), bounds);
+                    }
+                }
+                WindowEvent::Destroyed => {
+                    if let Err(e) = state.close_window(window.label()) {
+                        eprintln!("Failed to save window layout: {}", e);
+                    }
+                    // The other windows cannot live without the main one.
+                    if window.label() == MAIN_WINDOW {
+                        window.app_handle().exit(0);
+                    }
+                }
+                _ => {}
+            }
+        })
         .invoke_handler(tauri::generate_handler![
+            workspace_status,
+            open_window,
+            window_state,
+            list_windows,
             create_scratch,
             update_scratch,
             load_scratch,
             list_scratches,
+            list_scratch_summaries,
             delete_scratch,
+            scratch_references,
+            scratch_usages,
+            list_scratch_versions,
+            diff_scratch_versions,
+            restore_scratch_version,
             create_project,
             load_project,
             save_project,
-            list_projects
+            list_projects,
+            list_project_summaries,
+            project_drift,
+            drift_view,
+            resolve_drift,
+            build_project,
+            project_pdf,
+            create_release,
+            list_releases,
+            load_release,
+            diff_releases,
+            fork_release,
+            outline_changes,
+            preview_merge,
+            merge_projects,
+            duplicate_project,
+            rename_project,
+            set_project_archived,
+            delete_project,
+            list_templates,
+            list_blueprints,
+            create_project_from_blueprint,
+            save_project_as_blueprint,
+            delete_blueprint,
+            search,
+            list_trash,
+            restore_from_trash,
+            purge_from_trash,
+            workspace_issues,
+            restore_quarantined,
+            check_workspace,
+            repair_workspace,
+            cache_metrics,
+            export_workspace,
+            import_workspace,
+            list_backups,
+            journal_scratch,
+            journal_project,
+            pending_recoveries,
+            recover_draft,
+            discard_draft
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |_, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Some(server) = &server {
+                    server.shutdown();
+                }
+            }
+        });
+}
+
+/// Reopens the windows saved for the workspace and puts the main window
+/// back where it was.
+fn restore_windows(handle: &tauri::AppHandle) {
+    let state = handle.state::<AppState>();
+    for window in state.restore_windows() {
+        if let Err(e) = build_window(handle, &window) {
+            eprintln!("Failed to restore window {}: {}", window.label, e);
+            let _ = state.close_window(&window.label);
+        }
+    }
+    let bounds = state.window(MAIN_WINDOW).and_then(|w| w.bounds);
+    if let (Some(main), Some(bounds)) = (handle.get_window(MAIN_WINDOW), bounds) {
+        let _ = main.set_position(tauri::LogicalPosition::new(bounds.x, bounds.y));
+        let _ = main.set_size(tauri::LogicalSize::new(bounds.width, bounds.height));
+    }
+}
+
+/// Creates the Tauri window for `window`. The frontend learns what to show
+/// from `window_state`, called with its own label.
+fn build_window(handle: &tauri::AppHandle, window: &WindowState) -> tauri::Result<tauri::Window> {
+    let url = tauri::WindowUrl::App(format!("index.html?window={}", window.label).into());
+    let mut builder = tauri::WindowBuilder::new(handle, window.label.clone(), url)
+        .title(format!("Tarsius – {:?}", window.kind));
+    if let Some(bounds) = window.bounds {
+        builder = builder
+            .position(bounds.x, bounds.y)
+            .inner_size(bounds.width, bounds.height);
+    }
+    builder.build()
+}
+
+fn window_bounds(window: &tauri::Window) -> tauri::Result<WindowBounds> {
+    let scale = window.scale_factor()?;
+    let position = window.outer_position()?.to_logical::<f64>(scale);
+    let size = window.inner_size()?.to_logical::<f64>(scale);
+    Ok(WindowBounds {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    })
 }
 
 #[tauri::command]
-fn create_scratch(
-    state: State<AppState>,
+fn workspace_status(state: State<AppState>) -> tarsius_app::WorkspaceStatus {
+    state.status()
+}
+
+/// Opens `target_id` in a window of its own, or focuses the window already
+/// showing it. `kind` is `"Scratch"`, `"Project"`, `"Outline"` or
+/// `"Preview"`.
+#[tauri::command]
+async fn open_window(
+    handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    kind: String,
+    target_id: String,
+) -> Result<WindowState> {
+    let kind: WindowKind = kind.parse().map_err(|e| CoreError::invalid("kind", e))?;
+    let (window, new) = state
+        .spawn(move |app| app.open_window(kind, Some(target_id)))
+        .await?;
+    if new {
+        if let Err(e) = build_window(&handle, &window) {
+            state.close_window(&window.label)?;
+            return Err(CoreError::Storage(format!("Failed to open window: {}", e)));
+        }
+    } else if let Some(existing) = handle.get_window(&window.label) {
+        let _ = existing.set_focus();
+    }
+    Ok(window)
+}
+
+/// What the window with `label` shows; `None` for a window the backend does
+/// not know, which then behaves as the main window.
+#[tauri::command]
+fn window_state(state: State<AppState>, label: String) -> Option<WindowState> {
+    state.window(&label)
+}
+
+#[tauri::command]
+fn list_windows(state: State<AppState>) -> Vec<WindowState> {
+    state.windows()
+}
+
+#[tauri::command]
+async fn create_scratch(
+    state: State<'_, AppState>,
     request: CreateScratchRequest,
-) -> std::result::Result<tarsius_core::ScratchDto, String> {
+) -> Result<tarsius_core::ScratchDto> {
     let scratch = state
-        .scratch_manager
-        .create(request.title, request.content, request.tags, request.source)
-        .map_err(|e| format!("Failed to create scratch: {}", e))?;
+        .spawn(move |app| {
+            app.create_scratch(request.title, request.content, request.tags, request.source)
+        })
+        .await?;
     Ok(scratch.into())
 }
 
 #[tauri::command]
-fn update_scratch(
-    state: State<AppState>,
+async fn update_scratch(
+    state: State<'_, AppState>,
     request: UpdateScratchRequest,
-) -> std::result::Result<tarsius_core::ScratchDto, String> {
+) -> Result<tarsius_core::ScratchDto> {
     let scratch = state
-        .scratch_manager
-        .update(
-            request.id,
-            request.title,
-            request.content,
-            request.tags,
-            request.source,
-        )
-        .map_err(|e| format!("Failed to update scratch: {}", e))?;
+        .spawn(move |app| {
+            app.update_scratch(
+                request.id,
+                request.title,
+                request.content,
+                request.tags,
+                request.source,
+            )
+        })
+        .await?;
     Ok(scratch.into())
 }
 
 #[tauri::command]
-fn create_project(
-    state: State<AppState>,
+async fn create_project(
+    state: State<'_, AppState>,
     request: CreateProjectRequest,
-) -> std::result::Result<tarsius_core::ProjectDto, String> {
+) -> Result<tarsius_core::ProjectDto> {
     let project = state
-        .project_manager
-        .create(request.title, request.template_id, request.output_dir)
-        .map_err(|e| format!("Failed to create project: {}", e))?;
+        .spawn(move |app| {
+            app.create_project(request.title, request.template_id, request.output_dir)
+        })
+        .await?;
     Ok(project.into())
 }
 
 #[tauri::command]
-fn load_project(
-    state: State<AppState>,
+async fn load_project(state: State<'_, AppState>, id: String) -> Result<LoadedProjectDto> {
+    let loaded = state.spawn(move |app| app.load_project(&id)).await?;
+    Ok(LoadedProjectDto {
+        project: loaded.project.into(),
+        warnings: loaded.warnings,
+    })
+}
+
+#[tauri::command]
+async fn save_project(
+    state: State<'_, AppState>,
+    project_dto: tarsius_core::ProjectDto,
+) -> Result<()> {
+    let project: tarsius_core::Project = project_dto.try_into()?;
+    state.spawn(move |app| app.save_project(&project)).await
+}
+
+/// Lists projects; archived ones only with `includeArchived`.
+#[tauri::command]
+async fn list_projects(
+    state: State<'_, AppState>,
+    include_archived: Option<bool>,
+) -> Result<Vec<tarsius_core::ProjectDto>> {
+    let projects = state
+        .spawn(move |app| app.list_projects(include_archived.unwrap_or(false)))
+        .await?;
+    Ok(projects.into_iter().map(Into::into).collect())
+}
+
+#[tauri::command]
+async fn list_project_summaries(
+    state: State<'_, AppState>,
+    query: Option<ListQuery>,
+) -> Result<Page<tarsius_core::ProjectSummaryDto>> {
+    let page = state
+        .spawn(move |app| app.project_summaries(&query.unwrap_or_default()))
+        .await?;
+    Ok(page.map(Into::into))
+}
+
+#[tauri::command]
+async fn project_drift(
+    state: State<'_, AppState>,
     id: String,
-) -> std::result::Result<tarsius_core::ProjectDto, String> {
+) -> Result<Vec<tarsius_core::DriftedLink>> {
+    state.spawn(move |app| app.project_drift(&id)).await
+}
+
+#[tauri::command]
+async fn drift_view(
+    state: State<'_, AppState>,
+    project_id: String,
+    node_id: String,
+    scratch_id: String,
+) -> Result<tarsius_core::DriftView> {
+    state
+        .spawn(move |app| app.drift_view(&project_id, &node_id, &scratch_id))
+        .await
+}
+
+/// `resolution` is one of `"AcceptUpdate"`, `"KeepLocal"` or `"Merge"`; the
+/// latter takes the merged text in `merged`.
+#[tauri::command]
+async fn resolve_drift(
+    state: State<'_, AppState>,
+    project_id: String,
+    node_id: String,
+    scratch_id: String,
+    resolution: String,
+    merged: Option<String>,
+) -> Result<tarsius_core::ProjectDto> {
+    let resolution = DriftResolution::parse(&resolution, merged)
+        .map_err(|e| CoreError::invalid("resolution", e))?;
     let project = state
-        .project_manager
-        .load(&id)
-        .map_err(|e| format!("Failed to load project: {}", e))?;
+        .spawn(move |app| app.resolve_drift(&project_id, &node_id, &scratch_id, resolution))
+        .await?;
     Ok(project.into())
 }
 
 #[tauri::command]
-fn save_project(
-    state: State<AppState>,
-    project_dto: tarsius_core::ProjectDto,
-) -> std::result::Result<(), String> {
-    let project = project_dto
-        .try_into()
-        .map_err(|e| format!("Invalid project data: {}", e))?;
+async fn build_project(state: State<'_, AppState>, id: String) -> Result<tarsius_app::BuildOutput> {
+    state.spawn(move |app| app.build_project(&id, None)).await
+}
+
+/// The PDF compiled from the project's last build, for the preview window.
+#[tauri::command]
+async fn project_pdf(state: State<'_, AppState>, id: String) -> Result<Option<Vec<u8>>> {
+    state.spawn(move |app| app.project_pdf(&id)).await
+}
+
+#[tauri::command]
+async fn create_release(
+    state: State<'_, AppState>,
+    project_id: String,
+    name: String,
+) -> Result<ReleaseSummary> {
+    let release = state
+        .spawn(move |app| app.create_release(&project_id, name))
+        .await?;
+    Ok(ReleaseSummary::from(&release))
+}
+
+#[tauri::command]
+async fn list_releases(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<Vec<ReleaseSummary>> {
+    state.spawn(move |app| app.list_releases(&project_id)).await
+}
+
+#[tauri::command]
+async fn load_release(
+    state: State<'_, AppState>,
+    project_id: String,
+    release_id: String,
+) -> Result<Release> {
     state
-        .project_manager
-        .save(&project)
-        .map_err(|e| format!("Failed to save project: {}", e))
+        .spawn(move |app| app.load_release(&project_id, &release_id))
+        .await
 }
 
 #[tauri::command]
-fn list_projects(
-    state: State<AppState>,
-) -> std::result::Result<Vec<tarsius_core::ProjectDto>, String> {
-    let projects = state
-        .project_manager
-        .list()
-        .map_err(|e| format!("Failed to list projects: {}", e))?;
-    Ok(projects.into_iter().map(Into::into).collect())
+async fn diff_releases(
+    state: State<'_, AppState>,
+    project_id: String,
+    from: String,
+    to: String,
+) -> Result<ReleaseDiff> {
+    state
+        .spawn(move |app| app.diff_releases(&project_id, &from, &to))
+        .await
+}
+
+/// Starts a new project from a release.
+#[tauri::command]
+async fn fork_release(
+    state: State<'_, AppState>,
+    project_id: String,
+    release_id: String,
+    title: String,
+) -> Result<tarsius_core::ProjectDto> {
+    let project = state
+        .spawn(move |app| app.fork_release(&project_id, &release_id, title))
+        .await?;
+    Ok(project.into())
+}
+
+/// How the project's outline changed since a release.
+#[tauri::command]
+async fn outline_changes(
+    state: State<'_, AppState>,
+    project_id: String,
+    release_id: String,
+) -> Result<Vec<OutlineChange>> {
+    state
+        .spawn(move |app| app.outline_changes(&project_id, &release_id))
+        .await
+}
+
+/// Merges another project's outline into this one without saving it.
+#[tauri::command]
+async fn preview_merge(
+    state: State<'_, AppState>,
+    project_id: String,
+    other_id: String,
+    base_release_id: String,
+) -> Result<OutlineMerge> {
+    state
+        .spawn(move |app| app.preview_merge(&project_id, &other_id, &base_release_id))
+        .await
+}
+
+#[tauri::command]
+async fn merge_projects(
+    state: State<'_, AppState>,
+    project_id: String,
+    other_id: String,
+    base_release_id: String,
+) -> Result<OutlineMerge> {
+    state
+        .spawn(move |app| app.merge_projects(&project_id, &other_id, &base_release_id))
+        .await
+}
+
+#[tauri::command]
+async fn load_scratch(state: State<'_, AppState>, id: String) -> Result<tarsius_core::ScratchDto> {
+    let scratch = state.spawn(move |app| app.load_scratch(&id)).await?;
+    Ok(scratch.into())
+}
+
+/// Lists scratches, optionally keeping only those that are (`"used"`) or are
+/// not (`"unused"`) linked from any project outline.
+#[tauri::command]
+async fn list_scratches(
+    state: State<'_, AppState>,
+    usage: Option<String>,
+) -> Result<Vec<tarsius_core::ScratchDto>> {
+    let usage = usage
+        .map(|usage| usage.parse::<UsageFilter>())
+        .transpose()
+        .map_err(|e| CoreError::invalid("usage", e))?;
+    let scratches = state.spawn(move |app| app.list_scratches(usage)).await?;
+    Ok(scratches.into_iter().map(Into::into).collect())
+}
+
+/// A page of scratch summaries; open a scratch with `load_scratch` to get its
+/// content. `usage` is as for `list_scratches`.
+#[tauri::command]
+async fn list_scratch_summaries(
+    state: State<'_, AppState>,
+    usage: Option<String>,
+    query: Option<ListQuery>,
+) -> Result<Page<tarsius_core::ScratchSummaryDto>> {
+    let usage = usage
+        .map(|usage| usage.parse::<UsageFilter>())
+        .transpose()
+        .map_err(|e| CoreError::invalid("usage", e))?;
+    let page = state
+        .spawn(move |app| app.scratch_summaries(usage, &query.unwrap_or_default()))
+        .await?;
+    Ok(page.map(Into::into))
+}
+
+#[tauri::command]
+async fn delete_scratch(
+    state: State<'_, AppState>,
+    id: String,
+    policy: Option<String>,
+) -> Result<tarsius_core::DeleteReportDto> {
+    let policy = match policy {
+        Some(policy) => policy
+            .parse()
+            .map_err(|e| CoreError::invalid("policy", e))?,
+        None => DeletePolicy::Block,
+    };
+    let report = state
+        .spawn(move |app| app.delete_scratch(&id, policy))
+        .await?;
+    Ok(report.into())
+}
+
+#[tauri::command]
+async fn scratch_references(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<tarsius_core::ScratchReferenceDto>> {
+    let references = state.spawn(move |app| app.scratch_references(&id)).await?;
+    Ok(references.into_iter().map(Into::into).collect())
+}
+
+#[tauri::command]
+async fn list_scratch_versions(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<ScratchVersion>> {
+    state.spawn(move |app| app.scratch_versions(&id)).await
 }
 
 #[tauri::command]
-fn load_scratch(
-    state: State<AppState>,
+async fn diff_scratch_versions(
+    state: State<'_, AppState>,
     id: String,
-) -> std::result::Result<tarsius_core::ScratchDto, String> {
+    from: u32,
+    to: u32,
+) -> Result<VersionDiff> {
+    state
+        .spawn(move |app| app.diff_scratch_versions(&id, from, to))
+        .await
+}
+
+#[tauri::command]
+async fn restore_scratch_version(
+    state: State<'_, AppState>,
+    id: String,
+    version: u32,
+) -> Result<tarsius_core::ScratchDto> {
     let scratch = state
-        .scratch_manager
-        .load(&id)
-        .map_err(|e| format!("Failed to load scratch: {}", e))?;
+        .spawn(move |app| app.restore_scratch_version(&id, version))
+        .await?;
     Ok(scratch.into())
 }
 
 #[tauri::command]
-fn list_scratches(
-    state: State<AppState>,
-) -> std::result::Result<Vec<tarsius_core::ScratchDto>, String> {
-    let scratches = state
-        .scratch_manager
-        .list()
-        .map_err(|e| format!("Failed to list scratches: {}", e))?;
-    Ok(scratches.into_iter().map(Into::into).collect())
+async fn scratch_usages(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<tarsius_core::ScratchReferenceDto>> {
+    let usages = state.spawn(move |app| app.scratch_usages(&id)).await?;
+    Ok(usages.into_iter().map(Into::into).collect())
+}
+
+/// Copies a project with fresh outline node ids, and with `copyIncluded`
+/// the scratches it includes as well.
+#[tauri::command]
+async fn duplicate_project(
+    state: State<'_, AppState>,
+    id: String,
+    title: String,
+    copy_included: bool,
+) -> Result<tarsius_core::ProjectDto> {
+    let project = state
+        .spawn(move |app| app.duplicate_project(&id, title, copy_included))
+        .await?;
+    Ok(project.into())
+}
+
+#[tauri::command]
+async fn rename_project(
+    state: State<'_, AppState>,
+    id: String,
+    title: String,
+) -> Result<tarsius_core::ProjectDto> {
+    let project = state
+        .spawn(move |app| app.rename_project(&id, title))
+        .await?;
+    Ok(project.into())
+}
+
+#[tauri::command]
+async fn set_project_archived(
+    state: State<'_, AppState>,
+    id: String,
+    archived: bool,
+) -> Result<tarsius_core::ProjectDto> {
+    let project = state
+        .spawn(move |app| app.set_project_archived(&id, archived))
+        .await?;
+    Ok(project.into())
 }
 
+/// Moves the project to the trash.
 #[tauri::command]
-fn delete_scratch(state: State<AppState>, id: String) -> std::result::Result<(), String> {
+async fn delete_project(state: State<'_, AppState>, id: String) -> Result<()> {
+    state.spawn(move |app| app.delete_project(&id)).await
+}
+
+#[tauri::command]
+async fn list_templates(state: State<'_, AppState>) -> Result<Vec<tarsius_core::TemplateDto>> {
+    let templates = state.spawn(|app| app.list_templates()).await?;
+    Ok(templates.into_iter().map(Into::into).collect())
+}
+
+/// The built-in blueprints, then the ones saved in this workspace.
+#[tauri::command]
+async fn list_blueprints(state: State<'_, AppState>) -> Result<Vec<Blueprint>> {
+    state.spawn(|app| app.list_blueprints()).await
+}
+
+#[tauri::command]
+async fn create_project_from_blueprint(
+    state: State<'_, AppState>,
+    blueprint_id: String,
+    title: String,
+) -> Result<tarsius_core::ProjectDto> {
+    let project = state
+        .spawn(move |app| app.create_project_from_blueprint(&blueprint_id, title))
+        .await?;
+    Ok(project.into())
+}
+
+/// Saves the project's outline, template and settings as a blueprint.
+#[tauri::command]
+async fn save_project_as_blueprint(
+    state: State<'_, AppState>,
+    project_id: String,
+    name: String,
+    description: Option<String>,
+) -> Result<Blueprint> {
     state
-        .scratch_manager
-        .delete(&id)
-        .map_err(|e| format!("Failed to delete scratch: {}", e))
+        .spawn(move |app| {
+            app.save_project_as_blueprint(&project_id, name, description.unwrap_or_default())
+        })
+        .await
 }
 
+#[tauri::command]
+async fn delete_blueprint(state: State<'_, AppState>, id: String) -> Result<()> {
+    state.spawn(move |app| app.delete_blueprint(&id)).await
+}
+
+#[tauri::command]
+async fn search(state: State<'_, AppState>, query: String) -> Result<Vec<tarsius_core::SearchHit>> {
+    state.spawn(move |app| app.search(&query)).await
+}
 
+#[tauri::command]
+async fn list_trash(state: State<'_, AppState>) -> Result<Vec<tarsius_core::TrashEntryDto>> {
+    let entries = state.spawn(|app| app.list_trash()).await?;
+    Ok(entries.into_iter().map(Into::into).collect())
+}
+
+#[tauri::command]
+async fn restore_from_trash(
+    state: State<'_, AppState>,
+    kind: String,
+    id: String,
+) -> Result<tarsius_core::TrashEntryDto> {
+    let kind: TrashKind = kind.parse().map_err(|e| CoreError::invalid("kind", e))?;
+    let entry = state
+        .spawn(move |app| app.restore_from_trash(kind, &id))
+        .await?;
+    Ok(entry.into())
+}
+
+#[tauri::command]
+async fn purge_from_trash(state: State<'_, AppState>, kind: String, id: String) -> Result<()> {
+    let kind: TrashKind = kind.parse().map_err(|e| CoreError::invalid("kind", e))?;
+    state
+        .spawn(move |app| app.purge_from_trash(kind, &id))
+        .await
+}
+
+#[tauri::command]
+async fn workspace_issues(
+    state: State<'_, AppState>,
+) -> Result<Vec<tarsius_storage::WorkspaceIssue>> {
+    state.spawn(|app| app.workspace_issues()).await
+}
+
+#[tauri::command]
+async fn restore_quarantined(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<tarsius_storage::WorkspaceIssue> {
+    state.spawn(move |app| app.restore_quarantined(&id)).await
+}
+
+#[tauri::command]
+async fn check_workspace(state: State<'_, AppState>) -> Result<Vec<tarsius_storage::Finding>> {
+    state.spawn(|app| app.check_workspace()).await
+}
+
+/// Fixes the given findings, as returned by `check_workspace`, and returns
+/// the findings left. Pass `confirmed` only once the user has answered yes
+/// to the `confirm` question of every finding given.
+#[tauri::command]
+async fn repair_workspace(
+    state: State<'_, AppState>,
+    findings: Vec<tarsius_storage::Finding>,
+    confirmed: bool,
+) -> Result<Vec<tarsius_storage::Finding>> {
+    let problems: Vec<_> = findings.into_iter().map(|f| f.problem).collect();
+    state
+        .spawn(move |app| app.repair_workspace(&problems, confirmed))
+        .await
+}
+
+#[tauri::command]
+fn cache_metrics(state: State<AppState>) -> tarsius_app::CacheReport {
+    state.cache_metrics()
+}
+
+#[tauri::command]
+async fn export_workspace(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<tarsius_storage::BackupManifest> {
+    state
+        .spawn(move |app| app.export_workspace(std::path::Path::new(&path)))
+        .await
+}
+
+/// `policy` decides what happens to items whose id already exists:
+/// `"Skip"`, `"Overwrite"` or `"Rename"`.
+#[tauri::command]
+async fn import_workspace(
+    state: State<'_, AppState>,
+    path: String,
+    policy: String,
+) -> Result<tarsius_storage::ImportReport> {
+    let policy: CollisionPolicy = policy
+        .parse()
+        .map_err(|e| CoreError::invalid("policy", e))?;
+    state
+        .spawn(move |app| app.import_workspace(std::path::Path::new(&path), policy))
+        .await
+}
+
+#[tauri::command]
+async fn list_backups(state: State<'_, AppState>) -> Result<Vec<tarsius_storage::BackupInfo>> {
+    state.spawn(|app| app.list_backups()).await
+}
+
+/// Records unsaved editor content; call it on every change, ahead of the
+/// debounced `update_scratch`.
+#[tauri::command]
+async fn journal_scratch(
+    state: State<'_, AppState>,
+    id: String,
+    draft: tarsius_core::ScratchDraft,
+    edited_at: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    state
+        .spawn(move |app| app.record_scratch_draft(&id, draft, edited_at))
+        .await
+}
+
+#[tauri::command]
+async fn journal_project(
+    state: State<'_, AppState>,
+    project_dto: tarsius_core::ProjectDto,
+) -> Result<()> {
+    state
+        .spawn(move |app| app.record_project_draft(project_dto))
+        .await
+}
+
+#[tauri::command]
+async fn pending_recoveries(
+    state: State<'_, AppState>,
+) -> Result<Vec<tarsius_app::PendingRecovery>> {
+    state.spawn(|app| app.pending_recoveries()).await
+}
+
+/// `kind` is `"Scratch"` or `"Project"`.
+#[tauri::command]
+async fn recover_draft(state: State<'_, AppState>, kind: String, id: String) -> Result<()> {
+    let kind: JournalKind = kind.parse().map_err(|e| CoreError::invalid("kind", e))?;
+    state.spawn(move |app| app.recover_draft(kind, &id)).await
+}
+
+#[tauri::command]
+async fn discard_draft(state: State<'_, AppState>, kind: String, id: String) -> Result<()> {
+    let kind: JournalKind = kind.parse().map_err(|e| CoreError::invalid("kind", e))?;
+    state
+        .spawn(move |app| app.discard_recovery(kind, &id))
+        .await
+}